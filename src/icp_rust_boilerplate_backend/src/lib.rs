@@ -1,12 +1,72 @@
 #[macro_use]
 extern crate serde;
 use candid::{Decode, Encode};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
 use ic_cdk::api::caller;
 use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
 use ic_stable_structures::{BoundedStorable, Cell, DefaultMemoryImpl, StableBTreeMap, Storable};
 use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
 use std::{borrow::Cow, cell::RefCell};
 
+// Length of the slot a match with a kickoff time occupies
+const MATCH_SLOT_MINUTES: i64 = 120;
+
+// Limits on the size of an official's availability calendar
+const MAX_AVAILABILITY_WINDOWS: usize = 100;
+const MAX_RECURRING_UNAVAILABILITY: usize = 20;
+
+// Limits keeping a referee's certifications within the referee's storage size
+const MAX_ISSUING_BODY_LENGTH: usize = 100;
+const MAX_REVOCATION_REASON_LENGTH: usize = 100;
+
+// Limits on the size of a match report
+const MAX_REPORT_ENTRIES: usize = 50;
+const MAX_REPORT_NOTES_LENGTH: usize = 2000;
+const MAX_REPORT_DESCRIPTION_LENGTH: usize = 200;
+
+// Longest notes a match result can carry, keeping the match within its storage size
+const MAX_RESULT_NOTES_LENGTH: usize = 500;
+
+// Limits keeping a tournament within its storage size
+const MAX_TOURNAMENT_TEAMS: usize = 64;
+const MAX_TOURNAMENT_NAME_LENGTH: usize = 100;
+
+// Longest reason given for rejecting a tournament entry, keeping the entry within its storage size
+const MAX_REJECTION_REASON_LENGTH: usize = 200;
+
+// Limits on the transfer windows and exam blackouts of a season, keeping it within its storage size
+const MAX_SEASON_PERIODS: usize = 8;
+const MAX_SEASON_PERIOD_NAME_LENGTH: usize = 100;
+
+// Limit on the divisions of a league
+const MAX_LEAGUE_DIVISIONS: usize = 8;
+
+// Limits keeping a league's rules within their storage size
+const MAX_RULES_UNIVERSITIES: usize = 32;
+const MAX_UNIVERSITY_NAME_LENGTH: usize = 100;
+
+// Limits keeping an overall championship within its storage size
+const MAX_CHAMPIONSHIP_COMPETITIONS: usize = 32;
+const MAX_PLACING_POINTS: usize = 16;
+
+// Longest result confirmation deadline a league can set, in hours
+const MAX_RESULT_CONFIRMATION_HOURS: u32 = 24 * 30;
+
+// Limits keeping a league within its storage size
+const MAX_LEAGUE_TEAMS: usize = 64;
+const MAX_LEAGUE_TOURNAMENTS: usize = 32;
+
+// Size of one slot of a bracket drawn as SVG
+const BRACKET_SVG_SLOT_WIDTH: u32 = 200;
+const BRACKET_SVG_SLOT_HEIGHT: u32 = 50;
+
+// Most steps spent searching for Swiss pairings without rematches before allowing them
+const MAX_SWISS_PAIRING_STEPS: u32 = 10_000;
+
+// Most steps spent searching for a group draw that keeps clashing teams apart
+const MAX_DRAW_STEPS: u32 = 100_000;
+
 // Memory Management
 type Memory = VirtualMemory<DefaultMemoryImpl>;
 type IdCell = Cell<u64, Memory>;
@@ -29,12 +89,62 @@ pub enum Error {
     PaymentCompleted { msg: String },
 }
 
+// Backend used to pay officials, reporting PaymentCompleted with the payment reference or PaymentFailed with the reason
+pub trait PaymentBackend {
+    fn pay(&self, entry: &PaymentEntry, payee: &Referee) -> Error;
+}
+
+// Install the backend officials are paid through; until one is installed, approved payments are not processed
+pub fn install_payment_backend(backend: Box<dyn PaymentBackend>) {
+    PAYMENT_BACKEND.with(|current| *current.borrow_mut() = Some(backend));
+}
+
+// Backend for officials paid outside the canister, recording each payout with a reference for the finance office
+pub struct ManualPaymentBackend;
+
+impl PaymentBackend for ManualPaymentBackend {
+    fn pay(&self, entry: &PaymentEntry, _payee: &Referee) -> Error {
+        Error::PaymentCompleted {
+            msg: format!("MANUAL-{}", entry.id),
+        }
+    }
+}
+
+// Staging backend that moves no funds, completing every payment up to its limit and declining larger ones
+pub struct SandboxPaymentBackend {
+    payout_limit: u64,
+}
+
+impl PaymentBackend for SandboxPaymentBackend {
+    fn pay(&self, entry: &PaymentEntry, payee: &Referee) -> Error {
+        if entry.amount > self.payout_limit {
+            Error::PaymentFailed {
+                msg: format!(
+                    "Payment of {} to {} exceeds the sandbox limit of {}",
+                    entry.amount, payee.name, self.payout_limit
+                ),
+            }
+        } else {
+            Error::PaymentCompleted {
+                msg: format!("SANDBOX-{}", entry.id),
+            }
+        }
+    }
+}
+
 // Thread-local storage
 thread_local! {
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
         MemoryManager::init(DefaultMemoryImpl::default())
     );
 
+    static PAYMENT_BACKEND: RefCell<Option<Box<dyn PaymentBackend>>> = RefCell::new(None);
+
+    static PAYMENT_SETTINGS: RefCell<Cell<PaymentSettings, Memory>> = RefCell::new(
+        Cell::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(35))), PaymentSettings::default())
+            .expect("Cannot create the payment settings")
+    );
+
     static ID_COUNTER: RefCell<IdCell> = RefCell::new(
         IdCell::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(0))), 0)
             .expect("Cannot create a counter")
@@ -50,26 +160,164 @@ thread_local! {
             MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(2))))
     );
 
+    // Memory 3 held matches before they moved to memory 38, and is only read to migrate them
     static MATCHES_STORAGE: RefCell<StableBTreeMap<u64, Match, Memory>> = RefCell::new(
         StableBTreeMap::init(
-            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(3))))
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(38))))
     );
 
+    // Memory 4 held referees before they moved to memory 36, and is only read to migrate them
     static REFEREE_STORAGE: RefCell<StableBTreeMap<u64, Referee, Memory>> = RefCell::new(
         StableBTreeMap::init(
-            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(4))))
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(36))))
     );
 
-    static TOURNAMENTS_STORAGE: RefCell<StableBTreeMap<u64, Tournament, Memory>> = RefCell::new(
+    // Memory 5 held tournaments before they moved to memory 34, and is only read to migrate them
+    // Memory 6 held leagues before they moved to memory 37, and is only read to migrate them
+    static LEAGUES_STORAGE: RefCell<StableBTreeMap<u64, League, Memory>> = RefCell::new(
         StableBTreeMap::init(
-            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(5))))
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(37))))
     );
 
-    static LEAGUES_STORAGE: RefCell<StableBTreeMap<u64, League, Memory>> = RefCell::new(
+    static ASSIGNMENTS_STORAGE: RefCell<StableBTreeMap<u64, RefereeAssignment, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(7))))
+    );
+
+    static RATINGS_STORAGE: RefCell<StableBTreeMap<u64, RefereeRating, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(8))))
+    );
+
+    static AVAILABILITY_STORAGE: RefCell<StableBTreeMap<u64, OfficialAvailability, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(9))))
+    );
+
+    static REPORTS_STORAGE: RefCell<StableBTreeMap<u64, MatchReport, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(10))))
+    );
+
+    static CARDS_STORAGE: RefCell<StableBTreeMap<u64, Card, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(11))))
+    );
+
+    static DISCIPLINARY_RULES_STORAGE: RefCell<StableBTreeMap<u64, DisciplinaryRule, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(12))))
+    );
+
+    static SUSPENSIONS_STORAGE: RefCell<StableBTreeMap<u64, Suspension, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(13))))
+    );
+
+    static LINEUPS_STORAGE: RefCell<StableBTreeMap<u64, Lineup, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(14))))
+    );
+
+    static OFFICIATING_HISTORY_STORAGE: RefCell<StableBTreeMap<OfficiatingKey, OfficiatingRecord, Memory>> = RefCell::new(
         StableBTreeMap::init(
-            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(6))))
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(15))))
     );
 
+    static PAYMENTS_STORAGE: RefCell<StableBTreeMap<u64, PaymentEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(16))))
+    );
+
+    static FEE_RATES_STORAGE: RefCell<StableBTreeMap<u64, OfficialFeeRate, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(17))))
+    );
+
+    static BRACKETS_STORAGE: RefCell<StableBTreeMap<u64, Bracket, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(18))))
+    );
+
+    static GROUP_STAGES_STORAGE: RefCell<StableBTreeMap<u64, GroupStage, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(19))))
+    );
+
+    static DOUBLE_ELIMINATION_STORAGE: RefCell<StableBTreeMap<u64, DoubleEliminationBracket, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(20))))
+    );
+
+    static SWISS_STORAGE: RefCell<StableBTreeMap<u64, SwissTournament, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(21))))
+    );
+
+    static REGISTRATIONS_STORAGE: RefCell<StableBTreeMap<u64, TournamentRegistration, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(22))))
+    );
+
+    static TOURNAMENT_ENTRIES_STORAGE: RefCell<StableBTreeMap<u64, TournamentEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(23))))
+    );
+
+    static TIES_STORAGE: RefCell<StableBTreeMap<u64, TwoLeggedTie, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(24))))
+    );
+
+    static GROUP_DRAWS_STORAGE: RefCell<StableBTreeMap<u64, GroupDraw, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(25))))
+    );
+
+    static MATCH_INDEX_STORAGE: RefCell<StableBTreeMap<MatchIndexKey, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(26))))
+    );
+
+    static SEASONS_STORAGE: RefCell<StableBTreeMap<u64, Season, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(27))))
+    );
+
+    static DIVISIONS_STORAGE: RefCell<StableBTreeMap<u64, Division, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(28))))
+    );
+
+    static SEASON_DIVISIONS_STORAGE: RefCell<StableBTreeMap<u64, SeasonDivisions, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(29))))
+    );
+
+    static LEAGUE_RULES_STORAGE: RefCell<StableBTreeMap<u64, LeagueRules, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(30))))
+    );
+
+    static APPOINTMENTS_STORAGE: RefCell<StableBTreeMap<u64, OfficialAppointment, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(31))))
+    );
+
+    static CHAMPIONSHIPS_STORAGE: RefCell<StableBTreeMap<u64, Championship, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(32))))
+    );
+
+    static CERTIFICATION_HISTORY_STORAGE: RefCell<StableBTreeMap<CertificationKey, Certification, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(33))))
+    );
+
+    static TOURNAMENTS_STORAGE: RefCell<StableBTreeMap<u64, Tournament, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(34))))
+    );
 }
 
 // Implement Storable for User
@@ -115,8 +363,9 @@ impl Storable for Match {
     }
 }
 
+// A match carries both of its teams, each up to the 1024 bytes a team is stored in, plus its result and schedule
 impl BoundedStorable for Match {
-    const MAX_SIZE: u32 = 1024;
+    const MAX_SIZE: u32 = 4096;
     const IS_FIXED_SIZE: bool = false;
 }
 
@@ -132,7 +381,7 @@ impl Storable for Referee {
 }
 
 impl BoundedStorable for Referee {
-    const MAX_SIZE: u32 = 1024;
+    const MAX_SIZE: u32 = 4096;
     const IS_FIXED_SIZE: bool = false;
 }
 
@@ -148,6 +397,26 @@ impl Storable for Tournament {
 }
 
 impl BoundedStorable for Tournament {
+    const MAX_SIZE: u32 = 4096;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for LegacyStored, with the bound the original storages were created with
+impl<T: candid::CandidType + serde::Serialize + serde::de::DeserializeOwned> Storable
+    for LegacyStored<T>
+{
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(&self.0).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        LegacyStored(Decode!(bytes.as_ref(), T).unwrap())
+    }
+}
+
+impl<T: candid::CandidType + serde::Serialize + serde::de::DeserializeOwned> BoundedStorable
+    for LegacyStored<T>
+{
     const MAX_SIZE: u32 = 1024;
     const IS_FIXED_SIZE: bool = false;
 }
@@ -164,541 +433,10305 @@ impl Storable for League {
 }
 
 impl BoundedStorable for League {
-    const MAX_SIZE: u32 = 1024;
+    const MAX_SIZE: u32 = 4096;
     const IS_FIXED_SIZE: bool = false;
 }
 
-// Helper Functions
-
-// Generates a unique identifier for objects
-fn generate_uuid() -> u64 {
-    let id = ID_COUNTER.with(|counter| {
-        let current_id = *counter.borrow().get();
-        let _ = counter.borrow_mut().set(current_id + 1);
-        current_id
-    });
+// Implement Storable for RefereeAssignment
+impl Storable for RefereeAssignment {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
 
-    id
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
 }
 
-// Validate email format
-fn validate_email_format(email: &str) -> Result<(), String> {
-    let email_regex = Regex::new(r"^\S+@\S+\.\S+$").unwrap();
-    if !email_regex.is_match(email) {
-        Err("Invalid email format".to_string())
-    } else {
-        Ok(())
-    }
+impl BoundedStorable for RefereeAssignment {
+    const MAX_SIZE: u32 = 256;
+    const IS_FIXED_SIZE: bool = false;
 }
 
-// Validate email uniqueness
-fn validate_email_uniqueness(email: &str) -> Result<(), String> {
-    let email_exists =
-        USERS_STORAGE.with(|storage| storage.borrow().iter().any(|(_, user)| user.email == email));
+// Implement Storable for RefereeRating
+impl Storable for RefereeRating {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
 
-    if email_exists {
-        Err("User with this email already exists".to_string())
-    } else {
-        Ok(())
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
     }
 }
 
-/*
-Register a new User
-This function takes a RegisterUserPayload as input and returns a Result containing either a User or a String error message.
-It generates a unique ID for the new user, creates a User object, and stores it in the USERS_STORAGE.
-Performs validation checks on input data and handles potential registration errors.
-*/
-#[ic_cdk::update]
-fn register_user(payload: RegisterUserPayload) -> Result<User, String> {
-    // Validate the user payload to ensure all required fields are present
-    if payload.name.is_empty() || payload.email.is_empty() || payload.address.is_empty() {
-        return Err("Name, email, and address are required fields".to_string());
+impl BoundedStorable for RefereeRating {
+    const MAX_SIZE: u32 = 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for OfficialAvailability
+impl Storable for OfficialAvailability {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
     }
 
-    // Validate email format
-    validate_email_format(&payload.email)?;
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
 
-    // Check if user with this email already exists
-    validate_email_uniqueness(&payload.email)?;
+impl BoundedStorable for OfficialAvailability {
+    const MAX_SIZE: u32 = 32 * 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
 
-    // Generate unique ID
-    let id = generate_uuid();
+// Implement Storable for MatchReport
+impl Storable for MatchReport {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
 
-    // Create user object
-    let user = User {
-        id,
-        owner: caller(),
-        name: payload.name,
-        email: payload.email,
-        address: payload.address,
-        role: payload.role,
-    };
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
 
-    // Store user in storage
-    USERS_STORAGE.with(|users| {
-        users.borrow_mut().insert(id, user.clone());
-        Ok(user)
-    })
+impl BoundedStorable for MatchReport {
+    const MAX_SIZE: u32 = 64 * 1024;
+    const IS_FIXED_SIZE: bool = false;
 }
 
-//  Function to update a user's details
-#[ic_cdk::update]
-fn update_user(payload: UpdateUserPayload) -> Result<User, String> {
-    // Ensure the user exists
-    if !USERS_STORAGE.with(|storage| storage.borrow().contains_key(&payload.id)) {
-        return Err("User not found".to_string());
+// Implement Storable for Card
+impl Storable for Card {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
     }
 
-    // Validate email format
-    validate_email_format(&payload.email)?;
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
 
-    // Check if user with this email already exists
-    let email_exists = USERS_STORAGE.with(|storage| {
-        storage
-            .borrow()
-            .iter()
-            .any(|(_, user)| user.email == payload.email && user.id != payload.id)
-    });
+impl BoundedStorable for Card {
+    const MAX_SIZE: u32 = 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
 
-    if email_exists {
-        return Err("User with this email already exists".to_string());
+// Implement Storable for DisciplinaryRule
+impl Storable for DisciplinaryRule {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
     }
 
-    let user = User {
-        id: payload.id,
-        owner: caller(),
-        name: payload.name,
-        email: payload.email,
-        address: payload.address,
-        role: payload.role,
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for DisciplinaryRule {
+    const MAX_SIZE: u32 = 256;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for Suspension
+impl Storable for Suspension {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for Suspension {
+    const MAX_SIZE: u32 = 2048;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for Lineup
+impl Storable for Lineup {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for Lineup {
+    const MAX_SIZE: u32 = 4096;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for OfficiatingRecord
+impl Storable for OfficiatingRecord {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for OfficiatingRecord {
+    const MAX_SIZE: u32 = 512;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for LegacyRefereeHistory
+impl Storable for LegacyRefereeHistory {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for LegacyRefereeHistory {
+    const MAX_SIZE: u32 = 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for LegacyMatchReport
+impl Storable for LegacyMatchReport {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for LegacyMatchReport {
+    const MAX_SIZE: u32 = 64 * 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for OfficiatingKey as big-endian IDs, so keys sort by referee and then by match
+impl Storable for OfficiatingKey {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        let mut bytes = self.referee_id.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.match_id.to_be_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        let (referee_id, match_id) = bytes.split_at(8);
+        OfficiatingKey {
+            referee_id: u64::from_be_bytes(referee_id.try_into().unwrap()),
+            match_id: u64::from_be_bytes(match_id.try_into().unwrap()),
+        }
+    }
+}
+
+impl BoundedStorable for OfficiatingKey {
+    const MAX_SIZE: u32 = 16;
+    const IS_FIXED_SIZE: bool = true;
+}
+
+// Implement Storable for CertificationKey as big-endian IDs, so keys sort by referee and then by certification
+impl Storable for CertificationKey {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        let mut bytes = self.referee_id.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.certification_id.to_be_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        let (referee_id, certification_id) = bytes.split_at(8);
+        CertificationKey {
+            referee_id: u64::from_be_bytes(referee_id.try_into().unwrap()),
+            certification_id: u64::from_be_bytes(certification_id.try_into().unwrap()),
+        }
+    }
+}
+
+impl BoundedStorable for CertificationKey {
+    const MAX_SIZE: u32 = 16;
+    const IS_FIXED_SIZE: bool = true;
+}
+// Implement Storable for Certification
+impl Storable for Certification {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for Certification {
+    const MAX_SIZE: u32 = 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for MatchIndexKey as big-endian values, so keys sort by competition, round and match
+impl Storable for MatchIndexKey {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        let mut bytes = self.competition_id.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.round.to_be_bytes());
+        bytes.extend_from_slice(&self.match_id.to_be_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        let (competition_id, rest) = bytes.split_at(8);
+        let (round, match_id) = rest.split_at(4);
+        MatchIndexKey {
+            competition_id: u64::from_be_bytes(competition_id.try_into().unwrap()),
+            round: u32::from_be_bytes(round.try_into().unwrap()),
+            match_id: u64::from_be_bytes(match_id.try_into().unwrap()),
+        }
+    }
+}
+
+impl BoundedStorable for MatchIndexKey {
+    const MAX_SIZE: u32 = 20;
+    const IS_FIXED_SIZE: bool = true;
+}
+
+// Implement Storable for PaymentEntry
+impl Storable for PaymentEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for PaymentEntry {
+    const MAX_SIZE: u32 = 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for PaymentSettings
+impl Storable for PaymentSettings {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+// Implement Storable for OfficialFeeRate
+impl Storable for OfficialFeeRate {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for OfficialFeeRate {
+    const MAX_SIZE: u32 = 256;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for Bracket
+impl Storable for Bracket {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for Bracket {
+    const MAX_SIZE: u32 = 16384;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for GroupStage
+impl Storable for GroupStage {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for GroupStage {
+    const MAX_SIZE: u32 = 4096;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for DoubleEliminationBracket
+impl Storable for DoubleEliminationBracket {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for DoubleEliminationBracket {
+    const MAX_SIZE: u32 = 32768;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for SwissTournament
+impl Storable for SwissTournament {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for SwissTournament {
+    const MAX_SIZE: u32 = 4096;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for TournamentRegistration
+impl Storable for TournamentRegistration {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for TournamentRegistration {
+    const MAX_SIZE: u32 = 4096;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for TournamentEntry
+impl Storable for TournamentEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for TournamentEntry {
+    const MAX_SIZE: u32 = 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for TwoLeggedTie
+impl Storable for TwoLeggedTie {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for TwoLeggedTie {
+    const MAX_SIZE: u32 = 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for GroupDraw
+impl Storable for GroupDraw {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for GroupDraw {
+    const MAX_SIZE: u32 = 8192;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for Season
+impl Storable for Season {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for Season {
+    const MAX_SIZE: u32 = 8192;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for Division
+impl Storable for Division {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for Division {
+    const MAX_SIZE: u32 = 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for SeasonDivisions
+impl Storable for SeasonDivisions {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for SeasonDivisions {
+    const MAX_SIZE: u32 = 16384;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for LeagueRules
+impl Storable for LeagueRules {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for LeagueRules {
+    const MAX_SIZE: u32 = 8192;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for OfficialAppointment
+impl Storable for OfficialAppointment {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for OfficialAppointment {
+    const MAX_SIZE: u32 = 1024;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Implement Storable for Championship
+impl Storable for Championship {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+impl BoundedStorable for Championship {
+    const MAX_SIZE: u32 = 8192;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// Helper Functions
+
+// Generates a unique identifier for objects
+fn generate_uuid() -> u64 {
+    let id = ID_COUNTER.with(|counter| {
+        let current_id = *counter.borrow().get();
+        let _ = counter.borrow_mut().set(current_id + 1);
+        current_id
+    });
+
+    id
+}
+
+// Validate email format
+fn validate_email_format(email: &str) -> Result<(), String> {
+    let email_regex = Regex::new(r"^\S+@\S+\.\S+$").unwrap();
+    if !email_regex.is_match(email) {
+        Err("Invalid email format".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+// Validate email uniqueness
+fn validate_email_uniqueness(email: &str) -> Result<(), String> {
+    let email_exists =
+        USERS_STORAGE.with(|storage| storage.borrow().iter().any(|(_, user)| user.email == email));
+
+    if email_exists {
+        Err("User with this email already exists".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+// Fetch the registered user making the call
+fn get_caller_user() -> Result<User, String> {
+    let caller = caller();
+    USERS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .find(|(_, user)| user.owner == caller)
+            .map(|(_, user)| user)
+            .ok_or_else(|| format!("User not found for caller: {}", caller.to_text()))
+    })
+}
+
+// Ensure the caller is a registered user holding one of the given roles
+fn ensure_caller_role(roles: &[UserRole]) -> Result<User, String> {
+    let user = get_caller_user()?;
+    if roles.contains(&user.role) {
+        Ok(user)
+    } else {
+        Err(format!(
+            "Users with the {:?} role are not allowed to perform this action",
+            user.role
+        ))
+    }
+}
+
+/*
+Check that a role may be given out by whoever is granting it
+Administrators and league officials can only be appointed by an existing administrator or by a controller of the
+canister, which is how the first administrator is registered. Every other role is open to anyone.
+*/
+fn ensure_role_grant_allowed(
+    role: UserRole,
+    granter: Option<&User>,
+    granter_is_controller: bool,
+) -> Result<(), String> {
+    let privileged = matches!(role, UserRole::Administrator | UserRole::LeagueOfficial);
+    let granter_is_admin = granter.is_some_and(|user| user.role == UserRole::Administrator);
+    if !privileged || granter_is_admin || granter_is_controller {
+        Ok(())
+    } else {
+        Err(format!(
+            "Only an administrator can grant the {:?} role",
+            role
+        ))
+    }
+}
+
+/*
+Check whether a user may run competitions of a league and sport
+Administrators may run any competition. League officials need an active appointment covering it: an appointment
+to a league covers that league, one to a sport covers every competition of the sport, and one to both covers the
+league only when it plays that sport. Competitions outside any league are covered by sport appointments alone.
+*/
+fn is_competition_official(user: &User, league_id: Option<u64>, sport_type: SportType) -> bool {
+    if user.role == UserRole::Administrator {
+        return true;
+    }
+    if user.role != UserRole::LeagueOfficial {
+        return false;
+    }
+
+    APPOINTMENTS_STORAGE.with(|storage| {
+        storage.borrow().iter().any(|(_, appointment)| {
+            appointment.user_id == user.id
+                && appointment.revoked_at.is_none()
+                && (appointment.sport_type.is_none() || appointment.sport_type == Some(sport_type))
+                && (appointment.league_id.is_none() || appointment.league_id == league_id)
+        })
+    })
+}
+
+// Ensure the caller may run competitions of a league and sport
+fn ensure_competition_official(
+    league_id: Option<u64>,
+    sport_type: SportType,
+) -> Result<User, String> {
+    let user = ensure_caller_role(&[UserRole::Administrator, UserRole::LeagueOfficial])?;
+    if is_competition_official(&user, league_id, sport_type) {
+        Ok(user)
+    } else {
+        Err(format!(
+            "You are not appointed to run this {:?} competition",
+            sport_type
+        ))
+    }
+}
+
+// Ensure the caller may run a league
+fn ensure_league_official(league: &League) -> Result<User, String> {
+    ensure_competition_official(Some(league.id), league.sport_type)
+}
+
+// Ensure the caller may run a tournament
+fn ensure_tournament_official(tournament: &Tournament) -> Result<User, String> {
+    ensure_competition_official(tournament_league_id(tournament.id), tournament.sport_type)
+}
+
+// Ensure the caller may run the competition a match is played in
+fn ensure_match_official(match_obj: &Match) -> Result<User, String> {
+    ensure_competition_official(match_obj.league_id, match_obj.sport_type)
+}
+
+// Trim a university name, treating blank names as unaffiliated
+fn normalize_university(university: Option<String>) -> Option<String> {
+    university
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+// Parse a date in the format YYYY-MM-DD
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date {}, expected the format YYYY-MM-DD", date))
+}
+
+// Parse a time of day in the format HH:MM
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|_| format!("Invalid time {}, expected the format HH:MM", time))
+}
+
+// Parse a date and time in the format YYYY-MM-DD HH:MM
+fn parse_date_time(date_time: &str) -> Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(date_time, "%Y-%m-%d %H:%M").map_err(|_| {
+        format!(
+            "Invalid date and time {}, expected the format YYYY-MM-DD HH:MM",
+            date_time
+        )
+    })
+}
+
+// Get the time range a match slot occupies; without a kickoff time the slot is the whole day
+fn slot_range(
+    date: &str,
+    kickoff_time: &Option<String>,
+) -> Result<(NaiveDateTime, NaiveDateTime), String> {
+    let date = parse_date(date)?;
+    match kickoff_time {
+        Some(kickoff_time) => {
+            let start = date.and_time(parse_time(kickoff_time)?);
+            Ok((start, start + Duration::minutes(MATCH_SLOT_MINUTES)))
+        }
+        None => {
+            let start = date.and_time(NaiveTime::MIN);
+            Ok((start, start + Duration::days(1)))
+        }
+    }
+}
+
+// Get the current date and time from the IC system time
+fn now() -> NaiveDateTime {
+    DateTime::from_timestamp((ic_cdk::api::time() / 1_000_000_000) as i64, 0)
+        .map(|now| now.naive_utc())
+        .unwrap_or_default()
+}
+
+// Get the current date from the IC system time
+fn today() -> NaiveDate {
+    now().date()
+}
+
+// Derive the season label (the calendar year) from a YYYY-MM-DD date
+fn season_from_date(date: &str) -> String {
+    date.split('-').next().unwrap_or_default().to_string()
+}
+
+/*
+Register a new User
+This function takes a RegisterUserPayload as input and returns a Result containing either a User or a String error message.
+It generates a unique ID for the new user, creates a User object, and stores it in the USERS_STORAGE.
+Performs validation checks on input data and handles potential registration errors.
+*/
+#[ic_cdk::update]
+fn register_user(payload: RegisterUserPayload) -> Result<User, String> {
+    // Validate the user payload to ensure all required fields are present
+    if payload.name.is_empty() || payload.email.is_empty() || payload.address.is_empty() {
+        return Err("Name, email, and address are required fields".to_string());
+    }
+
+    // Validate email format
+    validate_email_format(&payload.email)?;
+
+    // Check if user with this email already exists
+    validate_email_uniqueness(&payload.email)?;
+
+    // Privileged roles cannot be self-assigned
+    ensure_role_grant_allowed(payload.role, None, ic_cdk::api::is_controller(&caller()))?;
+
+    // Generate unique ID
+    let id = generate_uuid();
+
+    // Create user object
+    let user = User {
+        id,
+        owner: caller(),
+        name: payload.name,
+        email: payload.email,
+        address: payload.address,
+        role: payload.role,
+    };
+
+    // Store user in storage
+    USERS_STORAGE.with(|users| {
+        users.borrow_mut().insert(id, user.clone());
+        Ok(user)
+    })
+}
+
+//  Function to update a user's details
+#[ic_cdk::update]
+fn update_user(payload: UpdateUserPayload) -> Result<User, String> {
+    // Ensure the user exists
+    let existing = USERS_STORAGE
+        .with(|storage| storage.borrow().get(&payload.id))
+        .ok_or_else(|| "User not found".to_string())?;
+
+    // Only the user themselves or an administrator may change the record
+    let caller_user = get_caller_user().ok();
+    let caller_is_admin = caller_user
+        .as_ref()
+        .is_some_and(|user| user.role == UserRole::Administrator);
+    if existing.owner != caller() && !caller_is_admin {
+        return Err("Only the user or an administrator can update this user".to_string());
+    }
+
+    // A role change is a grant by the caller
+    if payload.role != existing.role {
+        ensure_role_grant_allowed(
+            payload.role,
+            caller_user.as_ref(),
+            ic_cdk::api::is_controller(&caller()),
+        )?;
+    }
+
+    // Validate email format
+    validate_email_format(&payload.email)?;
+
+    // Check if user with this email already exists
+    let email_exists = USERS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .any(|(_, user)| user.email == payload.email && user.id != payload.id)
+    });
+
+    if email_exists {
+        return Err("User with this email already exists".to_string());
+    }
+
+    let user = User {
+        id: payload.id,
+        owner: existing.owner,
+        name: payload.name,
+        email: payload.email,
+        address: payload.address,
+        role: payload.role,
+    };
+
+    USERS_STORAGE.with(
+        |storage| match storage.borrow_mut().insert(payload.id, user.clone()) {
+            Some(_) => Ok(user),
+            None => Err("User not found".to_string()),
+        },
+    )
+}
+
+// Fetch a user by ID
+#[ic_cdk::query]
+fn get_user(id: u64) -> Result<User, String> {
+    USERS_STORAGE.with(|storage| match storage.borrow().get(&id) {
+        Some(user) => Ok(user.clone()),
+        None => Err(format!("User with ID {} not found", id)),
+    })
+}
+
+// Function to get user by owner
+#[ic_cdk::query]
+fn get_user_by_owner() -> Result<User, String> {
+    USERS_STORAGE.with(|users| {
+        let users = users.borrow();
+        let user = users
+            .iter()
+            .find(|(_, user)| user.owner.to_text() == caller().to_text());
+        match user {
+            Some((_, client)) => Ok(client.clone()),
+            None => Err(format!("User not found for caller: {}", caller().to_text())),
+        }
+    })
+}
+
+// Function to retrieve all users and throw an error if no users are found
+#[ic_cdk::query]
+fn get_all_users() -> Result<Vec<User>, String> {
+    USERS_STORAGE.with(|storage| {
+        let users: Vec<User> = storage
+            .borrow()
+            .iter()
+            .map(|(_, user)| user.clone())
+            .collect();
+        if users.is_empty() {
+            Err("No users found".to_string())
+        } else {
+            Ok(users)
+        }
+    })
+}
+
+// FUnction to get user by name, putting into consideration the case sensitivity
+#[ic_cdk::query]
+fn get_user_by_name(name: String) -> Result<User, String> {
+    USERS_STORAGE.with(|storage| {
+        let users = storage.borrow();
+        let user = users
+            .iter()
+            .find(|(_, user)| user.name.to_lowercase() == name.to_lowercase());
+        match user {
+            Some((_, client)) => Ok(client.clone()),
+            None => Err(format!("User with name {} not found", name)),
+        }
+    })
+}
+
+/**
+ * Function to create a new team
+ * This function takes a CreateTeamPayload as input and returns a Result containing either a Team or a String error message.
+ * It generates a unique ID for the new team, creates a Team object, and stores it in the TEAMS_STORAGE.
+ * Performs validation checks on input data and handles potential registration errors.
+ */
+
+#[ic_cdk::update]
+fn create_team(payload: CreateTeamPayload) -> Result<Team, String> {
+    // Validate the team payload to ensure all required fields are present
+    if payload.name.is_empty() {
+        return Err("Name is a required field".to_string());
+    }
+
+    // Generate unique ID
+    let id = generate_uuid();
+
+    // Create team object
+    let team = Team {
+        id,
+        name: payload.name,
+        sport_type: payload.sport_type,
+        members: Vec::new(),
+        coaches: Vec::new(),
+        university: normalize_university(payload.university),
+    };
+
+    // Store team in storage
+    TEAMS_STORAGE.with(|teams| {
+        teams.borrow_mut().insert(id, team.clone());
+        Ok(team)
+    })
+}
+
+// Fetch a team by ID
+#[ic_cdk::query]
+fn get_team(id: u64) -> Result<Team, String> {
+    TEAMS_STORAGE.with(|storage| match storage.borrow().get(&id) {
+        Some(team) => Ok(team.clone()),
+        None => Err(format!("Team with ID {} not found", id)),
+    })
+}
+
+// Function to get all teams
+#[ic_cdk::query]
+fn get_all_teams() -> Result<Vec<Team>, String> {
+    TEAMS_STORAGE.with(|storage| {
+        let teams: Vec<Team> = storage
+            .borrow()
+            .iter()
+            .map(|(_, team)| team.clone())
+            .collect();
+        if teams.is_empty() {
+            Err("No teams found".to_string())
+        } else {
+            Ok(teams)
+        }
+    })
+}
+
+//  Function to add member to a team
+#[ic_cdk::update]
+fn add_member_to_team(payload: AddMemberPayload) -> Result<Team, String> {
+    let team_id = payload.team_id;
+    let member_id = payload.member_id;
+
+    // Check if team exists
+    let team = TEAMS_STORAGE.with(|storage| match storage.borrow().get(&team_id) {
+        Some(team) => Some(team.clone()),
+        None => None,
+    });
+
+    if team.is_none() {
+        return Err("Team not found".to_string());
+    }
+
+    // Check if member exists
+    let member = USERS_STORAGE.with(|storage| match storage.borrow().get(&member_id) {
+        Some(user) => Some(user.clone()),
+        None => None,
+    });
+
+    if member.is_none() {
+        return Err(format!("Member with ID {} not found", member_id));
+    }
+
+    // Check if member is already part of a team
+    let member_already_in_team = TEAMS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .any(|(_, team)| team.members.contains(&member_id))
+    });
+
+    if member_already_in_team {
+        return Err("Member is already part of a team".to_string());
+    }
+
+    // Ensure the member is a player
+    if member.as_ref().unwrap().role != UserRole::Player {
+        return Err("Member must be a player".to_string());
+    }
+
+    ensure_transfer_window(team_id)?;
+
+    // Respect the squad size limits of the team's leagues
+    for rules in team_league_rules(team_id) {
+        if let Some(max_squad_size) = rules.roster.max_squad_size {
+            if team.as_ref().unwrap().members.len() as u32 >= max_squad_size {
+                return Err(format!(
+                    "A squad can have at most {} players under the league's rules",
+                    max_squad_size
+                ));
+            }
+        }
+    }
+
+    // Add member to team
+    TEAMS_STORAGE.with(|teams| {
+        let mut teams = teams.borrow_mut();
+        match teams.get(&team_id) {
+            Some(existing_team) => {
+                let mut team = existing_team.clone();
+                if team.members.contains(&member_id) {
+                    return Err("Member is already in this team".to_string());
+                }
+                team.members.push(member_id);
+                teams.insert(team_id, team.clone());
+                Ok(team)
+            }
+            None => Err("Team not found".to_string()),
+        }
+    })
+}
+// Schedule a match
+#[ic_cdk::update]
+pub fn schedule_match(payload: ScheduleMatchPayload) -> Result<Match, String> {
+    // Validate the match payload to ensure all required fields are present
+    if payload.scheduled_date.is_empty() {
+        return Err("Ensure scheduled date is provided in the format YYYY-MM-DD".to_string());
+    }
+
+    // Validate the match slot
+    slot_range(&payload.scheduled_date, &payload.kickoff_time)?;
+
+    let sport_type = payload.sport_type.clone();
+    let home_team_id = payload.home_team_id;
+    let away_team_id = payload.away_team_id;
+    let scheduled_date = payload.scheduled_date;
+
+    // Check if home team exists
+    let home_team = TEAMS_STORAGE.with(|storage| match storage.borrow().get(&home_team_id) {
+        Some(team) => Some(team.clone()),
+        None => None,
+    });
+
+    if home_team.is_none() {
+        return Err("Home team not found".to_string());
+    }
+
+    // Check if away team exists
+    let away_team = TEAMS_STORAGE.with(|storage| match storage.borrow().get(&away_team_id) {
+        Some(team) => Some(team.clone()),
+        None => None,
+    });
+
+    if away_team.is_none() {
+        return Err("Away team not found".to_string());
+    }
+
+    // Ensure the teams are not the same
+    if home_team_id == away_team_id {
+        return Err("Home team and away team cannot be the same".to_string());
+    }
+
+    // Generate unique ID for the match
+    let id = generate_uuid();
+
+    // Create match object
+    let match_obj = Match {
+        id,
+        sport_type,
+        home_team: home_team.unwrap(),
+        away_team: away_team.unwrap(),
+        scheduled_date,
+        kickoff_time: payload.kickoff_time,
+        result: None,
+        tournament_id: None,
+        round: None,
+        league_id: None,
+        season_id: None,
+        stage: None,
+        leg: None,
+    };
+
+    // Store match in storage
+    MATCHES_STORAGE.with(|matches| {
+        matches.borrow_mut().insert(id, match_obj.clone());
+        Ok(match_obj)
+    })
+}
+
+// Submit match result
+#[ic_cdk::update]
+pub fn submit_match_result(payload: MatchResultPayload) -> Result<Match, String> {
+    let match_id = payload.match_id;
+    let result = payload.result;
+
+    // Check if match exists
+    let existing_match = MATCHES_STORAGE.with(|storage| match storage.borrow().get(&match_id) {
+        Some(match_obj) => Some(match_obj.clone()),
+        None => None,
+    });
+
+    if existing_match.is_none() {
+        return Err(format!("Match with ID {} not found", match_id));
+    }
+
+    ensure_match_season_open(existing_match.as_ref().unwrap())?;
+
+    // Results are recorded by administrators, the league officials appointed to the competition or the match referee
+    if ensure_match_official(existing_match.as_ref().unwrap()).is_err()
+        && get_caller_match_referee(match_id).is_err()
+    {
+        return Err(
+            "Only administrators, officials appointed to this competition or the match referee can submit its result"
+                .to_string(),
+        );
+    }
+
+    // Check if result is already submitted
+    if existing_match.as_ref().unwrap().result.is_some() {
+        return Err("Match result has already been submitted".to_string());
+    }
+
+    if result.notes.len() > MAX_RESULT_NOTES_LENGTH {
+        return Err(format!(
+            "Result notes cannot exceed {} characters",
+            MAX_RESULT_NOTES_LENGTH
+        ));
+    }
+
+    validate_extra_time(&result)?;
+    validate_forfeit(existing_match.as_ref().unwrap(), &result)?;
+    validate_knockout_result(existing_match.as_ref().unwrap(), &result)?;
+    validate_result_winner(existing_match.as_ref().unwrap(), &result)?;
+    check_result_deadline(existing_match.as_ref().unwrap())?;
+
+    // Submit match result
+    let updated_match = MATCHES_STORAGE.with(|matches| {
+        let mut matches = matches.borrow_mut();
+        match matches.get(&match_id) {
+            Some(existing_match_obj) => {
+                let mut updated_match = existing_match_obj.clone();
+                updated_match.result = Some(result);
+                matches.insert(match_id.clone(), updated_match.clone());
+                Ok(updated_match)
+            }
+            None => Err("Match not found".to_string()),
+        }
+    })?;
+
+    // Credit the officials and count the match towards suspensions being served
+    complete_match(&updated_match);
+
+    Ok(updated_match)
+}
+
+// Fair play points a card costs its team; fewer is better
+fn fair_play_points(card_type: CardType) -> u32 {
+    match card_type {
+        CardType::Yellow | CardType::TechnicalFoul => 1,
+        CardType::SinBin => 2,
+        CardType::Red => 3,
+    }
+}
+
+// Drawing of lots, derived from the competition and team ids so that every query ranks tied teams the same way
+fn drawn_lot(competition_id: u64, team_id: u64) -> u64 {
+    let mut lot = competition_id ^ team_id.rotate_left(32);
+    lot = (lot ^ (lot >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    lot = (lot ^ (lot >> 27)).wrapping_mul(0x94d049bb133111eb);
+    lot ^ (lot >> 31)
+}
+
+// Check that standings rules award more for a win than a draw, and more for a draw than a loss
+fn validate_standings_rules(rules: &StandingsRules) -> Result<(), String> {
+    if rules.points_for_win < rules.points_for_draw || rules.points_for_draw < rules.points_for_loss
+    {
+        return Err("A win must be worth at least a draw, and a draw at least a loss".to_string());
+    }
+
+    if rules
+        .points_for_forfeit
+        .is_some_and(|points| points > rules.points_for_loss)
+    {
+        return Err("A forfeit must not be worth more than a loss".to_string());
+    }
+
+    for (index, tiebreaker) in rules.tiebreakers.iter().enumerate() {
+        if rules.tiebreakers[..index].contains(tiebreaker) {
+            return Err(format!(
+                "Tiebreaker {:?} is listed more than once",
+                tiebreaker
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// Points a result is worth under the given rules; a team that forfeited the match gets the forfeit points
+fn result_points(rules: &StandingsRules, scored: u32, conceded: u32, forfeited: bool) -> u32 {
+    if forfeited {
+        return rules.points_for_forfeit.unwrap_or(rules.points_for_loss);
+    }
+
+    match scored.cmp(&conceded) {
+        std::cmp::Ordering::Greater => rules.points_for_win,
+        std::cmp::Ordering::Equal => rules.points_for_draw,
+        std::cmp::Ordering::Less => rules.points_for_loss,
+    }
+}
+
+/*
+Build a standings table for the given teams from their completed matches against each other
+Teams are ranked on points. Teams level on points are separated by the tiebreakers in order,
+with head-to-head counting only the matches between the teams that are level.
+Teams still level after every tiebreaker keep the order they were given in.
+*/
+fn compute_standings(
+    team_ids: &[u64],
+    matches: &[Match],
+    rules: &StandingsRules,
+    competition_id: u64,
+) -> Vec<StandingsEntry> {
+    let mut standings: Vec<StandingsEntry> = team_ids
+        .iter()
+        .map(|team_id| StandingsEntry {
+            team_id: *team_id,
+            ..Default::default()
+        })
+        .collect();
+
+    let position = |team_id: u64| team_ids.iter().position(|id| *id == team_id);
+
+    let played: Vec<&Match> = matches
+        .iter()
+        .filter(|match_obj| {
+            match_obj.result.is_some()
+                && position(match_obj.home_team.id).is_some()
+                && position(match_obj.away_team.id).is_some()
+        })
+        .collect();
+
+    for match_obj in &played {
+        let result = match_obj.result.as_ref().unwrap();
+
+        for (team_id, scored, conceded) in [
+            (
+                match_obj.home_team.id,
+                result.score_team_a,
+                result.score_team_b,
+            ),
+            (
+                match_obj.away_team.id,
+                result.score_team_b,
+                result.score_team_a,
+            ),
+        ] {
+            let entry = &mut standings[position(team_id).unwrap()];
+            entry.played += 1;
+            entry.goals_for += scored;
+            entry.goals_against += conceded;
+            entry.goal_difference += scored as i64 - conceded as i64;
+            entry.points += result_points(
+                rules,
+                scored,
+                conceded,
+                result.forfeited_by == Some(team_id),
+            );
+            match scored.cmp(&conceded) {
+                std::cmp::Ordering::Greater => entry.won += 1,
+                std::cmp::Ordering::Equal => entry.drawn += 1,
+                std::cmp::Ordering::Less => entry.lost += 1,
+            }
+        }
+    }
+
+    // Cards shown in the counted matches
+    CARDS_STORAGE.with(|storage| {
+        for (_, card) in storage.borrow().iter() {
+            if played.iter().any(|match_obj| match_obj.id == card.match_id) {
+                if let Some(index) = position(card.team_id) {
+                    standings[index].fair_play_points += fair_play_points(card.card_type);
+                }
+            }
+        }
+    });
+
+    // Stable sort, so teams level on points keep the given order
+    standings.sort_by_key(|entry| std::cmp::Reverse(entry.points));
+
+    let mut start = 0;
+    while start < standings.len() {
+        let points = standings[start].points;
+        let end = start
+            + standings[start..]
+                .iter()
+                .take_while(|entry| entry.points == points)
+                .count();
+
+        if end - start > 1 {
+            let level: Vec<u64> = standings[start..end]
+                .iter()
+                .map(|entry| entry.team_id)
+                .collect();
+
+            // Points each level team took from the matches between them
+            let head_to_head = |team_id: u64| -> u32 {
+                played
+                    .iter()
+                    .filter(|match_obj| {
+                        level.contains(&match_obj.home_team.id)
+                            && level.contains(&match_obj.away_team.id)
+                    })
+                    .map(|match_obj| {
+                        let result = match_obj.result.as_ref().unwrap();
+                        let forfeited = result.forfeited_by == Some(team_id);
+                        if match_obj.home_team.id == team_id {
+                            result_points(
+                                rules,
+                                result.score_team_a,
+                                result.score_team_b,
+                                forfeited,
+                            )
+                        } else if match_obj.away_team.id == team_id {
+                            result_points(
+                                rules,
+                                result.score_team_b,
+                                result.score_team_a,
+                                forfeited,
+                            )
+                        } else {
+                            0
+                        }
+                    })
+                    .sum()
+            };
+
+            // Higher keys rank first
+            let keys = |entry: &StandingsEntry| -> Vec<i128> {
+                rules
+                    .tiebreakers
+                    .iter()
+                    .map(|tiebreaker| match tiebreaker {
+                        Tiebreaker::HeadToHead => head_to_head(entry.team_id) as i128,
+                        Tiebreaker::GoalDifference => entry.goal_difference as i128,
+                        Tiebreaker::GoalsScored => entry.goals_for as i128,
+                        Tiebreaker::FairPlay => -(entry.fair_play_points as i128),
+                        Tiebreaker::DrawingOfLots => {
+                            -(drawn_lot(competition_id, entry.team_id) as i128)
+                        }
+                    })
+                    .collect()
+            };
+
+            standings[start..end].sort_by_cached_key(|entry| std::cmp::Reverse(keys(entry)));
+        }
+
+        start = end;
+    }
+
+    for (index, entry) in standings.iter_mut().enumerate() {
+        entry.position = index as u32 + 1;
+    }
+
+    standings
+}
+
+// Get the standings of a tournament from all of its completed matches
+#[ic_cdk::query]
+fn get_tournament_standings(tournament_id: u64) -> Result<Vec<StandingsEntry>, String> {
+    let tournament = get_tournament(tournament_id)?;
+    let matches = get_tournament_matches(tournament_id);
+
+    Ok(compute_standings(
+        &tournament.team_ids,
+        &matches,
+        &tournament_standings_rules(&tournament),
+        tournament.id,
+    ))
+}
+
+// Get the standings of a league across the completed matches of all of its tournaments
+// Members that have not played yet are listed with no points
+#[ic_cdk::query]
+fn get_league_standings(league_id: u64) -> Result<Vec<StandingsEntry>, String> {
+    let league = get_league(league_id)?;
+
+    // Every member is ranked, along with any other team that played in the league's tournaments
+    let mut team_ids = league.team_ids.clone();
+    for tournament_id in &league.tournament_ids {
+        for team_id in get_tournament(*tournament_id)?.team_ids {
+            if !team_ids.contains(&team_id) {
+                team_ids.push(team_id);
+            }
+        }
+    }
+
+    let matches = get_indexed_matches(league_id, None);
+
+    Ok(compute_standings(
+        &team_ids,
+        &matches,
+        &effective_standings_rules(&league, None),
+        league_id,
+    ))
+}
+
+/**
+ * Function to assign a coach to a team
+ * This function takes an AssignCoachPayload as input and returns a Result containing either a Team or a String error message.
+ * It fetches the team and coach by their IDs, validates their existence, and assigns the coach to the team.
+ * Handles potential errors such as missing team or coach, and duplicate coach assignment.
+ */
+#[ic_cdk::update]
+fn assign_coach(payload: AssignCoachPayload) -> Result<Team, String> {
+    let team_id = payload.team_id;
+    let coach_id = payload.coach_id;
+
+    // Check if team exists
+    let team = TEAMS_STORAGE.with(|storage| match storage.borrow().get(&team_id) {
+        Some(team) => Some(team.clone()),
+        None => None,
+    });
+
+    if team.is_none() {
+        return Err("Team not found".to_string());
+    }
+
+    // Check if coach exists
+    let coach = USERS_STORAGE.with(|storage| match storage.borrow().get(&coach_id) {
+        Some(user) => Some(user.clone()),
+        None => None,
+    });
+
+    if coach.is_none() {
+        return Err(format!("Coach with ID {} not found", coach_id));
+    }
+
+    // Check if coach is already part of a team
+    let coach_already_in_team = TEAMS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .any(|(_, team)| team.coaches.contains(&coach_id))
+    });
+
+    if coach_already_in_team {
+        return Err("Coach is already part of a team".to_string());
+    }
+
+    // Ensure the coach is a coach
+    if coach.as_ref().unwrap().role != UserRole::Coach {
+        return Err("Coach must have Coach role".to_string());
+    }
+
+    // Assign coach to team
+    TEAMS_STORAGE.with(|teams| {
+        let mut teams = teams.borrow_mut();
+        match teams.get(&team_id) {
+            Some(existing_team) => {
+                let mut team = existing_team.clone();
+                if team.coaches.contains(&coach_id) {
+                    return Err("Coach is already in this team".to_string());
+                }
+                team.coaches.push(coach_id);
+                teams.insert(team_id, team.clone());
+                Ok(team)
+            }
+            None => Err("Team not found".to_string()),
+        }
+    })
+}
+// Check whether a user currently coaches a team
+fn is_team_coach(team_id: u64, user_id: u64) -> bool {
+    TEAMS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .get(&team_id)
+            .is_some_and(|team| team.coaches.contains(&user_id))
+    })
+}
+
+// Get all matches
+#[ic_cdk::query]
+fn get_all_matches() -> Result<Vec<Match>, String> {
+    MATCHES_STORAGE.with(|storage| {
+        let matches: Vec<Match> = storage
+            .borrow()
+            .iter()
+            .map(|(_, matches)| matches.clone())
+            .collect();
+        if matches.is_empty() {
+            Err("No matches found".to_string())
+        } else {
+            Ok(matches)
+        }
+    })
+}
+
+// Get match by ID
+#[ic_cdk::query]
+fn get_match(id: u64) -> Result<Match, String> {
+    MATCHES_STORAGE.with(|storage| match storage.borrow().get(&id) {
+        Some(match_obj) => Ok(match_obj.clone()),
+        None => Err(format!("Match with ID {} not found", id)),
+    })
+}
+
+// Get all matches for a specific team
+#[ic_cdk::query]
+fn get_matches_by_team(team_id: u64) -> Result<Vec<Match>, String> {
+    MATCHES_STORAGE.with(|storage| {
+        let matches: Vec<Match> = storage
+            .borrow()
+            .iter()
+            .filter(|(_, match_obj)| {
+                match_obj.home_team.id == team_id || match_obj.away_team.id == team_id
+            })
+            .map(|(_, match_obj)| match_obj.clone())
+            .collect();
+        if matches.is_empty() {
+            Err("No matches found for this team".to_string())
+        } else {
+            Ok(matches)
+        }
+    })
+}
+
+// Get all matches for a specific sport type
+#[ic_cdk::query]
+fn get_matches_by_sport_type(sport_type: SportType) -> Result<Vec<Match>, String> {
+    MATCHES_STORAGE.with(|storage| {
+        let matches: Vec<Match> = storage
+            .borrow()
+            .iter()
+            .filter(|(_, match_obj)| match_obj.sport_type == sport_type)
+            .map(|(_, match_obj)| match_obj.clone())
+            .collect();
+        if matches.is_empty() {
+            Err("No matches found for this sport type".to_string())
+        } else {
+            Ok(matches)
+        }
+    })
+}
+
+// Get all matches for a specific date
+#[ic_cdk::query]
+fn get_matches_by_date(date: String) -> Result<Vec<Match>, String> {
+    MATCHES_STORAGE.with(|storage| {
+        let matches: Vec<Match> = storage
+            .borrow()
+            .iter()
+            .filter(|(_, match_obj)| match_obj.scheduled_date == date)
+            .map(|(_, match_obj)| match_obj.clone())
+            .collect();
+        if matches.is_empty() {
+            Err("No matches found for this date".to_string())
+        } else {
+            Ok(matches)
+        }
+    })
+}
+
+/**
+ * Function to register a new referee
+ * This function takes a RegisterRefereePayload as input and returns a Result containing either a Referee or a String error message.
+ * The caller becomes the owner of the referee profile, and ratings start out empty.
+ */
+#[ic_cdk::update]
+fn register_referee(payload: RegisterRefereePayload) -> Result<Referee, String> {
+    // Validate the referee payload to ensure all required fields are present
+    if payload.name.is_empty() || payload.email.is_empty() || payload.address.is_empty() {
+        return Err("Name, email, and address are required fields".to_string());
+    }
+
+    // Validate email format
+    validate_email_format(&payload.email)?;
+
+    // Check if referee with this email already exists
+    let email_exists = REFEREE_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .any(|(_, referee)| referee.email == payload.email)
+    });
+
+    if email_exists {
+        return Err("Referee with this email already exists".to_string());
+    }
+
+    // Generate unique ID
+    let id = generate_uuid();
+
+    let referee = Referee {
+        id,
+        owner: caller(),
+        name: payload.name,
+        email: payload.email,
+        address: payload.address,
+        university: normalize_university(payload.university),
+        conflicts_of_interest: Vec::new(),
+        certifications: Vec::new(),
+        performance_rating: 0.0,
+        total_rating: 0.0,
+        total_ratings: 0,
+        total_matches: 0,
+    };
+
+    REFEREE_STORAGE.with(|referees| {
+        referees.borrow_mut().insert(id, referee.clone());
+        Ok(referee)
+    })
+}
+
+// Fetch a referee by ID
+#[ic_cdk::query]
+fn get_referee(id: u64) -> Result<Referee, String> {
+    REFEREE_STORAGE.with(|storage| match storage.borrow().get(&id) {
+        Some(referee) => Ok(referee.clone()),
+        None => Err(format!("Referee with ID {} not found", id)),
+    })
+}
+
+// Function to get all referees
+#[ic_cdk::query]
+fn get_all_referees() -> Result<Vec<Referee>, String> {
+    REFEREE_STORAGE.with(|storage| {
+        let referees: Vec<Referee> = storage
+            .borrow()
+            .iter()
+            .map(|(_, referee)| referee.clone())
+            .collect();
+        if referees.is_empty() {
+            Err("No referees found".to_string())
+        } else {
+            Ok(referees)
+        }
+    })
+}
+
+/**
+ * Function to assign a referee to a match
+ * Only administrators and league officials may assign officials.
+ * A referee can hold a single role per match, and a match has at most one referee in the Referee role.
+ */
+#[ic_cdk::update]
+fn assign_referee(payload: AssignRefereePayload) -> Result<RefereeAssignment, String> {
+    // Check if match exists
+    let match_obj = get_match(payload.match_id)?;
+    ensure_match_official(&match_obj)?;
+
+    if match_obj.result.is_some() {
+        return Err("Cannot assign officials to a match that has already been played".to_string());
+    }
+
+    // Check if referee exists and can officiate the match
+    let referee = get_referee(payload.referee_id)?;
+    check_referee_eligibility(&referee, &match_obj, &[], None)?;
+
+    let officials = get_assignments_for_match(payload.match_id);
+
+    if officials
+        .iter()
+        .any(|assignment| assignment.referee_id == payload.referee_id)
+    {
+        return Err("Referee is already assigned to this match".to_string());
+    }
+
+    if payload.role == OfficialRole::Referee
+        && officials
+            .iter()
+            .any(|assignment| assignment.role == OfficialRole::Referee)
+    {
+        return Err("Match already has a referee assigned".to_string());
+    }
+
+    let id = generate_uuid();
+
+    let assignment = RefereeAssignment {
+        id,
+        match_id: payload.match_id,
+        referee_id: payload.referee_id,
+        role: payload.role,
+        assigned_at: ic_cdk::api::time(),
+    };
+
+    ASSIGNMENTS_STORAGE.with(|assignments| {
+        assignments.borrow_mut().insert(id, assignment.clone());
+        Ok(assignment)
+    })
+}
+
+/**
+ * Function to automatically allocate referees to a set of matches
+ * Matches are handled in order of decreasing stakes, so higher-stakes games get the first pick of officials.
+ * Each open slot goes to the eligible referee with the best balance of rating (weighted by stakes) and upcoming workload.
+ * With `preview` set the proposed assignments are returned without being stored.
+ */
+#[ic_cdk::update]
+fn allocate_referees(payload: AllocateRefereesPayload) -> Result<RefereeAllocation, String> {
+    ensure_caller_role(&[UserRole::Administrator, UserRole::LeagueOfficial])?;
+
+    if payload.matches.is_empty() {
+        return Err("At least one match is required".to_string());
+    }
+
+    if payload.assistants_per_match > 4 {
+        return Err("A match can have at most 4 assistants".to_string());
+    }
+
+    // Load and validate the matches
+    let mut targets: Vec<(Match, &AllocationMatch)> = Vec::new();
+    for target in &payload.matches {
+        if target.stakes > 10 {
+            return Err("Stakes must be between 0 and 10".to_string());
+        }
+
+        let match_obj = get_match(target.match_id)?;
+        ensure_match_official(&match_obj)?;
+
+        if match_obj.result.is_some() {
+            return Err(format!(
+                "Match with ID {} has already been played",
+                match_obj.id
+            ));
+        }
+
+        if targets
+            .iter()
+            .any(|(existing, _)| existing.id == match_obj.id)
+        {
+            return Err(format!(
+                "Match with ID {} is listed more than once",
+                match_obj.id
+            ));
+        }
+
+        targets.push((match_obj, target));
+    }
+
+    // Highest stakes first, then by date
+    targets.sort_by(|a, b| {
+        b.1.stakes
+            .cmp(&a.1.stakes)
+            .then(a.0.scheduled_date.cmp(&b.0.scheduled_date))
+            .then(a.0.id.cmp(&b.0.id))
+    });
+
+    let referees = get_all_referees()?;
+    let workloads = get_upcoming_workloads();
+
+    let mut planned: Vec<ProposedAssignment> = Vec::new();
+    let mut unfilled: Vec<UnfilledSlot> = Vec::new();
+
+    for (match_obj, target) in &targets {
+        let existing = get_assignments_for_match(match_obj.id);
+
+        // Work out which roles still need filling
+        let mut roles = Vec::new();
+        if !existing
+            .iter()
+            .any(|assignment| assignment.role == OfficialRole::Referee)
+        {
+            roles.push(OfficialRole::Referee);
+        }
+        let assistants = existing
+            .iter()
+            .filter(|assignment| assignment.role == OfficialRole::Assistant)
+            .count();
+        for _ in assistants..payload.assistants_per_match as usize {
+            roles.push(OfficialRole::Assistant);
+        }
+
+        for role in roles {
+            let best = referees
+                .iter()
+                // A referee already on the match, or already proposed for another of its roles, takes no further role
+                .filter(|referee| {
+                    !existing
+                        .iter()
+                        .any(|assignment| assignment.referee_id == referee.id)
+                        && !planned.iter().any(|proposal| {
+                            proposal.match_id == match_obj.id && proposal.referee_id == referee.id
+                        })
+                })
+                .filter(|referee| {
+                    check_referee_eligibility(referee, match_obj, &planned, target.minimum_grade)
+                        .is_ok()
+                })
+                .map(|referee| {
+                    let workload = workloads.get(&referee.id).copied().unwrap_or(0)
+                        + planned
+                            .iter()
+                            .filter(|proposal| proposal.referee_id == referee.id)
+                            .count() as u32;
+                    let score =
+                        referee.performance_rating * target.stakes as f32 / 10.0 - workload as f32;
+                    (referee, score)
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.id.cmp(&a.0.id)));
+
+            match best {
+                Some((referee, _)) => planned.push(ProposedAssignment {
+                    match_id: match_obj.id,
+                    referee_id: referee.id,
+                    referee_name: referee.name.clone(),
+                    role,
+                    scheduled_date: match_obj.scheduled_date.clone(),
+                }),
+                None => unfilled.push(UnfilledSlot {
+                    match_id: match_obj.id,
+                    role,
+                    reason: "No available, certified referee without a conflict of interest"
+                        .to_string(),
+                }),
+            }
+        }
+    }
+
+    // Store the assignments unless this is a preview
+    if !payload.preview {
+        for proposal in &planned {
+            let id = generate_uuid();
+            let assignment = RefereeAssignment {
+                id,
+                match_id: proposal.match_id,
+                referee_id: proposal.referee_id,
+                role: proposal.role,
+                assigned_at: ic_cdk::api::time(),
+            };
+            ASSIGNMENTS_STORAGE.with(|assignments| assignments.borrow_mut().insert(id, assignment));
+        }
+    }
+
+    Ok(RefereeAllocation {
+        committed: !payload.preview,
+        assignments: planned,
+        unfilled,
+    })
+}
+
+// Function for a referee to declare a conflict of interest with a team
+#[ic_cdk::update]
+fn declare_conflict_of_interest(payload: DeclareConflictPayload) -> Result<Referee, String> {
+    let mut referee = get_referee(payload.referee_id)?;
+
+    if referee.owner != caller() {
+        return Err("Only the referee can declare their conflicts of interest".to_string());
+    }
+
+    // Check if team exists
+    get_team(payload.team_id)?;
+
+    if referee.conflicts_of_interest.contains(&payload.team_id) {
+        return Err("Conflict of interest has already been declared".to_string());
+    }
+
+    referee.conflicts_of_interest.push(payload.team_id);
+    REFEREE_STORAGE.with(|referees| referees.borrow_mut().insert(referee.id, referee.clone()));
+
+    Ok(referee)
+}
+
+/*
+Check whether a referee can officiate a match
+A referee is ineligible without a certification for the sport that is valid on the match date and meets the minimum grade,
+when they declared a conflict of interest with either team, belong to the university of either team,
+when their availability calendar rules out the match slot,
+or when they are already booked, or planned alongside this match, for another match on the same day.
+*/
+fn check_referee_eligibility(
+    referee: &Referee,
+    match_obj: &Match,
+    planned: &[ProposedAssignment],
+    minimum_grade: Option<CertificationGrade>,
+) -> Result<(), String> {
+    let certified = referee.certifications.iter().any(|certification| {
+        certification.revoked_at.is_none()
+            && certification.sport_type == match_obj.sport_type
+            && certification.expiry_date >= match_obj.scheduled_date
+            && certification.grade >= minimum_grade.unwrap_or_default()
+    });
+
+    if !certified {
+        return Err(match minimum_grade {
+            Some(grade) => format!(
+                "Referee {} holds no valid {:?} certification of grade {:?} or above",
+                referee.name, match_obj.sport_type, grade
+            ),
+            None => format!(
+                "Referee {} holds no valid {:?} certification",
+                referee.name, match_obj.sport_type
+            ),
+        });
+    }
+
+    for team in [&match_obj.home_team, &match_obj.away_team] {
+        if referee.conflicts_of_interest.contains(&team.id) {
+            return Err(format!(
+                "Referee {} has declared a conflict of interest with {}",
+                referee.name, team.name
+            ));
+        }
+
+        if referee.university.is_some() && referee.university == team.university {
+            return Err(format!(
+                "Referee {} belongs to the same university as {}",
+                referee.name, team.name
+            ));
+        }
+    }
+
+    let (start, end) = slot_range(&match_obj.scheduled_date, &match_obj.kickoff_time)?;
+    if !is_official_free(referee.id, start, end) {
+        return Err(format!(
+            "Referee {} is unavailable on {}",
+            referee.name, match_obj.scheduled_date
+        ));
+    }
+
+    let planned_same_day = planned.iter().any(|proposal| {
+        proposal.referee_id == referee.id
+            && proposal.match_id != match_obj.id
+            && proposal.scheduled_date == match_obj.scheduled_date
+    });
+
+    if planned_same_day
+        || is_referee_booked_on(referee.id, &match_obj.scheduled_date, Some(match_obj.id))
+    {
+        return Err(format!(
+            "Referee {} is already officiating another match on {}",
+            referee.name, match_obj.scheduled_date
+        ));
+    }
+
+    Ok(())
+}
+
+// Check whether a referee is assigned to a match on the given date, other than the excluded match
+fn is_referee_booked_on(referee_id: u64, date: &str, excluded_match_id: Option<u64>) -> bool {
+    ASSIGNMENTS_STORAGE.with(|storage| {
+        storage.borrow().iter().any(|(_, assignment)| {
+            assignment.referee_id == referee_id
+                && Some(assignment.match_id) != excluded_match_id
+                && MATCHES_STORAGE.with(|matches| {
+                    matches
+                        .borrow()
+                        .get(&assignment.match_id)
+                        .is_some_and(|other| other.scheduled_date == date)
+                })
+        })
+    })
+}
+
+/*
+Check an official's availability calendar for a time range
+Unavailable windows overlapping the range always rule the official out. Otherwise an available window covering
+the whole range overrides recurring unavailability, which rules the official out on any overlap.
+*/
+fn is_official_free(referee_id: u64, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+    let availability = match AVAILABILITY_STORAGE.with(|storage| storage.borrow().get(&referee_id))
+    {
+        Some(availability) => availability,
+        None => return true,
+    };
+
+    let windows: Vec<(AvailabilityKind, NaiveDateTime, NaiveDateTime)> = availability
+        .windows
+        .iter()
+        .filter_map(|window| {
+            Some((
+                window.kind,
+                parse_date_time(&window.start).ok()?,
+                parse_date_time(&window.end).ok()?,
+            ))
+        })
+        .collect();
+
+    if windows.iter().any(|(kind, window_start, window_end)| {
+        *kind == AvailabilityKind::Unavailable && *window_start < end && start < *window_end
+    }) {
+        return false;
+    }
+
+    if windows.iter().any(|(kind, window_start, window_end)| {
+        *kind == AvailabilityKind::Available && *window_start <= start && end <= *window_end
+    }) {
+        return true;
+    }
+
+    !availability
+        .recurring
+        .iter()
+        .any(|rule| recurring_rule_overlaps(rule, start, end))
+}
+
+// Check whether a weekly unavailability rule overlaps a time range
+fn recurring_rule_overlaps(
+    rule: &RecurringUnavailability,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> bool {
+    let (Ok(rule_start), Ok(rule_end)) = (parse_time(&rule.start_time), parse_time(&rule.end_time))
+    else {
+        return false;
+    };
+    let from_date = rule
+        .from_date
+        .as_deref()
+        .and_then(|date| parse_date(date).ok());
+    let until_date = rule
+        .until_date
+        .as_deref()
+        .and_then(|date| parse_date(date).ok());
+
+    let mut day = start.date();
+    while day <= end.date() {
+        let in_effect = from_date.unwrap_or(NaiveDate::MIN) <= day
+            && day <= until_date.unwrap_or(NaiveDate::MAX);
+
+        if in_effect
+            && to_weekday(day.weekday()) == rule.weekday
+            && day.and_time(rule_start) < end
+            && start < day.and_time(rule_end)
+        {
+            return true;
+        }
+
+        day += Duration::days(1);
+    }
+
+    false
+}
+
+// Convert a chrono weekday into the candid Weekday
+fn to_weekday(weekday: chrono::Weekday) -> Weekday {
+    match weekday {
+        chrono::Weekday::Mon => Weekday::Monday,
+        chrono::Weekday::Tue => Weekday::Tuesday,
+        chrono::Weekday::Wed => Weekday::Wednesday,
+        chrono::Weekday::Thu => Weekday::Thursday,
+        chrono::Weekday::Fri => Weekday::Friday,
+        chrono::Weekday::Sat => Weekday::Saturday,
+        chrono::Weekday::Sun => Weekday::Sunday,
+    }
+}
+
+/**
+ * Function to add a one-off available or unavailable range to an official's calendar
+ * Only the official may edit their own calendar. Ranges that have already ended are pruned on every addition.
+ */
+#[ic_cdk::update]
+fn add_availability_window(
+    payload: AddAvailabilityWindowPayload,
+) -> Result<OfficialAvailability, String> {
+    let mut availability = get_own_availability(payload.referee_id)?;
+
+    let start = parse_date_time(&payload.start)?;
+    let end = parse_date_time(&payload.end)?;
+
+    if start >= end {
+        return Err("Start must be before end".to_string());
+    }
+
+    let now = now();
+    if end <= now {
+        return Err("End must be in the future".to_string());
+    }
+
+    if payload.note.len() > 100 {
+        return Err("Note cannot exceed 100 characters".to_string());
+    }
+
+    // Prune windows that have already ended
+    availability
+        .windows
+        .retain(|window| parse_date_time(&window.end).is_ok_and(|window_end| window_end > now));
+
+    if availability.windows.len() >= MAX_AVAILABILITY_WINDOWS {
+        return Err(format!(
+            "An official can have at most {} availability windows",
+            MAX_AVAILABILITY_WINDOWS
+        ));
+    }
+
+    availability.windows.push(AvailabilityWindow {
+        id: generate_uuid(),
+        kind: payload.kind,
+        start: payload.start,
+        end: payload.end,
+        note: payload.note,
+    });
+
+    AVAILABILITY_STORAGE.with(|storage| {
+        storage
+            .borrow_mut()
+            .insert(availability.referee_id, availability.clone())
+    });
+
+    Ok(availability)
+}
+
+// Function to add a weekly unavailability rule to an official's calendar
+#[ic_cdk::update]
+fn add_recurring_unavailability(
+    payload: AddRecurringUnavailabilityPayload,
+) -> Result<OfficialAvailability, String> {
+    let mut availability = get_own_availability(payload.referee_id)?;
+
+    if parse_time(&payload.start_time)? >= parse_time(&payload.end_time)? {
+        return Err("Start time must be before end time".to_string());
+    }
+
+    if let (Some(from_date), Some(until_date)) = (&payload.from_date, &payload.until_date) {
+        if parse_date(from_date)? > parse_date(until_date)? {
+            return Err("From date must not be after until date".to_string());
+        }
+    }
+
+    for date in [&payload.from_date, &payload.until_date]
+        .into_iter()
+        .flatten()
+    {
+        parse_date(date)?;
+    }
+
+    if payload.note.len() > 100 {
+        return Err("Note cannot exceed 100 characters".to_string());
+    }
+
+    if availability.recurring.len() >= MAX_RECURRING_UNAVAILABILITY {
+        return Err(format!(
+            "An official can have at most {} recurring unavailability rules",
+            MAX_RECURRING_UNAVAILABILITY
+        ));
+    }
+
+    availability.recurring.push(RecurringUnavailability {
+        id: generate_uuid(),
+        weekday: payload.weekday,
+        start_time: payload.start_time,
+        end_time: payload.end_time,
+        from_date: payload.from_date,
+        until_date: payload.until_date,
+        note: payload.note,
+    });
+
+    AVAILABILITY_STORAGE.with(|storage| {
+        storage
+            .borrow_mut()
+            .insert(availability.referee_id, availability.clone())
+    });
+
+    Ok(availability)
+}
+
+// Function to remove a window or recurring rule from an official's calendar
+#[ic_cdk::update]
+fn remove_availability_entry(
+    payload: RemoveAvailabilityEntryPayload,
+) -> Result<OfficialAvailability, String> {
+    let mut availability = get_own_availability(payload.referee_id)?;
+
+    let entries = availability.windows.len() + availability.recurring.len();
+    availability
+        .windows
+        .retain(|window| window.id != payload.entry_id);
+    availability
+        .recurring
+        .retain(|rule| rule.id != payload.entry_id);
+
+    if availability.windows.len() + availability.recurring.len() == entries {
+        return Err(format!(
+            "Availability entry with ID {} not found",
+            payload.entry_id
+        ));
+    }
+
+    AVAILABILITY_STORAGE.with(|storage| {
+        storage
+            .borrow_mut()
+            .insert(availability.referee_id, availability.clone())
+    });
+
+    Ok(availability)
+}
+
+// Get an official's availability calendar
+#[ic_cdk::query]
+fn get_referee_availability(referee_id: u64) -> Result<OfficialAvailability, String> {
+    get_referee(referee_id)?;
+
+    Ok(AVAILABILITY_STORAGE
+        .with(|storage| storage.borrow().get(&referee_id))
+        .unwrap_or(OfficialAvailability {
+            referee_id,
+            ..Default::default()
+        }))
+}
+
+/**
+ * Function for schedulers to find the officials free in a match slot
+ * Officials are free when their calendar allows the slot and they are not assigned to another match that day.
+ * When a sport is given, only officials certified for it on that date are returned.
+ */
+#[ic_cdk::query]
+fn get_free_officials(query: FreeOfficialsQuery) -> Result<Vec<Referee>, String> {
+    let (start, end) = slot_range(&query.date, &query.kickoff_time)?;
+
+    let officials: Vec<Referee> = REFEREE_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, referee)| referee)
+            .filter(|referee| {
+                query.sport_type.is_none()
+                    || referee.certifications.iter().any(|certification| {
+                        certification.revoked_at.is_none()
+                            && Some(certification.sport_type) == query.sport_type
+                            && certification.expiry_date >= query.date
+                    })
+            })
+            .filter(|referee| is_official_free(referee.id, start, end))
+            .filter(|referee| !is_referee_booked_on(referee.id, &query.date, None))
+            .collect()
+    });
+
+    if officials.is_empty() {
+        Err("No officials are free for this slot".to_string())
+    } else {
+        Ok(officials)
+    }
+}
+
+// Fetch the calendar of a referee owned by the caller
+fn get_own_availability(referee_id: u64) -> Result<OfficialAvailability, String> {
+    let referee = get_referee(referee_id)?;
+
+    if referee.owner != caller() {
+        return Err("Only the official can edit their availability".to_string());
+    }
+
+    Ok(AVAILABILITY_STORAGE
+        .with(|storage| storage.borrow().get(&referee_id))
+        .unwrap_or(OfficialAvailability {
+            referee_id,
+            ..Default::default()
+        }))
+}
+
+// Count each referee's assignments to matches that have not been played yet
+fn get_upcoming_workloads() -> BTreeMap<u64, u32> {
+    let mut workloads = BTreeMap::new();
+    ASSIGNMENTS_STORAGE.with(|storage| {
+        for (_, assignment) in storage.borrow().iter() {
+            let upcoming = MATCHES_STORAGE.with(|matches| {
+                matches
+                    .borrow()
+                    .get(&assignment.match_id)
+                    .is_some_and(|match_obj| match_obj.result.is_none())
+            });
+            if upcoming {
+                *workloads.entry(assignment.referee_id).or_insert(0) += 1;
+            }
+        }
+    });
+    workloads
+}
+
+/**
+ * Function to grant a referee a certification for a sport
+ * Only administrators may grant certifications. A new certification supersedes any active one for the same sport.
+ */
+#[ic_cdk::update]
+fn grant_certification(payload: GrantCertificationPayload) -> Result<Referee, String> {
+    let administrator = ensure_caller_role(&[UserRole::Administrator])?;
+
+    if payload.issuing_body.trim().is_empty() {
+        return Err("Issuing body is a required field".to_string());
+    }
+
+    if payload.issuing_body.trim().len() > MAX_ISSUING_BODY_LENGTH {
+        return Err(format!(
+            "Issuing body must be at most {} characters",
+            MAX_ISSUING_BODY_LENGTH
+        ));
+    }
+
+    // Validate the expiry date
+    if parse_date(&payload.expiry_date)? <= today() {
+        return Err("Expiry date must be in the future".to_string());
+    }
+
+    let mut referee = get_referee(payload.referee_id)?;
+    let now = ic_cdk::api::time();
+
+    // Supersede the active certification for the same sport
+    for certification in referee.certifications.iter_mut() {
+        if certification.sport_type == payload.sport_type && certification.revoked_at.is_none() {
+            certification.revoked_at = Some(now);
+            certification.revocation_reason = Some("Superseded".to_string());
+        }
+    }
+
+    referee.certifications.push(Certification {
+        id: generate_uuid(),
+        sport_type: payload.sport_type,
+        grade: payload.grade,
+        issuing_body: payload.issuing_body.trim().to_string(),
+        expiry_date: payload.expiry_date,
+        granted_by: administrator.id,
+        granted_at: now,
+        revoked_at: None,
+        revocation_reason: None,
+    });
+
+    archive_revoked_certifications(&mut referee);
+    REFEREE_STORAGE.with(|referees| referees.borrow_mut().insert(referee.id, referee.clone()));
+
+    Ok(referee)
+}
+
+// Function for an administrator to revoke a referee's certification
+#[ic_cdk::update]
+fn revoke_certification(payload: RevokeCertificationPayload) -> Result<Referee, String> {
+    ensure_caller_role(&[UserRole::Administrator])?;
+
+    if payload.reason.trim().is_empty() {
+        return Err("A reason is required to revoke a certification".to_string());
+    }
+
+    if payload.reason.trim().len() > MAX_REVOCATION_REASON_LENGTH {
+        return Err(format!(
+            "Reason must be at most {} characters",
+            MAX_REVOCATION_REASON_LENGTH
+        ));
+    }
+
+    let mut referee = get_referee(payload.referee_id)?;
+
+    let certification = referee
+        .certifications
+        .iter_mut()
+        .find(|certification| certification.id == payload.certification_id)
+        .ok_or_else(|| {
+            format!(
+                "Certification with ID {} not found",
+                payload.certification_id
+            )
+        })?;
+
+    if certification.revoked_at.is_some() {
+        return Err("Certification has already been revoked".to_string());
+    }
+
+    certification.revoked_at = Some(ic_cdk::api::time());
+    certification.revocation_reason = Some(payload.reason.trim().to_string());
+
+    archive_revoked_certifications(&mut referee);
+    REFEREE_STORAGE.with(|referees| referees.borrow_mut().insert(referee.id, referee.clone()));
+
+    Ok(referee)
+}
+
+// Move a referee's superseded and revoked certifications to the certification history, keeping only active ones on the referee
+fn archive_revoked_certifications(referee: &mut Referee) {
+    let (revoked, active): (Vec<Certification>, Vec<Certification>) = referee
+        .certifications
+        .drain(..)
+        .partition(|certification| certification.revoked_at.is_some());
+
+    CERTIFICATION_HISTORY_STORAGE.with(|storage| {
+        let mut storage = storage.borrow_mut();
+        for certification in revoked {
+            let key = CertificationKey {
+                referee_id: referee.id,
+                certification_id: certification.id,
+            };
+            storage.insert(key, certification);
+        }
+    });
+
+    referee.certifications = active;
+}
+
+// Get every certification a referee has held, active or not, in the order they were granted
+#[ic_cdk::query]
+fn get_certification_history(referee_id: u64) -> Result<Vec<Certification>, String> {
+    let referee = get_referee(referee_id)?;
+
+    let start = CertificationKey {
+        referee_id,
+        certification_id: 0,
+    };
+    let end = CertificationKey {
+        referee_id,
+        certification_id: u64::MAX,
+    };
+
+    let mut certifications: Vec<Certification> = CERTIFICATION_HISTORY_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .range(start..=end)
+            .map(|(_, certification)| certification)
+            .collect()
+    });
+    certifications.extend(referee.certifications);
+    certifications.sort_by_key(|certification| certification.granted_at);
+
+    if certifications.is_empty() {
+        Err("No certifications found for this referee".to_string())
+    } else {
+        Ok(certifications)
+    }
+}
+
+// Get the active certifications that expire within the given number of days
+#[ic_cdk::query]
+fn get_expiring_certifications(within_days: u32) -> Result<Vec<CertificationReminder>, String> {
+    let today = today();
+
+    let mut reminders: Vec<CertificationReminder> = REFEREE_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .flat_map(|(_, referee)| {
+                referee
+                    .certifications
+                    .iter()
+                    .filter(|certification| certification.revoked_at.is_none())
+                    .filter_map(|certification| {
+                        let expiry_date = parse_date(&certification.expiry_date).ok()?;
+                        let days_remaining = (expiry_date - today).num_days();
+                        (0..=within_days as i64).contains(&days_remaining).then(|| {
+                            CertificationReminder {
+                                referee_id: referee.id,
+                                referee_name: referee.name.clone(),
+                                email: referee.email.clone(),
+                                certification: certification.clone(),
+                                days_remaining,
+                            }
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    });
+
+    if reminders.is_empty() {
+        return Err("No certifications expire in this period".to_string());
+    }
+
+    reminders.sort_by_key(|reminder| reminder.days_remaining);
+
+    Ok(reminders)
+}
+
+// Get the officials assigned to a match
+#[ic_cdk::query]
+fn get_match_officials(match_id: u64) -> Result<Vec<RefereeAssignment>, String> {
+    let officials = get_assignments_for_match(match_id);
+    if officials.is_empty() {
+        Err("No officials assigned to this match".to_string())
+    } else {
+        Ok(officials)
+    }
+}
+
+// Collect the assignments for a match
+fn get_assignments_for_match(match_id: u64) -> Vec<RefereeAssignment> {
+    ASSIGNMENTS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .filter(|(_, assignment)| assignment.match_id == match_id)
+            .map(|(_, assignment)| assignment.clone())
+            .collect()
+    })
+}
+
+// Record a completed match in the history of everyone assigned to it, counting it once per official
+fn record_matches_officiated(match_obj: &Match) {
+    for assignment in get_assignments_for_match(match_obj.id) {
+        if record_officiating(assignment.referee_id, assignment.role, match_obj) {
+            REFEREE_STORAGE.with(|referees| {
+                let mut referees = referees.borrow_mut();
+                if let Some(mut referee) = referees.get(&assignment.referee_id) {
+                    referee.total_matches += 1;
+                    referees.insert(referee.id, referee);
+                }
+            });
+        }
+    }
+}
+
+// Store or refresh a match in a referee's history, returning whether it is a new entry
+fn record_officiating(referee_id: u64, role: OfficialRole, match_obj: &Match) -> bool {
+    let result = match &match_obj.result {
+        Some(result) => result,
+        None => return false,
+    };
+
+    let outcome = match result.score_team_a.cmp(&result.score_team_b) {
+        std::cmp::Ordering::Greater => MatchOutcome::HomeWin,
+        std::cmp::Ordering::Less => MatchOutcome::AwayWin,
+        std::cmp::Ordering::Equal => MatchOutcome::Draw,
+    };
+
+    let record = OfficiatingRecord {
+        referee_id,
+        match_id: match_obj.id,
+        role,
+        sport_type: match_obj.sport_type,
+        scheduled_date: match_obj.scheduled_date.clone(),
+        home_team_id: match_obj.home_team.id,
+        away_team_id: match_obj.away_team.id,
+        home_score: result.score_team_a,
+        away_score: result.score_team_b,
+        outcome,
+        recorded_at: ic_cdk::api::time(),
+    };
+
+    let key = OfficiatingKey {
+        referee_id,
+        match_id: match_obj.id,
+    };
+
+    OFFICIATING_HISTORY_STORAGE.with(|storage| storage.borrow_mut().insert(key, record).is_none())
+}
+
+/**
+ * Function to get a page of a referee's officiating history
+ * Records are ordered by match ID and optionally filtered by role and sport. Pass the returned `next_cursor`
+ * to fetch the following page; a `limit` of 0 uses the default page size of 20, and at most 100 records are returned.
+ */
+#[ic_cdk::query]
+fn get_officiating_history(
+    query: OfficiatingHistoryQuery,
+) -> Result<OfficiatingHistoryPage, String> {
+    get_referee(query.referee_id)?;
+
+    let limit = match query.limit {
+        0 => 20,
+        limit => limit.min(100) as usize,
+    };
+
+    let start = OfficiatingKey {
+        referee_id: query.referee_id,
+        match_id: query.cursor.map_or(0, |cursor| cursor.saturating_add(1)),
+    };
+    let end = OfficiatingKey {
+        referee_id: query.referee_id,
+        match_id: u64::MAX,
+    };
+
+    let mut records: Vec<OfficiatingRecord> = OFFICIATING_HISTORY_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .range(start..=end)
+            .map(|(_, record)| record)
+            .filter(|record| query.role.is_none() || query.role == Some(record.role))
+            .filter(|record| {
+                query.sport_type.is_none() || query.sport_type == Some(record.sport_type)
+            })
+            .take(limit + 1)
+            .collect()
+    });
+
+    // Fetching one record more than the limit tells whether there is another page
+    let next_cursor = if records.len() > limit {
+        records.truncate(limit);
+        records.last().map(|record| record.match_id)
+    } else {
+        None
+    };
+
+    Ok(OfficiatingHistoryPage {
+        records,
+        next_cursor,
+    })
+}
+
+#[ic_cdk::post_upgrade]
+fn post_upgrade() {
+    restore_payment_backend();
+    relocate_storage(MemoryId::new(3), &MATCHES_STORAGE);
+    relocate_storage(MemoryId::new(5), &TOURNAMENTS_STORAGE);
+    migrate_referee_history();
+    relocate_storage(MemoryId::new(4), &REFEREE_STORAGE);
+    relocate_storage(MemoryId::new(6), &LEAGUES_STORAGE);
+    migrate_report_sanctions();
+    migrate_drawn_results();
+    index_all_matches();
+}
+
+/*
+Move an entity out of a storage created with the original 1024-byte bound into the larger storage that replaced it
+A stable map's bound cannot be raised once it holds data, so an entity that outgrows its bound gets a new memory
+and is moved there on upgrade. Each entry is removed from the old memory as it moves, so later upgrades find
+nothing left to move. The old memory is never reused.
+*/
+fn relocate_storage<T>(
+    legacy_memory: MemoryId,
+    storage: &'static std::thread::LocalKey<RefCell<StableBTreeMap<u64, T, Memory>>>,
+) where
+    T: BoundedStorable + candid::CandidType + serde::Serialize + serde::de::DeserializeOwned,
+{
+    MEMORY_MANAGER.with(|m| {
+        let mut legacy: StableBTreeMap<u64, LegacyStored<T>, Memory> =
+            StableBTreeMap::init(m.borrow().get(legacy_memory));
+        let ids: Vec<u64> = legacy.iter().map(|(id, _)| id).collect();
+
+        for id in ids {
+            if let Some(LegacyStored(value)) = legacy.remove(&id) {
+                storage.with(|storage| storage.borrow_mut().insert(id, value));
+            }
+        }
+    });
+}
+
+/*
+Migrate match reports filed before cards were typed, whose disciplinary actions carry a free-text `sanction`
+The sanction becomes the card it names; one naming no card is kept in the offence and recorded as a yellow card.
+No cards are issued, since reports signed before then never counted towards suspensions.
+*/
+fn migrate_report_sanctions() {
+    let legacy: Vec<LegacyMatchReport> = MEMORY_MANAGER.with(|m| {
+        let storage: StableBTreeMap<u64, LegacyMatchReport, Memory> =
+            StableBTreeMap::init(m.borrow().get(MemoryId::new(10)));
+        storage
+            .iter()
+            .map(|(_, report)| report)
+            .filter(|report| {
+                report
+                    .disciplinary_actions
+                    .iter()
+                    .any(|action| action.card.is_none())
+            })
+            .collect()
+    });
+
+    for entry in legacy {
+        let disciplinary_actions = entry
+            .disciplinary_actions
+            .into_iter()
+            .map(|action| {
+                let sanction = action.sanction.unwrap_or_default();
+                let named_card = card_from_sanction(&sanction);
+                let offence =
+                    if action.card.is_none() && named_card.is_none() && !sanction.is_empty() {
+                        format!("{} ({})", action.offence, sanction)
+                    } else {
+                        action.offence
+                    };
+                DisciplinaryAction {
+                    player_id: action.player_id,
+                    team_id: action.team_id,
+                    minute: action.minute,
+                    offence,
+                    card: action.card.or(named_card).unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        let report = MatchReport {
+            match_id: entry.match_id,
+            referee_id: entry.referee_id,
+            home_score: entry.home_score,
+            away_score: entry.away_score,
+            extra_time: entry.extra_time,
+            disciplinary_actions,
+            injuries: entry.injuries,
+            incidents: entry.incidents,
+            attendance: entry.attendance,
+            notes: entry.notes,
+            filed_at: entry.filed_at,
+            signed_at: entry.signed_at,
+            superseded_result: entry.superseded_result,
+        };
+
+        REPORTS_STORAGE.with(|storage| storage.borrow_mut().insert(report.match_id, report));
+    }
+}
+
+/*
+Record drawn matches without a winner
+Results used to name a winner ID of 0 for a draw, which is also the ID of the first team an older install created.
+A result keeps the winner it names when that is one of its two teams and the result is not level;
+any other result takes the winner its score gives, which for a draw is none. Superseded results on reports are
+migrated the same way.
+*/
+fn migrate_drawn_results() {
+    let matches: Vec<Match> = MATCHES_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, match_obj)| match_obj)
+            .filter(|match_obj| match_obj.result.is_some())
+            .collect()
+    });
+
+    for mut match_obj in matches {
+        if let Some(mut result) = match_obj.result.take() {
+            let winner = settled_winner(&match_obj, &result);
+            if result.winner_team_id != winner {
+                result.winner_team_id = winner;
+                match_obj.result = Some(result);
+                MATCHES_STORAGE
+                    .with(|storage| storage.borrow_mut().insert(match_obj.id, match_obj));
+            }
+        }
+    }
+
+    let reports: Vec<MatchReport> = REPORTS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, report)| report)
+            .filter(|report| report.superseded_result.is_some())
+            .collect()
+    });
+
+    for mut report in reports {
+        let match_obj = match get_match(report.match_id) {
+            Ok(match_obj) => match_obj,
+            Err(_) => continue,
+        };
+        if let Some(mut result) = report.superseded_result.take() {
+            let winner = settled_winner(&match_obj, &result);
+            if result.winner_team_id != winner {
+                result.winner_team_id = winner;
+                report.superseded_result = Some(result);
+                REPORTS_STORAGE
+                    .with(|storage| storage.borrow_mut().insert(report.match_id, report));
+            }
+        }
+    }
+}
+
+// The winner a result recorded before draws had no winner should name: none for a draw, otherwise the team it
+// names when that is one of the two playing, or else the team its score puts ahead
+fn settled_winner(match_obj: &Match, result: &MatchResult) -> Option<u64> {
+    let scored = winner_from_scores(
+        match_obj,
+        result.score_team_a,
+        result.score_team_b,
+        result.extra_time.as_ref(),
+    );
+    let named = result
+        .winner_team_id
+        .filter(|winner| *winner == match_obj.home_team.id || *winner == match_obj.away_team.id);
+
+    scored.and(named).or(scored)
+}
+
+// The card a free-text sanction names, if any
+fn card_from_sanction(sanction: &str) -> Option<CardType> {
+    let sanction = sanction.to_lowercase();
+    if sanction.contains("red") {
+        Some(CardType::Red)
+    } else if sanction.contains("sin") {
+        Some(CardType::SinBin)
+    } else if sanction.contains("technical") {
+        Some(CardType::TechnicalFoul)
+    } else if sanction.contains("yellow") {
+        Some(CardType::Yellow)
+    } else {
+        None
+    }
+}
+
+/*
+Migrate referees stored with the untyped `matches_officiated` list of match IDs
+Each listed match that still exists becomes a typed history entry, keeping the role from its assignment.
+This reads the referees' original storage, so it runs before they are moved out of it, and the move
+saves each referee again without the legacy field. The officiated match count is left unchanged.
+*/
+fn migrate_referee_history() {
+    let legacy: Vec<LegacyRefereeHistory> = MEMORY_MANAGER.with(|m| {
+        let storage: StableBTreeMap<u64, LegacyRefereeHistory, Memory> =
+            StableBTreeMap::init(m.borrow().get(MemoryId::new(4)));
+        storage
+            .iter()
+            .map(|(_, referee)| referee)
+            .filter(|referee| referee.matches_officiated.is_some())
+            .collect()
+    });
+
+    for entry in legacy {
+        let match_ids = entry.matches_officiated.unwrap_or_default();
+        for match_id in match_ids.iter().filter_map(|id| id.parse::<u64>().ok()) {
+            if let Ok(match_obj) = get_match(match_id) {
+                let role = get_assignments_for_match(match_id)
+                    .iter()
+                    .find(|assignment| assignment.referee_id == entry.id)
+                    .map_or(OfficialRole::Referee, |assignment| assignment.role);
+                record_officiating(entry.id, role, &match_obj);
+            }
+        }
+    }
+}
+
+/**
+ * Function to rate a referee's performance in a match
+ * Coaches of either team and league officials acting as assessors may rate each official of a played match once.
+ * The referee's aggregated rating is recomputed on every submission.
+ */
+#[ic_cdk::update]
+fn submit_referee_rating(payload: SubmitRefereeRatingPayload) -> Result<RefereeRating, String> {
+    let rater = ensure_caller_role(&[UserRole::Coach, UserRole::LeagueOfficial])?;
+
+    if !(1..=10).contains(&payload.score) {
+        return Err("Score must be between 1 and 10".to_string());
+    }
+
+    if payload.comment.len() > 500 {
+        return Err("Comment cannot exceed 500 characters".to_string());
+    }
+
+    // Check if match exists and has been played
+    let match_obj = get_match(payload.match_id)?;
+
+    if match_obj.result.is_none() {
+        return Err("Referees can only be rated once the match result is submitted".to_string());
+    }
+
+    // Coaches may only rate matches their own team played in
+    if rater.role == UserRole::Coach
+        && !is_team_coach(match_obj.home_team.id, rater.id)
+        && !is_team_coach(match_obj.away_team.id, rater.id)
+    {
+        return Err("Coach is not part of either team in this match".to_string());
+    }
+
+    // League officials may only rate officials in the competitions they are appointed to
+    if rater.role == UserRole::LeagueOfficial {
+        ensure_match_official(&match_obj)?;
+    }
+
+    // Ensure the referee officiated the match
+    if !get_assignments_for_match(payload.match_id)
+        .iter()
+        .any(|assignment| assignment.referee_id == payload.referee_id)
+    {
+        return Err("Referee did not officiate this match".to_string());
+    }
+
+    let mut referee = get_referee(payload.referee_id)?;
+
+    if referee.owner == rater.owner {
+        return Err("Referees cannot rate themselves".to_string());
+    }
+
+    // Only one rating per rater, referee and match
+    let already_rated = RATINGS_STORAGE.with(|storage| {
+        storage.borrow().iter().any(|(_, rating)| {
+            rating.match_id == payload.match_id
+                && rating.referee_id == payload.referee_id
+                && rating.rater_id == rater.id
+        })
+    });
+
+    if already_rated {
+        return Err("You have already rated this referee for this match".to_string());
+    }
+
+    let id = generate_uuid();
+
+    let rating = RefereeRating {
+        id,
+        match_id: payload.match_id,
+        referee_id: payload.referee_id,
+        rater_id: rater.id,
+        rater_role: rater.role,
+        sport_type: match_obj.sport_type,
+        season: season_from_date(&match_obj.scheduled_date),
+        score: payload.score,
+        comment: payload.comment,
+        submitted_at: ic_cdk::api::time(),
+    };
+
+    RATINGS_STORAGE.with(|ratings| ratings.borrow_mut().insert(id, rating.clone()));
+
+    // Recompute the referee's aggregated rating
+    referee.total_rating += rating.score as f32;
+    referee.total_ratings += 1;
+    referee.performance_rating = referee.total_rating / referee.total_ratings as f32;
+    REFEREE_STORAGE.with(|referees| referees.borrow_mut().insert(referee.id, referee));
+
+    Ok(rating)
+}
+
+// Get all ratings submitted for a referee
+#[ic_cdk::query]
+fn get_referee_ratings(referee_id: u64) -> Result<Vec<RefereeRating>, String> {
+    let ratings = get_ratings_for_referee(referee_id);
+    if ratings.is_empty() {
+        Err("No ratings found for this referee".to_string())
+    } else {
+        Ok(ratings)
+    }
+}
+
+// Get a referee's ratings aggregated overall, per sport and per season
+#[ic_cdk::query]
+fn get_referee_rating_summary(referee_id: u64) -> Result<RefereeRatingSummary, String> {
+    let referee = get_referee(referee_id)?;
+    let ratings = get_ratings_for_referee(referee_id);
+
+    let mut by_sport: Vec<SportRatingAggregate> = Vec::new();
+    let mut by_season: Vec<SeasonRatingAggregate> = Vec::new();
+
+    for rating in &ratings {
+        match by_sport
+            .iter_mut()
+            .find(|entry| entry.sport_type == rating.sport_type)
+        {
+            Some(entry) => add_to_aggregate(&mut entry.aggregate, rating.score),
+            None => {
+                let mut aggregate = RatingAggregate::default();
+                add_to_aggregate(&mut aggregate, rating.score);
+                by_sport.push(SportRatingAggregate {
+                    sport_type: rating.sport_type,
+                    aggregate,
+                });
+            }
+        }
+
+        match by_season
+            .iter_mut()
+            .find(|entry| entry.season == rating.season)
+        {
+            Some(entry) => add_to_aggregate(&mut entry.aggregate, rating.score),
+            None => {
+                let mut aggregate = RatingAggregate::default();
+                add_to_aggregate(&mut aggregate, rating.score);
+                by_season.push(SeasonRatingAggregate {
+                    season: rating.season.clone(),
+                    aggregate,
+                });
+            }
+        }
+    }
+
+    by_season.sort_by(|a, b| a.season.cmp(&b.season));
+
+    Ok(RefereeRatingSummary {
+        referee_id,
+        referee_name: referee.name,
+        overall: aggregate_ratings(ratings.iter()),
+        by_sport,
+        by_season,
+    })
+}
+
+/**
+ * Function to get the officials leaderboard
+ * Referees are ranked by average rating, then by number of ratings, optionally restricted to a sport and/or season.
+ * Referees with fewer than `min_ratings` matching ratings are left out; a `limit` of 0 returns every row.
+ */
+#[ic_cdk::query]
+fn get_referee_leaderboard(
+    query: RefereeLeaderboardQuery,
+) -> Result<Vec<RefereeLeaderboardEntry>, String> {
+    let ratings: Vec<RefereeRating> = RATINGS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, rating)| rating)
+            .filter(|rating| {
+                query.sport_type.is_none() || query.sport_type == Some(rating.sport_type)
+            })
+            .filter(|rating| {
+                query.season.is_none() || query.season.as_ref() == Some(&rating.season)
+            })
+            .collect()
+    });
+
+    let mut entries: Vec<RefereeLeaderboardEntry> = REFEREE_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, referee)| RefereeLeaderboardEntry {
+                rank: 0,
+                referee_id: referee.id,
+                referee_name: referee.name.clone(),
+                aggregate: aggregate_ratings(
+                    ratings
+                        .iter()
+                        .filter(|rating| rating.referee_id == referee.id),
+                ),
+                total_matches: referee.total_matches,
+            })
+            .filter(|entry| {
+                entry.aggregate.rating_count > 0
+                    && entry.aggregate.rating_count >= query.min_ratings
+            })
+            .collect()
+    });
+
+    if entries.is_empty() {
+        return Err("No rated referees found".to_string());
+    }
+
+    entries.sort_by(|a, b| {
+        b.aggregate
+            .average_rating
+            .total_cmp(&a.aggregate.average_rating)
+            .then(b.aggregate.rating_count.cmp(&a.aggregate.rating_count))
+            .then(a.referee_id.cmp(&b.referee_id))
+    });
+
+    if query.limit > 0 {
+        entries.truncate(query.limit as usize);
+    }
+
+    for (index, entry) in entries.iter_mut().enumerate() {
+        entry.rank = index as u32 + 1;
+    }
+
+    Ok(entries)
+}
+
+// Collect the ratings submitted for a referee
+fn get_ratings_for_referee(referee_id: u64) -> Vec<RefereeRating> {
+    RATINGS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .filter(|(_, rating)| rating.referee_id == referee_id)
+            .map(|(_, rating)| rating.clone())
+            .collect()
+    })
+}
+
+// Add a single score to a rating aggregate
+fn add_to_aggregate(aggregate: &mut RatingAggregate, score: u8) {
+    aggregate.rating_count += 1;
+    aggregate.total_rating += score as f32;
+    aggregate.average_rating = aggregate.total_rating / aggregate.rating_count as f32;
+}
+
+// Aggregate a set of ratings
+fn aggregate_ratings<'a>(ratings: impl Iterator<Item = &'a RefereeRating>) -> RatingAggregate {
+    let mut aggregate = RatingAggregate::default();
+    for rating in ratings {
+        add_to_aggregate(&mut aggregate, rating.score);
+    }
+    aggregate
+}
+
+/**
+ * Function for the match referee to file or amend their match report
+ * Only the official assigned to the match in the Referee role may file the report, and a signed report can no longer be amended.
+ * Disciplinary actions and injuries must name a player of the home or away team.
+ */
+#[ic_cdk::update]
+fn file_match_report(payload: FileMatchReportPayload) -> Result<MatchReport, String> {
+    let match_obj = get_match(payload.match_id)?;
+    let referee = get_caller_match_referee(payload.match_id)?;
+
+    if let Some(existing) = REPORTS_STORAGE.with(|storage| storage.borrow().get(&payload.match_id))
+    {
+        if existing.signed_at.is_some() {
+            return Err("Match report has been signed and can no longer be amended".to_string());
+        }
+    }
+
+    // Validate the report contents
+    if payload.disciplinary_actions.len() > MAX_REPORT_ENTRIES
+        || payload.injuries.len() > MAX_REPORT_ENTRIES
+        || payload.incidents.len() > MAX_REPORT_ENTRIES
+    {
+        return Err(format!(
+            "A match report can list at most {} disciplinary actions, injuries and incidents each",
+            MAX_REPORT_ENTRIES
+        ));
+    }
+
+    if payload.notes.len() > MAX_REPORT_NOTES_LENGTH {
+        return Err(format!(
+            "Notes cannot exceed {} characters",
+            MAX_REPORT_NOTES_LENGTH
+        ));
+    }
+
+    let descriptions = payload
+        .disciplinary_actions
+        .iter()
+        .map(|action| &action.offence)
+        .chain(payload.injuries.iter().map(|injury| &injury.description))
+        .chain(
+            payload
+                .incidents
+                .iter()
+                .map(|incident| &incident.description),
+        );
+
+    for description in descriptions {
+        if description.trim().is_empty() || description.len() > MAX_REPORT_DESCRIPTION_LENGTH {
+            return Err(format!(
+                "Report descriptions must be between 1 and {} characters",
+                MAX_REPORT_DESCRIPTION_LENGTH
+            ));
+        }
+    }
+
+    for action in &payload.disciplinary_actions {
+        check_match_player(&match_obj, action.team_id, action.player_id)?;
+    }
+
+    for injury in &payload.injuries {
+        check_match_player(&match_obj, injury.team_id, injury.player_id)?;
+    }
+
+    let report = MatchReport {
+        match_id: payload.match_id,
+        referee_id: referee.id,
+        home_score: payload.home_score,
+        away_score: payload.away_score,
+        extra_time: payload.extra_time,
+        disciplinary_actions: payload.disciplinary_actions,
+        injuries: payload.injuries,
+        incidents: payload.incidents,
+        attendance: payload.attendance,
+        notes: payload.notes,
+        filed_at: ic_cdk::api::time(),
+        signed_at: None,
+        superseded_result: None,
+    };
+
+    validate_extra_time(&report_result(&report, &match_obj))?;
+
+    REPORTS_STORAGE.with(|storage| storage.borrow_mut().insert(report.match_id, report.clone()));
+
+    Ok(report)
+}
+
+/**
+ * Function for the match referee to sign their report
+ * Signing makes the report immutable and the authoritative record of the match: its score replaces any result
+ * submitted by the teams, and a disagreeing team-submitted result is kept on the report for reference.
+ */
+#[ic_cdk::update]
+fn sign_match_report(match_id: u64) -> Result<MatchReport, String> {
+    get_caller_match_referee(match_id)?;
+
+    let mut report = REPORTS_STORAGE
+        .with(|storage| storage.borrow().get(&match_id))
+        .ok_or_else(|| "No match report has been filed for this match".to_string())?;
+
+    if report.signed_at.is_some() {
+        return Err("Match report has already been signed".to_string());
+    }
+
+    let mut match_obj = get_match(match_id)?;
+    ensure_match_season_open(&match_obj)?;
+
+    let reported_result = report_result(&report, &match_obj);
+
+    // The signed result must meet the same rules as a submitted one
+    validate_extra_time(&reported_result)?;
+    validate_forfeit(&match_obj, &reported_result)?;
+    validate_knockout_result(&match_obj, &reported_result)?;
+
+    let previous_result = match_obj.result.replace(reported_result.clone());
+
+    // Keep the team-submitted result when the referee's report disagrees with it
+    if let Some(previous) = &previous_result {
+        if previous.score_team_a != reported_result.score_team_a
+            || previous.score_team_b != reported_result.score_team_b
+            || previous.winner_team_id != reported_result.winner_team_id
+        {
+            report.superseded_result = Some(previous.clone());
+        }
+    }
+
+    report.signed_at = Some(ic_cdk::api::time());
+
+    MATCHES_STORAGE.with(|storage| storage.borrow_mut().insert(match_id, match_obj.clone()));
+    REPORTS_STORAGE.with(|storage| storage.borrow_mut().insert(match_id, report.clone()));
+
+    // Complete the match now if no result had been submitted before. Otherwise refresh the officials' history
+    // and move the tournament on again, as the report may have changed who went through
+    if previous_result.is_none() {
+        complete_match(&match_obj);
+    } else {
+        record_matches_officiated(&match_obj);
+
+        // Trapping rolls back the whole call, so a correction that cannot be applied leaves nothing half-updated
+        if let Err(msg) = advance_tournament(&match_obj) {
+            ic_cdk::api::trap(&msg);
+        }
+    }
+
+    // Record the cards shown, which may trigger suspensions
+    record_report_cards(&report, &match_obj);
+
+    Ok(report)
+}
+
+// Get the referee's report on a match
+#[ic_cdk::query]
+fn get_match_report(match_id: u64) -> Result<MatchReport, String> {
+    REPORTS_STORAGE.with(|storage| match storage.borrow().get(&match_id) {
+        Some(report) => Ok(report.clone()),
+        None => Err(format!(
+            "No match report found for match with ID {}",
+            match_id
+        )),
+    })
+}
+
+// Fetch the referee owned by the caller that is assigned to a match in the Referee role
+fn get_caller_match_referee(match_id: u64) -> Result<Referee, String> {
+    let caller = caller();
+
+    get_assignments_for_match(match_id)
+        .iter()
+        .filter(|assignment| assignment.role == OfficialRole::Referee)
+        .filter_map(|assignment| get_referee(assignment.referee_id).ok())
+        .find(|referee| referee.owner == caller)
+        .ok_or_else(|| "Only the referee of this match can file its report".to_string())
+}
+
+// Ensure a player belongs to the given team and that the team plays in the match
+fn check_match_player(match_obj: &Match, team_id: u64, player_id: u64) -> Result<(), String> {
+    if team_id != match_obj.home_team.id && team_id != match_obj.away_team.id {
+        return Err(format!(
+            "Team with ID {} is not playing in this match",
+            team_id
+        ));
+    }
+
+    let team = get_team(team_id)?;
+
+    if !team.members.contains(&player_id) {
+        return Err(format!(
+            "Player with ID {} is not a member of {}",
+            player_id, team.name
+        ));
+    }
+
+    Ok(())
+}
+
+// The result a match report records, keeping any forfeit of the submitted result and its extra time unless the report has its own
+fn report_result(report: &MatchReport, match_obj: &Match) -> MatchResult {
+    let submitted = match_obj.result.as_ref();
+    let extra_time = report
+        .extra_time
+        .clone()
+        .or_else(|| submitted.and_then(|result| result.extra_time.clone()));
+
+    MatchResult {
+        winner_team_id: winner_from_scores(
+            match_obj,
+            report.home_score,
+            report.away_score,
+            extra_time.as_ref(),
+        ),
+        score_team_a: report.home_score,
+        score_team_b: report.away_score,
+        // The full notes stay on the report, which has room for them; the match only records where its result came from
+        notes: "Recorded from the referee's signed match report".to_string(),
+        extra_time,
+        forfeited_by: submitted.and_then(|result| result.forfeited_by),
+    }
+}
+
+// Determine the winner of a match from its score, then any penalty shoot-out; a drawn match has no winner
+fn winner_from_scores(
+    match_obj: &Match,
+    home_score: u32,
+    away_score: u32,
+    extra_time: Option<&ExtraTime>,
+) -> Option<u64> {
+    let penalties = extra_time
+        .and_then(|extra_time| extra_time.penalties.as_ref())
+        .map(|penalties| penalties.score_team_a.cmp(&penalties.score_team_b))
+        .unwrap_or(std::cmp::Ordering::Equal);
+
+    match home_score.cmp(&away_score).then(penalties) {
+        std::cmp::Ordering::Greater => Some(match_obj.home_team.id),
+        std::cmp::Ordering::Less => Some(match_obj.away_team.id),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+// Credit the officials of a completed match, count it towards suspensions being served, record the fees owed
+// and move its tournament on
+fn complete_match(match_obj: &Match) {
+    record_matches_officiated(match_obj);
+    serve_suspensions(match_obj);
+    generate_official_payments(match_obj);
+
+    // Trapping rolls back the whole call, so a failed advancement never leaves a half-updated bracket
+    if let Err(msg) = advance_tournament(match_obj) {
+        ic_cdk::api::trap(&msg);
+    }
+}
+
+/**
+ * Function to configure how accumulated cards turn into suspensions
+ * Only administrators may set rules. Every `threshold` cards of the type a player receives in a season
+ * suspend them for the next `suspension_matches` matches of their team in that sport.
+ */
+#[ic_cdk::update]
+fn set_disciplinary_rule(payload: SetDisciplinaryRulePayload) -> Result<DisciplinaryRule, String> {
+    let administrator = ensure_caller_role(&[UserRole::Administrator])?;
+
+    if payload.threshold == 0 || payload.suspension_matches == 0 {
+        return Err("Threshold and suspension length must both be at least 1".to_string());
+    }
+
+    // Replace the existing rule for the sport and card type
+    let existing_id = DISCIPLINARY_RULES_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .find(|(_, rule)| {
+                rule.sport_type == payload.sport_type && rule.card_type == payload.card_type
+            })
+            .map(|(id, _)| id)
+    });
+
+    let rule = DisciplinaryRule {
+        id: existing_id.unwrap_or_else(generate_uuid),
+        sport_type: payload.sport_type,
+        card_type: payload.card_type,
+        threshold: payload.threshold,
+        suspension_matches: payload.suspension_matches,
+        updated_by: administrator.id,
+        updated_at: ic_cdk::api::time(),
+    };
+
+    DISCIPLINARY_RULES_STORAGE.with(|storage| storage.borrow_mut().insert(rule.id, rule.clone()));
+
+    Ok(rule)
+}
+
+// Get all card accumulation rules
+#[ic_cdk::query]
+fn get_disciplinary_rules() -> Result<Vec<DisciplinaryRule>, String> {
+    DISCIPLINARY_RULES_STORAGE.with(|storage| {
+        let rules: Vec<DisciplinaryRule> = storage
+            .borrow()
+            .iter()
+            .map(|(_, rule)| rule.clone())
+            .collect();
+        if rules.is_empty() {
+            Err("No disciplinary rules found".to_string())
+        } else {
+            Ok(rules)
+        }
+    })
+}
+
+// Get all cards shown to a player
+#[ic_cdk::query]
+fn get_player_cards(player_id: u64) -> Result<Vec<Card>, String> {
+    CARDS_STORAGE.with(|storage| {
+        let cards: Vec<Card> = storage
+            .borrow()
+            .iter()
+            .filter(|(_, card)| card.player_id == player_id)
+            .map(|(_, card)| card.clone())
+            .collect();
+        if cards.is_empty() {
+            Err("No cards found for this player".to_string())
+        } else {
+            Ok(cards)
+        }
+    })
+}
+
+// Get all suspensions of a player, served or not
+#[ic_cdk::query]
+fn get_player_suspensions(player_id: u64) -> Result<Vec<Suspension>, String> {
+    SUSPENSIONS_STORAGE.with(|storage| {
+        let suspensions: Vec<Suspension> = storage
+            .borrow()
+            .iter()
+            .filter(|(_, suspension)| suspension.player_id == player_id)
+            .map(|(_, suspension)| suspension.clone())
+            .collect();
+        if suspensions.is_empty() {
+            Err("No suspensions found for this player".to_string())
+        } else {
+            Ok(suspensions)
+        }
+    })
+}
+
+// Get all suspensions that still have matches left to serve
+#[ic_cdk::query]
+fn get_active_suspensions() -> Result<Vec<Suspension>, String> {
+    SUSPENSIONS_STORAGE.with(|storage| {
+        let suspensions: Vec<Suspension> = storage
+            .borrow()
+            .iter()
+            .filter(|(_, suspension)| is_suspension_active(suspension))
+            .map(|(_, suspension)| suspension.clone())
+            .collect();
+        if suspensions.is_empty() {
+            Err("No active suspensions found".to_string())
+        } else {
+            Ok(suspensions)
+        }
+    })
+}
+
+/**
+ * Function to submit a team's lineup for a match
+ * Only the team's coaches and administrators may submit it. Every starter and substitute must be a member of the team
+ * without an active suspension in the sport. Submitting again replaces the previous lineup.
+ */
+#[ic_cdk::update]
+fn submit_lineup(payload: SubmitLineupPayload) -> Result<Lineup, String> {
+    let submitter = ensure_caller_role(&[UserRole::Coach, UserRole::Administrator])?;
+
+    if submitter.role == UserRole::Coach && !is_team_coach(payload.team_id, submitter.id) {
+        return Err("Only the team's coaches can submit its lineup".to_string());
+    }
+
+    let match_obj = get_match(payload.match_id)?;
+
+    if match_obj.result.is_some() {
+        return Err("Cannot submit a lineup for a match that has already been played".to_string());
+    }
+
+    if payload.starters.is_empty() {
+        return Err("A lineup needs at least one starter".to_string());
+    }
+
+    if let Some(rules) = match_league_rules(&match_obj) {
+        check_lineup_size(&rules.roster, &payload)?;
+    }
+
+    let players: Vec<u64> = payload
+        .starters
+        .iter()
+        .chain(payload.substitutes.iter())
+        .copied()
+        .collect();
+
+    for (index, player_id) in players.iter().enumerate() {
+        if players[..index].contains(player_id) {
+            return Err(format!(
+                "Player with ID {} is listed more than once",
+                player_id
+            ));
+        }
+
+        check_match_player(&match_obj, payload.team_id, *player_id)?;
+
+        // Suspended players cannot be fielded
+        if let Some(suspension) = get_active_suspension(*player_id, match_obj.sport_type) {
+            return Err(format!(
+                "Player with ID {} is suspended for {} more match(es): {}",
+                player_id,
+                suspension.matches_total - suspension.served_match_ids.len() as u32,
+                suspension.reason
+            ));
+        }
+    }
+
+    // Replace any lineup already submitted for the team
+    let existing_id = LINEUPS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .find(|(_, lineup)| {
+                lineup.match_id == payload.match_id && lineup.team_id == payload.team_id
+            })
+            .map(|(id, _)| id)
+    });
+
+    let lineup = Lineup {
+        id: existing_id.unwrap_or_else(generate_uuid),
+        match_id: payload.match_id,
+        team_id: payload.team_id,
+        starters: payload.starters,
+        substitutes: payload.substitutes,
+        submitted_by: submitter.id,
+        submitted_at: ic_cdk::api::time(),
+    };
+
+    LINEUPS_STORAGE.with(|storage| storage.borrow_mut().insert(lineup.id, lineup.clone()));
+
+    Ok(lineup)
+}
+
+// Get the lineups submitted for a match
+#[ic_cdk::query]
+fn get_match_lineups(match_id: u64) -> Result<Vec<Lineup>, String> {
+    LINEUPS_STORAGE.with(|storage| {
+        let lineups: Vec<Lineup> = storage
+            .borrow()
+            .iter()
+            .filter(|(_, lineup)| lineup.match_id == match_id)
+            .map(|(_, lineup)| lineup.clone())
+            .collect();
+        if lineups.is_empty() {
+            Err("No lineups found for this match".to_string())
+        } else {
+            Ok(lineups)
+        }
+    })
+}
+
+// Check whether a suspension still has matches left to serve
+fn is_suspension_active(suspension: &Suspension) -> bool {
+    (suspension.served_match_ids.len() as u32) < suspension.matches_total
+}
+
+// Get a player's active suspension in a sport, if any
+fn get_active_suspension(player_id: u64, sport_type: SportType) -> Option<Suspension> {
+    SUSPENSIONS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, suspension)| suspension)
+            .find(|suspension| {
+                suspension.player_id == player_id
+                    && suspension.sport_type == sport_type
+                    && is_suspension_active(suspension)
+            })
+    })
+}
+
+// Count a completed match towards the active suspensions of both teams' players
+fn serve_suspensions(match_obj: &Match) {
+    let teams = [match_obj.home_team.id, match_obj.away_team.id];
+
+    let serving: Vec<Suspension> = SUSPENSIONS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, suspension)| suspension)
+            .filter(|suspension| {
+                teams.contains(&suspension.team_id)
+                    && suspension.sport_type == match_obj.sport_type
+                    && suspension.source_match_id != match_obj.id
+                    && !suspension.served_match_ids.contains(&match_obj.id)
+                    && is_suspension_active(suspension)
+            })
+            .collect()
+    });
+
+    for mut suspension in serving {
+        suspension.served_match_ids.push(match_obj.id);
+        SUSPENSIONS_STORAGE.with(|storage| storage.borrow_mut().insert(suspension.id, suspension));
+    }
+}
+
+/*
+Record the cards shown in a signed match report and apply the accumulation rules
+The thresholds in the rules of the match's league replace the global rules for the card types they cover, and only
+count cards shown in that league's matches. Each time a player's count of a card type in the season reaches a multiple of the rule's threshold,
+a suspension covering the cards that made up that multiple is created.
+*/
+fn record_report_cards(report: &MatchReport, match_obj: &Match) {
+    // Cards accumulate over the match's season, or over the calendar year for matches outside a season
+    let season = season_from_date(&match_obj.scheduled_date);
+    let season_name = match match_obj.season_id.and_then(|id| get_season(id).ok()) {
+        Some(season) => season.name,
+        None => season.clone(),
+    };
+    let league_rules = match_league_rules(match_obj);
+
+    for action in &report.disciplinary_actions {
+        let card = Card {
+            id: generate_uuid(),
+            match_id: match_obj.id,
+            player_id: action.player_id,
+            team_id: action.team_id,
+            sport_type: match_obj.sport_type,
+            season: season.clone(),
+            season_id: match_obj.season_id,
+            card_type: action.card,
+            minute: action.minute,
+            offence: action.offence.clone(),
+            issued_by: report.referee_id,
+            issued_at: ic_cdk::api::time(),
+        };
+
+        CARDS_STORAGE.with(|storage| storage.borrow_mut().insert(card.id, card.clone()));
+
+        let rule =
+            DISCIPLINARY_RULES_STORAGE.with(|storage| {
+                storage.borrow().iter().map(|(_, rule)| rule).find(|rule| {
+                    rule.sport_type == card.sport_type && rule.card_type == card.card_type
+                })
+            });
+
+        // A league's own threshold for the card type takes precedence over the global rule
+        let league_threshold = league_rules
+            .iter()
+            .flat_map(|rules| rules.disciplinary_thresholds.iter())
+            .find(|threshold| threshold.card_type == card.card_type);
+        let threshold = league_threshold
+            .map(|threshold| (threshold.threshold, threshold.suspension_matches))
+            .or(rule.map(|rule| (rule.threshold, rule.suspension_matches)));
+
+        let (threshold, suspension_matches) = match threshold {
+            Some(threshold) => threshold,
+            None => continue,
+        };
+
+        let card_ids: Vec<u64> = CARDS_STORAGE.with(|storage| {
+            storage
+                .borrow()
+                .iter()
+                .filter(|(_, other)| {
+                    other.player_id == card.player_id
+                        && other.sport_type == card.sport_type
+                        && other.card_type == card.card_type
+                        && other.season_id == card.season_id
+                        && (card.season_id.is_some() || other.season == card.season)
+                        && (league_threshold.is_none()
+                            || get_match(other.match_id).is_ok_and(|other_match| {
+                                other_match.league_id == match_obj.league_id
+                            }))
+                })
+                .map(|(id, _)| id)
+                .collect()
+        });
+
+        if card_ids.len() as u32 % threshold != 0 {
+            continue;
+        }
+
+        let suspension = Suspension {
+            id: generate_uuid(),
+            player_id: card.player_id,
+            team_id: card.team_id,
+            sport_type: card.sport_type,
+            reason: format!(
+                "{} {:?} card(s) in the {} season",
+                card_ids.len(),
+                card.card_type,
+                season_name
+            ),
+            source_match_id: match_obj.id,
+            card_ids: card_ids[card_ids.len() - threshold as usize..].to_vec(),
+            matches_total: suspension_matches,
+            served_match_ids: Vec::new(),
+            created_at: ic_cdk::api::time(),
+        };
+
+        SUSPENSIONS_STORAGE.with(|storage| storage.borrow_mut().insert(suspension.id, suspension));
+    }
+}
+
+// Function for administrators to set the fee and travel allowance of an official role in a sport
+#[ic_cdk::update]
+fn set_official_fee_rate(payload: SetFeeRatePayload) -> Result<OfficialFeeRate, String> {
+    ensure_caller_role(&[UserRole::Administrator])?;
+
+    // Replace the existing rate for the sport and role
+    let existing_id = FEE_RATES_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .find(|(_, rate)| rate.sport_type == payload.sport_type && rate.role == payload.role)
+            .map(|(id, _)| id)
+    });
+
+    let rate = OfficialFeeRate {
+        id: existing_id.unwrap_or_else(generate_uuid),
+        sport_type: payload.sport_type,
+        role: payload.role,
+        match_fee: payload.match_fee,
+        travel_allowance: payload.travel_allowance,
+    };
+
+    FEE_RATES_STORAGE.with(|storage| storage.borrow_mut().insert(rate.id, rate.clone()));
+
+    Ok(rate)
+}
+
+// Get all official fee rates
+#[ic_cdk::query]
+fn get_official_fee_rates() -> Result<Vec<OfficialFeeRate>, String> {
+    FEE_RATES_STORAGE.with(|storage| {
+        let rates: Vec<OfficialFeeRate> = storage
+            .borrow()
+            .iter()
+            .map(|(_, rate)| rate.clone())
+            .collect();
+        if rates.is_empty() {
+            Err("No fee rates found".to_string())
+        } else {
+            Ok(rates)
+        }
+    })
+}
+
+/*
+Record the fees owed to the officials of a completed match
+Each assignment earns the match fee and travel allowance configured for its sport and role, billed to the home team's university.
+Assignments that already have ledger entries are skipped.
+*/
+fn generate_official_payments(match_obj: &Match) {
+    for assignment in get_assignments_for_match(match_obj.id) {
+        let already_recorded = PAYMENTS_STORAGE.with(|storage| {
+            storage
+                .borrow()
+                .iter()
+                .any(|(_, entry)| entry.assignment_id == assignment.id)
+        });
+
+        if already_recorded {
+            continue;
+        }
+
+        let rate = FEE_RATES_STORAGE.with(|storage| {
+            storage.borrow().iter().map(|(_, rate)| rate).find(|rate| {
+                rate.sport_type == match_obj.sport_type && rate.role == assignment.role
+            })
+        });
+
+        let rate = match rate {
+            Some(rate) => rate,
+            None => continue,
+        };
+
+        for (kind, amount) in [
+            (PaymentKind::MatchFee, rate.match_fee),
+            (PaymentKind::TravelAllowance, rate.travel_allowance),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+
+            let entry = PaymentEntry {
+                id: generate_uuid(),
+                referee_id: assignment.referee_id,
+                match_id: match_obj.id,
+                assignment_id: assignment.id,
+                kind,
+                amount,
+                billed_university: match_obj.home_team.university.clone(),
+                status: PaymentStatus::Owed,
+                created_at: ic_cdk::api::time(),
+                approved_by: None,
+                approved_at: None,
+                paid_at: None,
+                payment_reference: None,
+                failure_reason: None,
+            };
+
+            PAYMENTS_STORAGE.with(|storage| storage.borrow_mut().insert(entry.id, entry));
+        }
+    }
+}
+
+// Function for administrators to approve an owed or failed payment for paying out
+#[ic_cdk::update]
+fn approve_payment(entry_id: u64) -> Result<PaymentEntry, String> {
+    let administrator = ensure_caller_role(&[UserRole::Administrator])?;
+
+    let mut entry = PAYMENTS_STORAGE
+        .with(|storage| storage.borrow().get(&entry_id))
+        .ok_or_else(|| format!("Payment with ID {} not found", entry_id))?;
+
+    if entry.status != PaymentStatus::Owed && entry.status != PaymentStatus::Failed {
+        return Err(format!(
+            "Only owed or failed payments can be approved, this payment is {:?}",
+            entry.status
+        ));
+    }
+
+    entry.status = PaymentStatus::Approved;
+    entry.approved_by = Some(administrator.id);
+    entry.approved_at = Some(ic_cdk::api::time());
+    entry.failure_reason = None;
+
+    PAYMENTS_STORAGE.with(|storage| storage.borrow_mut().insert(entry.id, entry.clone()));
+
+    Ok(entry)
+}
+
+/**
+ * Function for administrators to choose the backend officials are paid through
+ * The choice is kept in stable memory and the backend is installed again after every upgrade.
+ */
+#[ic_cdk::update]
+fn set_payment_backend(backend: PaymentBackendKind) -> Result<PaymentSettings, String> {
+    let administrator = ensure_caller_role(&[UserRole::Administrator])?;
+
+    select_payment_backend(backend, administrator.id, ic_cdk::api::time())
+}
+
+// Store the chosen payment backend and install it
+fn select_payment_backend(
+    backend: PaymentBackendKind,
+    set_by: u64,
+    now: u64,
+) -> Result<PaymentSettings, String> {
+    if let PaymentBackendKind::Sandbox { payout_limit: 0 } = backend {
+        return Err("The sandbox payout limit must be above 0".to_string());
+    }
+
+    let settings = PaymentSettings {
+        backend: Some(backend),
+        set_by: Some(set_by),
+        set_at: Some(now),
+    };
+
+    PAYMENT_SETTINGS
+        .with(|cell| cell.borrow_mut().set(settings.clone()))
+        .map_err(|_| "Cannot store the payment settings".to_string())?;
+    restore_payment_backend();
+
+    Ok(settings)
+}
+
+// Install the payment backend stored in the payment settings, if one has been chosen
+fn restore_payment_backend() {
+    let backend: Option<Box<dyn PaymentBackend>> =
+        match PAYMENT_SETTINGS.with(|cell| cell.borrow().get().backend) {
+            Some(PaymentBackendKind::Manual) => Some(Box::new(ManualPaymentBackend)),
+            Some(PaymentBackendKind::Sandbox { payout_limit }) => {
+                Some(Box::new(SandboxPaymentBackend { payout_limit }))
+            }
+            None => None,
+        };
+
+    PAYMENT_BACKEND.with(|current| *current.borrow_mut() = backend);
+}
+
+// Get the payment backend chosen for paying officials
+#[ic_cdk::query]
+fn get_payment_settings() -> PaymentSettings {
+    PAYMENT_SETTINGS.with(|cell| cell.borrow().get().clone())
+}
+
+/**
+ * Function to pay out every approved payment through the payment backend
+ * Completed payments are marked paid with the backend's reference, failed ones keep the reason and can be approved again.
+ */
+#[ic_cdk::update]
+fn process_approved_payments() -> Result<Vec<PaymentEntry>, String> {
+    ensure_caller_role(&[UserRole::Administrator])?;
+
+    pay_approved_payments(ic_cdk::api::time())
+}
+
+// Pay every approved payment through the installed payment backend, refusing while none is installed
+fn pay_approved_payments(now: u64) -> Result<Vec<PaymentEntry>, String> {
+    if PAYMENT_BACKEND.with(|backend| backend.borrow().is_none()) {
+        return Err(
+            "No payment backend is configured, so payments cannot be processed; an administrator can choose one with set_payment_backend"
+                .to_string(),
+        );
+    }
+
+    let approved: Vec<PaymentEntry> = PAYMENTS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, entry)| entry)
+            .filter(|entry| entry.status == PaymentStatus::Approved)
+            .collect()
+    });
+
+    if approved.is_empty() {
+        return Err("No approved payments to process".to_string());
+    }
+
+    let mut processed = Vec::new();
+
+    for mut entry in approved {
+        let outcome = match get_referee(entry.referee_id) {
+            Ok(payee) => PAYMENT_BACKEND.with(|backend| match backend.borrow().as_ref() {
+                Some(backend) => backend.pay(&entry, &payee),
+                None => Error::PaymentFailed {
+                    msg: "No payment backend is configured".to_string(),
+                },
+            }),
+            Err(msg) => Error::PaymentFailed { msg },
+        };
+
+        match outcome {
+            Error::PaymentCompleted { msg } => {
+                entry.status = PaymentStatus::Paid;
+                entry.paid_at = Some(now);
+                entry.payment_reference = Some(msg);
+            }
+            Error::PaymentFailed { msg } => {
+                entry.status = PaymentStatus::Failed;
+                entry.failure_reason = Some(msg);
+            }
+            _ => {
+                entry.status = PaymentStatus::Failed;
+                entry.failure_reason =
+                    Some("Payment backend returned an unexpected outcome".to_string());
+            }
+        }
+
+        PAYMENTS_STORAGE.with(|storage| storage.borrow_mut().insert(entry.id, entry.clone()));
+        processed.push(entry);
+    }
+
+    Ok(processed)
+}
+
+// Get all ledger entries of a referee
+#[ic_cdk::query]
+fn get_referee_payments(referee_id: u64) -> Result<Vec<PaymentEntry>, String> {
+    PAYMENTS_STORAGE.with(|storage| {
+        let entries: Vec<PaymentEntry> = storage
+            .borrow()
+            .iter()
+            .filter(|(_, entry)| entry.referee_id == referee_id)
+            .map(|(_, entry)| entry.clone())
+            .collect();
+        if entries.is_empty() {
+            Err("No payments found for this referee".to_string())
+        } else {
+            Ok(entries)
+        }
+    })
+}
+
+// Get the amounts billed to a university and their ledger entries
+#[ic_cdk::query]
+fn get_university_billing(university: String) -> Result<UniversityBilling, String> {
+    let university = normalize_university(Some(university));
+
+    get_billing()
+        .into_iter()
+        .find(|billing| billing.university == university)
+        .ok_or_else(|| "No payments are billed to this university".to_string())
+}
+
+// Get the totals billed to every university, without their ledger entries
+#[ic_cdk::query]
+fn get_billing_summary() -> Result<Vec<UniversityBilling>, String> {
+    let summary: Vec<UniversityBilling> = get_billing()
+        .into_iter()
+        .map(|billing| UniversityBilling {
+            entries: Vec::new(),
+            ..billing
+        })
+        .collect();
+
+    if summary.is_empty() {
+        Err("No payments found".to_string())
+    } else {
+        Ok(summary)
+    }
+}
+
+// Group the payment ledger by billed university
+fn get_billing() -> Vec<UniversityBilling> {
+    let mut billing: Vec<UniversityBilling> = Vec::new();
+
+    PAYMENTS_STORAGE.with(|storage| {
+        for (_, entry) in storage.borrow().iter() {
+            let index = match billing
+                .iter()
+                .position(|bill| bill.university == entry.billed_university)
+            {
+                Some(index) => index,
+                None => {
+                    billing.push(UniversityBilling {
+                        university: entry.billed_university.clone(),
+                        ..Default::default()
+                    });
+                    billing.len() - 1
+                }
+            };
+
+            let bill = &mut billing[index];
+            match entry.status {
+                PaymentStatus::Owed => bill.owed += entry.amount,
+                PaymentStatus::Approved => bill.approved += entry.amount,
+                PaymentStatus::Paid => bill.paid += entry.amount,
+                PaymentStatus::Failed => bill.failed += entry.amount,
+            }
+            bill.entries.push(entry);
+        }
+    });
+
+    billing
+}
+
+// Check that a tournament's teams are distinct, exist and play the tournament's sport
+// Tournaments may start with no teams and fill up through registration
+fn validate_tournament_teams(team_ids: &[u64], sport_type: SportType) -> Result<(), String> {
+    if team_ids.len() > MAX_TOURNAMENT_TEAMS {
+        return Err(format!(
+            "A tournament can have at most {} teams",
+            MAX_TOURNAMENT_TEAMS
+        ));
+    }
+
+    for (index, team_id) in team_ids.iter().enumerate() {
+        if team_ids[..index].contains(team_id) {
+            return Err(format!("Team with ID {} is listed more than once", team_id));
+        }
+
+        let team = get_team(*team_id)?;
+        if team.sport_type != sport_type {
+            return Err(format!(
+                "Team {} plays {:?}, not {:?}",
+                team.name, team.sport_type, sport_type
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// Validate a tournament name
+fn validate_tournament_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Name is a required field".to_string());
+    }
+    if name.len() > MAX_TOURNAMENT_NAME_LENGTH {
+        return Err(format!(
+            "Name must be at most {} characters",
+            MAX_TOURNAMENT_NAME_LENGTH
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/**
+ * Function to create a new tournament
+ * Only administrators and league officials can create tournaments.
+ * Every team must exist and play the tournament's sport.
+ */
+#[ic_cdk::update]
+fn create_tournament(payload: CreateTournamentPayload) -> Result<Tournament, String> {
+    let creator = ensure_competition_official(None, payload.sport_type)?;
+
+    let name = validate_tournament_name(&payload.name)?;
+    validate_tournament_teams(&payload.team_ids, payload.sport_type)?;
+
+    let standings_rules = payload.standings_rules.unwrap_or_default();
+    validate_standings_rules(&standings_rules)?;
+
+    let tournament = Tournament {
+        id: generate_uuid(),
+        name,
+        structure: payload.structure,
+        team_ids: payload.team_ids,
+        sport_type: payload.sport_type,
+        created_by: creator.id,
+        created_at: ic_cdk::api::time(),
+        updated_at: None,
+        status: TournamentStatus::Planned,
+        champion_team_id: None,
+        standings_rules,
+    };
+
+    TOURNAMENTS_STORAGE.with(|storage| {
+        storage
+            .borrow_mut()
+            .insert(tournament.id, tournament.clone())
+    });
+
+    Ok(tournament)
+}
+
+// Function for administrators and league officials to update a tournament's details and teams
+#[ic_cdk::update]
+fn update_tournament(payload: UpdateTournamentPayload) -> Result<Tournament, String> {
+    let mut tournament = get_tournament(payload.id)?;
+    ensure_tournament_official(&tournament)?;
+    ensure_competition_official(tournament_league_id(tournament.id), payload.sport_type)?;
+
+    let name = validate_tournament_name(&payload.name)?;
+    validate_tournament_teams(&payload.team_ids, payload.sport_type)?;
+
+    // The teams and format are fixed once fixtures have been generated
+    let format_changed = tournament.structure != payload.structure
+        || tournament.team_ids != payload.team_ids
+        || tournament.sport_type != payload.sport_type;
+
+    if format_changed && !get_tournament_matches(tournament.id).is_empty() {
+        return Err(
+            "The teams and format of a tournament cannot change once its fixtures exist"
+                .to_string(),
+        );
+    }
+
+    tournament.name = name;
+    tournament.structure = payload.structure;
+    tournament.team_ids = payload.team_ids;
+    tournament.sport_type = payload.sport_type;
+    tournament.updated_at = Some(ic_cdk::api::time());
+
+    // Keep the current standings rules unless new ones are given
+    if let Some(standings_rules) = payload.standings_rules {
+        validate_standings_rules(&standings_rules)?;
+        tournament.standings_rules = standings_rules;
+    }
+
+    TOURNAMENTS_STORAGE.with(|storage| {
+        storage
+            .borrow_mut()
+            .insert(tournament.id, tournament.clone())
+    });
+
+    Ok(tournament)
+}
+
+// Fetch a tournament by ID
+#[ic_cdk::query]
+fn get_tournament(id: u64) -> Result<Tournament, String> {
+    TOURNAMENTS_STORAGE.with(|storage| match storage.borrow().get(&id) {
+        Some(tournament) => Ok(tournament.clone()),
+        None => Err(format!("Tournament with ID {} not found", id)),
+    })
+}
+
+// Function to get all tournaments
+#[ic_cdk::query]
+fn get_all_tournaments() -> Result<Vec<Tournament>, String> {
+    TOURNAMENTS_STORAGE.with(|storage| {
+        let tournaments: Vec<Tournament> = storage
+            .borrow()
+            .iter()
+            .map(|(_, tournament)| tournament.clone())
+            .collect();
+        if tournaments.is_empty() {
+            Err("No tournaments found".to_string())
+        } else {
+            Ok(tournaments)
+        }
+    })
+}
+
+// Function to get the tournaments a team takes part in
+#[ic_cdk::query]
+fn get_team_tournaments(team_id: u64) -> Result<Vec<Tournament>, String> {
+    TOURNAMENTS_STORAGE.with(|storage| {
+        let tournaments: Vec<Tournament> = storage
+            .borrow()
+            .iter()
+            .filter(|(_, tournament)| tournament.team_ids.contains(&team_id))
+            .map(|(_, tournament)| tournament.clone())
+            .collect();
+        if tournaments.is_empty() {
+            Err("No tournaments found for this team".to_string())
+        } else {
+            Ok(tournaments)
+        }
+    })
+}
+
+// Validate a league name, which follows the rules for tournament names
+fn validate_league_name(name: &str) -> Result<String, String> {
+    validate_tournament_name(name)
+}
+
+// Save a league
+fn save_league(league: &League) {
+    LEAGUES_STORAGE.with(|storage| storage.borrow_mut().insert(league.id, league.clone()));
+}
+
+/**
+ * Function to create a new league for a sport
+ * Only administrators and league officials can create leagues, and the caller is recorded as its creator.
+ */
+#[ic_cdk::update]
+fn create_league(payload: CreateLeaguePayload) -> Result<League, String> {
+    let creator = ensure_competition_official(None, payload.sport_type)?;
+
+    let name = validate_league_name(&payload.name)?;
+
+    let standings_rules = payload.standings_rules.unwrap_or_default();
+    validate_standings_rules(&standings_rules)?;
+
+    let league = League {
+        id: generate_uuid(),
+        name,
+        sport_type: payload.sport_type,
+        team_ids: Vec::new(),
+        tournament_ids: Vec::new(),
+        created_by: creator.id,
+        created_at: ic_cdk::api::time(),
+        updated_at: None,
+        standings_rules,
+    };
+
+    save_league(&league);
+
+    Ok(league)
+}
+
+// Function for administrators and league officials to rename a league or change its standings rules
+#[ic_cdk::update]
+fn update_league(payload: UpdateLeaguePayload) -> Result<League, String> {
+    let mut league = get_league(payload.id)?;
+    ensure_league_official(&league)?;
+    league.name = validate_league_name(&payload.name)?;
+
+    // Keep the current standings rules unless new ones are given
+    if let Some(standings_rules) = payload.standings_rules {
+        validate_standings_rules(&standings_rules)?;
+        league.standings_rules = standings_rules;
+    }
+
+    league.updated_at = Some(ic_cdk::api::time());
+    save_league(&league);
+
+    Ok(league)
+}
+
+// Function for administrators to delete a league that has no tournaments or seasons
+#[ic_cdk::update]
+fn delete_league(id: u64) -> Result<League, String> {
+    ensure_caller_role(&[UserRole::Administrator])?;
+
+    let league = get_league(id)?;
+    if !league.tournament_ids.is_empty() {
+        return Err("A league with tournaments cannot be deleted".to_string());
+    }
+    if !get_seasons_for_league(id).is_empty() {
+        return Err("A league with seasons cannot be deleted".to_string());
+    }
+    if !get_divisions_for_league(id).is_empty() {
+        return Err("A league with divisions cannot be deleted".to_string());
+    }
+
+    LEAGUES_STORAGE.with(|storage| storage.borrow_mut().remove(&id));
+
+    Ok(league)
+}
+
+// Fetch a league by ID
+#[ic_cdk::query]
+fn get_league(id: u64) -> Result<League, String> {
+    LEAGUES_STORAGE.with(|storage| match storage.borrow().get(&id) {
+        Some(league) => Ok(league.clone()),
+        None => Err(format!("League with ID {} not found", id)),
+    })
+}
+
+// Function to get all leagues
+#[ic_cdk::query]
+fn get_all_leagues() -> Result<Vec<League>, String> {
+    let leagues: Vec<League> =
+        LEAGUES_STORAGE.with(|storage| storage.borrow().iter().map(|(_, league)| league).collect());
+
+    if leagues.is_empty() {
+        Err("No leagues found".to_string())
+    } else {
+        Ok(leagues)
+    }
+}
+
+// Function for administrators and league officials to add a team of the league's sport to a league
+#[ic_cdk::update]
+fn add_league_team(payload: LeagueTeamPayload) -> Result<League, String> {
+    let mut league = get_league(payload.league_id)?;
+    ensure_league_official(&league)?;
+    let team = get_team(payload.team_id)?;
+
+    if team.sport_type != league.sport_type {
+        return Err(format!(
+            "Team {} plays {:?}, not {:?}",
+            team.name, team.sport_type, league.sport_type
+        ));
+    }
+
+    if league.team_ids.contains(&team.id) {
+        return Err(format!("Team {} is already in this league", team.name));
+    }
+
+    if league.team_ids.len() >= MAX_LEAGUE_TEAMS {
+        return Err(format!(
+            "A league can have at most {} teams",
+            MAX_LEAGUE_TEAMS
+        ));
+    }
+
+    league.team_ids.push(team.id);
+    league.updated_at = Some(ic_cdk::api::time());
+    save_league(&league);
+
+    Ok(league)
+}
+
+// Function for administrators and league officials to remove a team that plays in none of a league's tournaments
+#[ic_cdk::update]
+fn remove_league_team(payload: LeagueTeamPayload) -> Result<League, String> {
+    let mut league = get_league(payload.league_id)?;
+    ensure_league_official(&league)?;
+
+    if !league.team_ids.contains(&payload.team_id) {
+        return Err("Team is not in this league".to_string());
+    }
+
+    for tournament_id in &league.tournament_ids {
+        if get_tournament(*tournament_id)?
+            .team_ids
+            .contains(&payload.team_id)
+        {
+            return Err("Team plays in one of the league's tournaments".to_string());
+        }
+    }
+
+    league
+        .team_ids
+        .retain(|team_id| *team_id != payload.team_id);
+    league.updated_at = Some(ic_cdk::api::time());
+    save_league(&league);
+
+    Ok(league)
+}
+
+/**
+ * Function for administrators and league officials to attach a tournament to a league, optionally in one of its seasons
+ * The tournament must play the league's sport and belong to no other league.
+ * Its existing matches are labelled with the league and season so they count towards their standings.
+ */
+#[ic_cdk::update]
+fn attach_league_tournament(payload: LeagueTournamentPayload) -> Result<League, String> {
+    let mut league = get_league(payload.league_id)?;
+    ensure_league_official(&league)?;
+    let tournament = get_tournament(payload.tournament_id)?;
+
+    if tournament.sport_type != league.sport_type {
+        return Err(format!(
+            "Tournament {} plays {:?}, not {:?}",
+            tournament.name, tournament.sport_type, league.sport_type
+        ));
+    }
+
+    if let Some(league_id) = tournament_league_id(tournament.id) {
+        return Err(format!(
+            "Tournament {} already belongs to league with ID {}",
+            tournament.name, league_id
+        ));
+    }
+
+    if league.tournament_ids.len() >= MAX_LEAGUE_TOURNAMENTS {
+        return Err(format!(
+            "A league can have at most {} tournaments",
+            MAX_LEAGUE_TOURNAMENTS
+        ));
+    }
+
+    let matches = get_tournament_matches(tournament.id);
+
+    let season = match payload.season_id {
+        Some(season_id) => {
+            let mut season = get_season(season_id)?;
+            if season.league_id != league.id {
+                return Err(format!(
+                    "Season {} is not a season of this league",
+                    season.name
+                ));
+            }
+            ensure_season_open(&season)?;
+            if season.tournament_ids.len() >= MAX_LEAGUE_TOURNAMENTS {
+                return Err(format!(
+                    "A season can have at most {} tournaments",
+                    MAX_LEAGUE_TOURNAMENTS
+                ));
+            }
+            for match_obj in &matches {
+                check_season_match_date(&season, &match_obj.scheduled_date)?;
+            }
+            season.tournament_ids.push(tournament.id);
+            Some(season)
+        }
+        None => None,
+    };
+
+    league.tournament_ids.push(tournament.id);
+    league.updated_at = Some(ic_cdk::api::time());
+    save_league(&league);
+
+    if let Some(season) = &season {
+        save_season(season);
+    }
+
+    for mut match_obj in matches {
+        match_obj.league_id = Some(league.id);
+        match_obj.season_id = season.as_ref().map(|season| season.id);
+        MATCHES_STORAGE
+            .with(|storage| storage.borrow_mut().insert(match_obj.id, match_obj.clone()));
+        index_match(&match_obj);
+    }
+
+    Ok(league)
+}
+
+// Function for administrators and league officials to detach a tournament from a league before its fixtures exist
+#[ic_cdk::update]
+fn detach_league_tournament(payload: LeagueTournamentPayload) -> Result<League, String> {
+    let mut league = get_league(payload.league_id)?;
+    ensure_league_official(&league)?;
+
+    if !league.tournament_ids.contains(&payload.tournament_id) {
+        return Err("Tournament is not in this league".to_string());
+    }
+
+    if !get_tournament_matches(payload.tournament_id).is_empty() {
+        return Err("A tournament cannot leave its league once its fixtures exist".to_string());
+    }
+
+    if let Some(mut season) = tournament_season(payload.tournament_id) {
+        ensure_season_open(&season)?;
+        season
+            .tournament_ids
+            .retain(|tournament_id| *tournament_id != payload.tournament_id);
+        save_season(&season);
+    }
+
+    league
+        .tournament_ids
+        .retain(|tournament_id| *tournament_id != payload.tournament_id);
+    league.updated_at = Some(ic_cdk::api::time());
+    save_league(&league);
+
+    Ok(league)
+}
+
+// Get the tournaments of a league
+#[ic_cdk::query]
+fn get_league_tournaments(league_id: u64) -> Result<Vec<Tournament>, String> {
+    let league = get_league(league_id)?;
+    league
+        .tournament_ids
+        .iter()
+        .map(|tournament_id| get_tournament(*tournament_id))
+        .collect()
+}
+
+// Save a season
+fn save_season(season: &Season) {
+    SEASONS_STORAGE.with(|storage| storage.borrow_mut().insert(season.id, season.clone()));
+}
+
+// Find the season a tournament is played in
+fn tournament_season(tournament_id: u64) -> Option<Season> {
+    SEASONS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, season)| season)
+            .find(|season| season.tournament_ids.contains(&tournament_id))
+    })
+}
+
+// Get the seasons of a league, in date order
+fn get_seasons_for_league(league_id: u64) -> Vec<Season> {
+    let mut seasons: Vec<Season> = SEASONS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, season)| season)
+            .filter(|season| season.league_id == league_id)
+            .collect()
+    });
+    seasons.sort_by(|a, b| a.start_date.cmp(&b.start_date));
+    seasons
+}
+
+// Archived seasons are read-only
+fn ensure_season_open(season: &Season) -> Result<(), String> {
+    if season.archived {
+        return Err(format!("Season {} is archived and read-only", season.name));
+    }
+    Ok(())
+}
+
+// Results of a match in an archived season can no longer change
+fn ensure_match_season_open(match_obj: &Match) -> Result<(), String> {
+    if let Some(season_id) = match_obj.season_id {
+        ensure_season_open(&get_season(season_id)?)?;
+    }
+    Ok(())
+}
+
+// Parse the dates of a season period
+fn parse_period(period: &SeasonPeriod) -> Result<(NaiveDate, NaiveDate), String> {
+    let start = parse_date(&period.start_date)?;
+    let end = parse_date(&period.end_date)?;
+    if end < start {
+        return Err(format!("{} must not end before it starts", period.name));
+    }
+    Ok((start, end))
+}
+
+// Check a season match is played within the season and outside its exam blackouts
+fn check_season_match_date(season: &Season, scheduled_date: &str) -> Result<(), String> {
+    let date = parse_date(scheduled_date)?;
+
+    if date < parse_date(&season.start_date)? || date > parse_date(&season.end_date)? {
+        return Err(format!(
+            "{} is outside season {}, which runs from {} to {}",
+            scheduled_date, season.name, season.start_date, season.end_date
+        ));
+    }
+
+    for blackout in &season.exam_blackouts {
+        let (start, end) = parse_period(blackout)?;
+        if start <= date && date <= end {
+            return Err(format!(
+                "{} falls in the exam blackout {}",
+                scheduled_date, blackout.name
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// The exam blackouts of a tournament's season, which fixtures are kept out of
+fn tournament_blackouts(tournament_id: u64) -> Result<Vec<(NaiveDate, NaiveDate)>, String> {
+    match tournament_season(tournament_id) {
+        Some(season) => season.exam_blackouts.iter().map(parse_period).collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+/*
+Players may only join a team registered for a season under way during one of its transfer windows
+Teams outside a running season can sign players at any time.
+*/
+fn ensure_transfer_window(team_id: u64) -> Result<(), String> {
+    let today = today();
+
+    let running_seasons: Vec<Season> = SEASONS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, season)| season)
+            .filter(|season| !season.archived && season.team_ids.contains(&team_id))
+            .collect()
+    });
+
+    for season in running_seasons {
+        let (start, end) = (
+            parse_date(&season.start_date)?,
+            parse_date(&season.end_date)?,
+        );
+        if today < start || today > end {
+            continue;
+        }
+
+        let in_window = season
+            .transfer_windows
+            .iter()
+            .filter_map(|window| parse_period(window).ok())
+            .any(|(start, end)| start <= today && today <= end);
+
+        if !in_window {
+            return Err(format!(
+                "Players can only join during a transfer window of season {}",
+                season.name
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/*
+Check the dates of a season and its periods
+Registration and transfer windows may open before the season starts but must close by its end,
+and exam blackouts fall within the season. Seasons of the same league do not overlap.
+*/
+fn validate_season(
+    league_id: u64,
+    season_id: u64,
+    payload: &UpdateSeasonPayload,
+) -> Result<(), String> {
+    let start = parse_date(&payload.start_date)?;
+    let end = parse_date(&payload.end_date)?;
+
+    if end < start {
+        return Err("A season must not end before it starts".to_string());
+    }
+
+    if payload.transfer_windows.len() > MAX_SEASON_PERIODS
+        || payload.exam_blackouts.len() > MAX_SEASON_PERIODS
+    {
+        return Err(format!(
+            "A season can have at most {} transfer windows and {} exam blackouts",
+            MAX_SEASON_PERIODS, MAX_SEASON_PERIODS
+        ));
+    }
+
+    let periods = std::iter::once(&payload.registration_window)
+        .chain(&payload.transfer_windows)
+        .chain(&payload.exam_blackouts);
+    for period in periods {
+        if period.name.trim().is_empty() || period.name.len() > MAX_SEASON_PERIOD_NAME_LENGTH {
+            return Err(format!(
+                "Period names must be between 1 and {} characters",
+                MAX_SEASON_PERIOD_NAME_LENGTH
+            ));
+        }
+    }
+
+    for window in std::iter::once(&payload.registration_window).chain(&payload.transfer_windows) {
+        let (_, window_end) = parse_period(window)?;
+        if window_end > end {
+            return Err(format!(
+                "{} must close by the end of the season",
+                window.name
+            ));
+        }
+    }
+
+    for blackout in &payload.exam_blackouts {
+        let (blackout_start, blackout_end) = parse_period(blackout)?;
+        if blackout_start < start || blackout_end > end {
+            return Err(format!("{} must fall within the season", blackout.name));
+        }
+    }
+
+    for other in get_seasons_for_league(league_id) {
+        if other.id == season_id {
+            continue;
+        }
+        if start <= parse_date(&other.end_date)? && parse_date(&other.start_date)? <= end {
+            return Err(format!("The season overlaps season {}", other.name));
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Function for administrators and league officials to create a season of a league
+ * A season has its own dates, a registration window, transfer windows and exam blackouts.
+ */
+#[ic_cdk::update]
+fn create_season(payload: CreateSeasonPayload) -> Result<Season, String> {
+    let league = get_league(payload.league_id)?;
+    let creator = ensure_league_official(&league)?;
+    let name = validate_tournament_name(&payload.name)?;
+
+    let season = Season {
+        id: generate_uuid(),
+        league_id: league.id,
+        name,
+        start_date: payload.start_date,
+        end_date: payload.end_date,
+        registration_window: payload.registration_window,
+        transfer_windows: payload.transfer_windows,
+        exam_blackouts: payload.exam_blackouts,
+        team_ids: Vec::new(),
+        tournament_ids: Vec::new(),
+        created_by: creator.id,
+        created_at: ic_cdk::api::time(),
+        updated_at: None,
+        archived: false,
+        archived_at: None,
+    };
+
+    validate_season(
+        league.id,
+        season.id,
+        &UpdateSeasonPayload {
+            id: season.id,
+            name: season.name.clone(),
+            start_date: season.start_date.clone(),
+            end_date: season.end_date.clone(),
+            registration_window: season.registration_window.clone(),
+            transfer_windows: season.transfer_windows.clone(),
+            exam_blackouts: season.exam_blackouts.clone(),
+        },
+    )?;
+
+    save_season(&season);
+
+    Ok(season)
+}
+
+// Function for administrators and league officials to change a season's dates; its scheduled matches must still fit
+#[ic_cdk::update]
+fn update_season(payload: UpdateSeasonPayload) -> Result<Season, String> {
+    let mut season = get_season(payload.id)?;
+    ensure_league_official(&get_league(season.league_id)?)?;
+    ensure_season_open(&season)?;
+
+    let name = validate_tournament_name(&payload.name)?;
+    validate_season(season.league_id, season.id, &payload)?;
+
+    season.name = name;
+    season.start_date = payload.start_date;
+    season.end_date = payload.end_date;
+    season.registration_window = payload.registration_window;
+    season.transfer_windows = payload.transfer_windows;
+    season.exam_blackouts = payload.exam_blackouts;
+    season.updated_at = Some(ic_cdk::api::time());
+
+    for match_obj in get_indexed_matches(season.id, None) {
+        check_season_match_date(&season, &match_obj.scheduled_date)?;
+    }
+
+    save_season(&season);
+
+    Ok(season)
+}
+
+// Fetch a season by ID
+#[ic_cdk::query]
+fn get_season(id: u64) -> Result<Season, String> {
+    SEASONS_STORAGE.with(|storage| match storage.borrow().get(&id) {
+        Some(season) => Ok(season.clone()),
+        None => Err(format!("Season with ID {} not found", id)),
+    })
+}
+
+// Get the seasons of a league, in date order
+#[ic_cdk::query]
+fn get_league_seasons(league_id: u64) -> Result<Vec<Season>, String> {
+    let seasons = get_seasons_for_league(league_id);
+    if seasons.is_empty() {
+        Err("No seasons found for this league".to_string())
+    } else {
+        Ok(seasons)
+    }
+}
+
+/**
+ * Function to register a league member for a season during its registration window
+ * Coaches register their own teams; administrators and league officials may register any member.
+ */
+#[ic_cdk::update]
+fn register_season_team(payload: SeasonTeamPayload) -> Result<Season, String> {
+    let user = get_caller_user()?;
+    let mut season = get_season(payload.season_id)?;
+    ensure_season_open(&season)?;
+
+    let team = get_team(payload.team_id)?;
+    let league = get_league(season.league_id)?;
+    let is_organiser = is_competition_official(&user, Some(league.id), league.sport_type);
+    if !is_organiser && !team.coaches.contains(&user.id) {
+        return Err("Only the team's coaches can register it for a season".to_string());
+    }
+
+    if !league.team_ids.contains(&team.id) {
+        return Err(format!("Team {} is not a member of the league", team.name));
+    }
+
+    if let Some(rules) = current_league_rules(season.league_id, Some(season.id)) {
+        check_team_eligibility(&rules.eligibility, &team)?;
+    }
+
+    if season.team_ids.contains(&team.id) {
+        return Err(format!(
+            "Team {} is already registered for this season",
+            team.name
+        ));
+    }
+
+    let (opens, closes) = parse_period(&season.registration_window)?;
+    let today = today();
+    if today < opens || today > closes {
+        return Err(format!(
+            "Registration for season {} is open from {} to {}",
+            season.name, season.registration_window.start_date, season.registration_window.end_date
+        ));
+    }
+
+    season.team_ids.push(team.id);
+    season.updated_at = Some(ic_cdk::api::time());
+    save_season(&season);
+
+    Ok(season)
+}
+
+// Function for administrators and league officials to withdraw a team that plays in none of a season's tournaments
+#[ic_cdk::update]
+fn withdraw_season_team(payload: SeasonTeamPayload) -> Result<Season, String> {
+    let mut season = get_season(payload.season_id)?;
+    ensure_league_official(&get_league(season.league_id)?)?;
+    ensure_season_open(&season)?;
+
+    if !season.team_ids.contains(&payload.team_id) {
+        return Err("Team is not registered for this season".to_string());
+    }
+
+    for tournament_id in &season.tournament_ids {
+        if get_tournament(*tournament_id)?
+            .team_ids
+            .contains(&payload.team_id)
+        {
+            return Err("Team plays in one of the season's tournaments".to_string());
+        }
+    }
+
+    season
+        .team_ids
+        .retain(|team_id| *team_id != payload.team_id);
+    season.updated_at = Some(ic_cdk::api::time());
+    save_season(&season);
+
+    let mut season_divisions = load_season_divisions(season.id);
+    if !season_divisions.divisions.is_empty() {
+        for division in season_divisions.divisions.iter_mut() {
+            division
+                .team_ids
+                .retain(|team_id| *team_id != payload.team_id);
+        }
+        save_season_divisions(&season_divisions);
+    }
+
+    Ok(season)
+}
+
+// Function for administrators and league officials to archive a season once all of its matches are played
+#[ic_cdk::update]
+fn archive_season(id: u64) -> Result<Season, String> {
+    let mut season = get_season(id)?;
+    ensure_league_official(&get_league(season.league_id)?)?;
+    ensure_season_open(&season)?;
+
+    if get_indexed_matches(season.id, None)
+        .iter()
+        .any(|match_obj| match_obj.result.is_none())
+    {
+        return Err("A season can only be archived once all of its matches are played".to_string());
+    }
+
+    season.archived = true;
+    season.archived_at = Some(ic_cdk::api::time());
+    save_season(&season);
+
+    Ok(season)
+}
+
+// Get the standings of a season across the completed matches of its tournaments, using the league's rules
+#[ic_cdk::query]
+fn get_season_standings(season_id: u64) -> Result<Vec<StandingsEntry>, String> {
+    let season = get_season(season_id)?;
+    let league = get_league(season.league_id)?;
+
+    Ok(compute_standings(
+        &season.team_ids,
+        &get_indexed_matches(season.id, None),
+        &effective_standings_rules(&league, Some(season.id)),
+        season.id,
+    ))
+}
+
+// Save a division
+fn save_division(division: &Division) {
+    DIVISIONS_STORAGE.with(|storage| storage.borrow_mut().insert(division.id, division.clone()));
+}
+
+// Load how a season's teams are split into divisions; a season without divisions has an empty split
+fn load_season_divisions(season_id: u64) -> SeasonDivisions {
+    SEASON_DIVISIONS_STORAGE
+        .with(|storage| storage.borrow().get(&season_id))
+        .unwrap_or(SeasonDivisions {
+            season_id,
+            ..Default::default()
+        })
+}
+
+// Save how a season's teams are split into divisions
+fn save_season_divisions(season_divisions: &SeasonDivisions) {
+    SEASON_DIVISIONS_STORAGE.with(|storage| {
+        storage
+            .borrow_mut()
+            .insert(season_divisions.season_id, season_divisions.clone())
+    });
+}
+
+// Get the divisions of a league, from the top division down
+fn get_divisions_for_league(league_id: u64) -> Vec<Division> {
+    let mut divisions: Vec<Division> = DIVISIONS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, division)| division)
+            .filter(|division| division.league_id == league_id)
+            .collect()
+    });
+    divisions.sort_by_key(|division| division.level);
+    divisions
+}
+
+// Check a division's level is free within its league
+fn validate_division_level(league_id: u64, division_id: u64, level: u32) -> Result<(), String> {
+    if level == 0 {
+        return Err("Division levels start at 1 for the top division".to_string());
+    }
+
+    if let Some(other) = get_divisions_for_league(league_id)
+        .into_iter()
+        .find(|other| other.id != division_id && other.level == level)
+    {
+        return Err(format!(
+            "Division {} is already at level {}",
+            other.name, level
+        ));
+    }
+
+    Ok(())
+}
+
+// Function for administrators and league officials to add a division to a league
+#[ic_cdk::update]
+fn create_division(payload: CreateDivisionPayload) -> Result<Division, String> {
+    let league = get_league(payload.league_id)?;
+    ensure_league_official(&league)?;
+    let name = validate_tournament_name(&payload.name)?;
+
+    if get_divisions_for_league(league.id).len() >= MAX_LEAGUE_DIVISIONS {
+        return Err(format!(
+            "A league can have at most {} divisions",
+            MAX_LEAGUE_DIVISIONS
+        ));
+    }
+
+    let division = Division {
+        id: generate_uuid(),
+        league_id: league.id,
+        name,
+        level: payload.level,
+        promotion_spots: payload.promotion_spots,
+        playoff_spots: payload.playoff_spots,
+        relegation_spots: payload.relegation_spots,
+        created_at: ic_cdk::api::time(),
+        updated_at: None,
+    };
+
+    validate_division_level(league.id, division.id, division.level)?;
+    save_division(&division);
+
+    Ok(division)
+}
+
+// Function for administrators and league officials to change a division's name, level or movement spots
+#[ic_cdk::update]
+fn update_division(payload: UpdateDivisionPayload) -> Result<Division, String> {
+    let mut division = get_division(payload.id)?;
+    ensure_league_official(&get_league(division.league_id)?)?;
+    let name = validate_tournament_name(&payload.name)?;
+    validate_division_level(division.league_id, division.id, payload.level)?;
+
+    division.name = name;
+    division.level = payload.level;
+    division.promotion_spots = payload.promotion_spots;
+    division.playoff_spots = payload.playoff_spots;
+    division.relegation_spots = payload.relegation_spots;
+    division.updated_at = Some(ic_cdk::api::time());
+    save_division(&division);
+
+    Ok(division)
+}
+
+// Function for administrators and league officials to delete a division no season has used
+#[ic_cdk::update]
+fn delete_division(id: u64) -> Result<Division, String> {
+    let division = get_division(id)?;
+    ensure_league_official(&get_league(division.league_id)?)?;
+    let in_use = get_seasons_for_league(division.league_id)
+        .iter()
+        .flat_map(|season| load_season_divisions(season.id).divisions)
+        .any(|season_division| season_division.division_id == id);
+    if in_use {
+        return Err(format!(
+            "Division {} has been used in a season",
+            division.name
+        ));
+    }
+
+    DIVISIONS_STORAGE.with(|storage| storage.borrow_mut().remove(&id));
+
+    Ok(division)
+}
+
+// Fetch a division by ID
+#[ic_cdk::query]
+fn get_division(id: u64) -> Result<Division, String> {
+    DIVISIONS_STORAGE.with(|storage| match storage.borrow().get(&id) {
+        Some(division) => Ok(division.clone()),
+        None => Err(format!("Division with ID {} not found", id)),
+    })
+}
+
+// Get the divisions of a league, from the top division down
+#[ic_cdk::query]
+fn get_league_divisions(league_id: u64) -> Result<Vec<Division>, String> {
+    let divisions = get_divisions_for_league(league_id);
+    if divisions.is_empty() {
+        Err("No divisions found for this league".to_string())
+    } else {
+        Ok(divisions)
+    }
+}
+
+// Function for administrators and league officials to place a season's team in a division, moving it out of any other
+#[ic_cdk::update]
+fn assign_season_division(payload: SeasonDivisionPayload) -> Result<SeasonDivisions, String> {
+    let season = get_season(payload.season_id)?;
+    ensure_league_official(&get_league(season.league_id)?)?;
+    ensure_season_open(&season)?;
+
+    let division = get_division(payload.division_id)?;
+    if division.league_id != season.league_id {
+        return Err(format!(
+            "Division {} is not a division of this league",
+            division.name
+        ));
+    }
+
+    if !season.team_ids.contains(&payload.team_id) {
+        return Err("Team is not registered for this season".to_string());
+    }
+
+    let mut season_divisions = load_season_divisions(season.id);
+    let divisions = &mut season_divisions.divisions;
+    for season_division in divisions.iter_mut() {
+        season_division
+            .team_ids
+            .retain(|team_id| *team_id != payload.team_id);
+    }
+    match divisions
+        .iter_mut()
+        .find(|season_division| season_division.division_id == division.id)
+    {
+        Some(season_division) => season_division.team_ids.push(payload.team_id),
+        None => divisions.push(SeasonDivision {
+            division_id: division.id,
+            team_ids: vec![payload.team_id],
+        }),
+    }
+    divisions.retain(|season_division| !season_division.team_ids.is_empty());
+
+    save_season_divisions(&season_divisions);
+
+    Ok(season_divisions)
+}
+
+// Get how a season's teams are split into divisions, and the moves made when it ended
+#[ic_cdk::query]
+fn get_season_divisions(season_id: u64) -> Result<SeasonDivisions, String> {
+    get_season(season_id)?;
+    Ok(load_season_divisions(season_id))
+}
+
+// Standings of a division in a season, counting only the season's matches between the division's teams
+fn division_standings(season: &Season, division_id: u64) -> Result<Vec<StandingsEntry>, String> {
+    let team_ids = load_season_divisions(season.id)
+        .divisions
+        .iter()
+        .find(|season_division| season_division.division_id == division_id)
+        .map(|season_division| season_division.team_ids.clone())
+        .ok_or_else(|| "Division has no teams this season".to_string())?;
+
+    let league = get_league(season.league_id)?;
+
+    Ok(compute_standings(
+        &team_ids,
+        &get_indexed_matches(season.id, None),
+        &effective_standings_rules(&league, Some(season.id)),
+        season.id,
+    ))
+}
+
+// Get the standings of a division in a season
+#[ic_cdk::query]
+fn get_division_standings(season_id: u64, division_id: u64) -> Result<Vec<StandingsEntry>, String> {
+    division_standings(&get_season(season_id)?, division_id)
+}
+
+/*
+Work out the moves between divisions from the final standings of a season
+Divisions are taken from the top down. The top spots of a division go up a level and the bottom spots go down,
+and the winner of a division's playoff takes one more promotion place. The top division promotes no one and
+the bottom division relegates no one, since there is nowhere for those teams to go.
+*/
+fn compute_division_movements(
+    season: &Season,
+    playoff_winner_ids: &[u64],
+) -> Result<Vec<DivisionMovement>, String> {
+    let season_divisions = load_season_divisions(season.id);
+    let divisions: Vec<Division> = get_divisions_for_league(season.league_id)
+        .into_iter()
+        .filter(|division| {
+            season_divisions
+                .divisions
+                .iter()
+                .any(|season_division| season_division.division_id == division.id)
+        })
+        .collect();
+
+    let mut movements = Vec::new();
+    let mut used_winners = Vec::new();
+
+    for (index, division) in divisions.iter().enumerate() {
+        let standings = division_standings(season, division.id)?;
+        let team_count = standings.len() as u32;
+        let above = index.checked_sub(1).map(|above| &divisions[above]);
+        let below = divisions.get(index + 1);
+
+        let (promotion_spots, playoff_spots) = match above {
+            Some(_) => (division.promotion_spots, division.playoff_spots),
+            None => (0, 0),
+        };
+        let relegation_spots = match below {
+            Some(_) => division.relegation_spots,
+            None => 0,
+        };
+
+        if promotion_spots + playoff_spots + relegation_spots > team_count {
+            return Err(format!(
+                "Division {} has more promotion, playoff and relegation spots than its {} teams",
+                division.name, team_count
+            ));
+        }
+
+        for entry in &standings {
+            let movement = if entry.position <= promotion_spots {
+                Some((above.unwrap().id, MovementKind::Promoted))
+            } else if entry.position <= promotion_spots + playoff_spots {
+                if playoff_winner_ids.contains(&entry.team_id) {
+                    used_winners.push(entry.team_id);
+                    Some((above.unwrap().id, MovementKind::PromotedViaPlayoff))
+                } else {
+                    None
+                }
+            } else if entry.position > team_count - relegation_spots {
+                Some((below.unwrap().id, MovementKind::Relegated))
+            } else {
+                None
+            };
+
+            if let Some((to_division_id, kind)) = movement {
+                movements.push(DivisionMovement {
+                    team_id: entry.team_id,
+                    from_division_id: division.id,
+                    to_division_id,
+                    position: entry.position,
+                    kind,
+                });
+            }
+        }
+
+        if playoff_spots > 0 {
+            let winners = standings
+                .iter()
+                .filter(|entry| {
+                    entry.position > promotion_spots
+                        && entry.position <= promotion_spots + playoff_spots
+                        && playoff_winner_ids.contains(&entry.team_id)
+                })
+                .count();
+            if winners != 1 {
+                return Err(format!(
+                    "Division {} needs exactly one playoff winner from its playoff places",
+                    division.name
+                ));
+            }
+        }
+    }
+
+    if let Some(team_id) = playoff_winner_ids
+        .iter()
+        .find(|team_id| !used_winners.contains(team_id))
+    {
+        return Err(format!(
+            "Team with ID {} did not finish in a playoff place",
+            team_id
+        ));
+    }
+
+    Ok(movements)
+}
+
+/**
+ * Function for administrators and league officials to end a season
+ * All of the season's matches must be played. Movements between divisions are computed from the final standings,
+ * the teams are registered for the next season in their new divisions, and the ended season is archived.
+ */
+#[ic_cdk::update]
+fn end_season(payload: EndSeasonPayload) -> Result<SeasonDivisions, String> {
+    let mut season = get_season(payload.season_id)?;
+    ensure_league_official(&get_league(season.league_id)?)?;
+    ensure_season_open(&season)?;
+
+    let mut next_season = get_season(payload.next_season_id)?;
+    ensure_season_open(&next_season)?;
+
+    if next_season.league_id != season.league_id || next_season.id == season.id {
+        return Err("The next season must be another season of the same league".to_string());
+    }
+    if parse_date(&next_season.start_date)? <= parse_date(&season.end_date)? {
+        return Err(format!(
+            "Season {} does not follow season {}",
+            next_season.name, season.name
+        ));
+    }
+    if !load_season_divisions(next_season.id).divisions.is_empty() {
+        return Err(format!(
+            "Season {} already has its divisions",
+            next_season.name
+        ));
+    }
+
+    if get_indexed_matches(season.id, None)
+        .iter()
+        .any(|match_obj| match_obj.result.is_none())
+    {
+        return Err("A season can only end once all of its matches are played".to_string());
+    }
+
+    let movements = compute_division_movements(&season, &payload.playoff_winner_ids)?;
+
+    let mut season_divisions = load_season_divisions(season.id);
+
+    let mut next_divisions: Vec<SeasonDivision> = season_divisions
+        .divisions
+        .clone()
+        .into_iter()
+        .map(|mut season_division| {
+            season_division.team_ids.retain(|team_id| {
+                !movements
+                    .iter()
+                    .any(|movement| movement.team_id == *team_id)
+            });
+            season_division
+        })
+        .collect();
+
+    for movement in &movements {
+        if let Some(season_division) = next_divisions
+            .iter_mut()
+            .find(|season_division| season_division.division_id == movement.to_division_id)
+        {
+            season_division.team_ids.push(movement.team_id);
+        }
+    }
+
+    for team_id in next_divisions
+        .iter()
+        .flat_map(|division| division.team_ids.iter())
+    {
+        if !next_season.team_ids.contains(team_id) {
+            next_season.team_ids.push(*team_id);
+        }
+    }
+    next_season.updated_at = Some(ic_cdk::api::time());
+    save_season(&next_season);
+    save_season_divisions(&SeasonDivisions {
+        season_id: next_season.id,
+        divisions: next_divisions,
+        movements: Vec::new(),
+    });
+
+    season_divisions.movements = movements;
+    save_season_divisions(&season_divisions);
+
+    season.archived = true;
+    season.archived_at = Some(ic_cdk::api::time());
+    save_season(&season);
+
+    Ok(season_divisions)
+}
+
+// Find the current rules for a season of a league, falling back to the league-wide rules
+fn current_league_rules(league_id: u64, season_id: Option<u64>) -> Option<LeagueRules> {
+    let latest = |season_id: Option<u64>| {
+        LEAGUE_RULES_STORAGE.with(|storage| {
+            storage
+                .borrow()
+                .iter()
+                .map(|(_, rules)| rules)
+                .filter(|rules| rules.league_id == league_id && rules.season_id == season_id)
+                .max_by_key(|rules| rules.version)
+        })
+    };
+
+    season_id
+        .and_then(|season_id| latest(Some(season_id)))
+        .or_else(|| latest(None))
+}
+
+// The standings rules of a league's season: its rules document when it has one, otherwise the league's own
+fn effective_standings_rules(league: &League, season_id: Option<u64>) -> StandingsRules {
+    current_league_rules(league.id, season_id)
+        .map(|rules| rules.standings)
+        .unwrap_or_else(|| league.standings_rules.clone())
+}
+
+// The standings rules of a tournament: those of its league's season when it is played in a league, otherwise its own
+fn tournament_standings_rules(tournament: &Tournament) -> StandingsRules {
+    match tournament_league_id(tournament.id).and_then(|league_id| get_league(league_id).ok()) {
+        Some(league) => effective_standings_rules(
+            &league,
+            tournament_season(tournament.id).map(|season| season.id),
+        ),
+        None => tournament.standings_rules.clone(),
+    }
+}
+
+// The rules governing a match, from its league and season
+fn match_league_rules(match_obj: &Match) -> Option<LeagueRules> {
+    match_obj
+        .league_id
+        .and_then(|league_id| current_league_rules(league_id, match_obj.season_id))
+}
+
+// The rules of every league a team belongs to, for the season it is playing in when there is one
+fn team_league_rules(team_id: u64) -> Vec<LeagueRules> {
+    let leagues: Vec<League> = LEAGUES_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, league)| league)
+            .filter(|league| league.team_ids.contains(&team_id))
+            .collect()
+    });
+
+    leagues
+        .iter()
+        .filter_map(|league| {
+            let season_id = get_seasons_for_league(league.id)
+                .into_iter()
+                .rev()
+                .find(|season| !season.archived && season.team_ids.contains(&team_id))
+                .map(|season| season.id);
+            current_league_rules(league.id, season_id)
+        })
+        .collect()
+}
+
+// Check a lineup against a league's squad limits
+fn check_lineup_size(roster: &RosterLimits, payload: &SubmitLineupPayload) -> Result<(), String> {
+    let starters = payload.starters.len() as u32;
+
+    if starters < roster.min_starters {
+        return Err(format!(
+            "A lineup needs at least {} starters under the league's rules",
+            roster.min_starters
+        ));
+    }
+
+    if roster
+        .max_starters
+        .is_some_and(|max_starters| starters > max_starters)
+    {
+        return Err(format!(
+            "A lineup can have at most {} starters under the league's rules",
+            roster.max_starters.unwrap()
+        ));
+    }
+
+    if roster
+        .max_substitutes
+        .is_some_and(|max_substitutes| payload.substitutes.len() as u32 > max_substitutes)
+    {
+        return Err(format!(
+            "A lineup can have at most {} substitutes under the league's rules",
+            roster.max_substitutes.unwrap()
+        ));
+    }
+
+    Ok(())
+}
+
+// The winner of a submitted result must be the team its score, then extra time and any shoot-out, put ahead
+fn validate_result_winner(match_obj: &Match, result: &MatchResult) -> Result<(), String> {
+    let winner = winner_from_scores(
+        match_obj,
+        result.score_team_a,
+        result.score_team_b,
+        result.extra_time.as_ref(),
+    );
+
+    if result.winner_team_id != winner {
+        return Err(
+            "The winner must be the team ahead on the score, or on penalties when the score is level"
+                .to_string(),
+        );
+    }
+
+    Ok(())
+}
+
+// A forfeited match is awarded to the other team, which must be ahead on the awarded score
+fn validate_forfeit(match_obj: &Match, result: &MatchResult) -> Result<(), String> {
+    let forfeited_by = match result.forfeited_by {
+        Some(forfeited_by) => forfeited_by,
+        None => return Ok(()),
+    };
+
+    let (winner, winner_score, loser_score) = if forfeited_by == match_obj.home_team.id {
+        (
+            match_obj.away_team.id,
+            result.score_team_b,
+            result.score_team_a,
+        )
+    } else if forfeited_by == match_obj.away_team.id {
+        (
+            match_obj.home_team.id,
+            result.score_team_a,
+            result.score_team_b,
+        )
+    } else {
+        return Err(format!(
+            "Team with ID {} is not playing in this match",
+            forfeited_by
+        ));
+    };
+
+    if result.winner_team_id != Some(winner) || winner_score <= loser_score {
+        return Err("A forfeited match must be awarded to the other team".to_string());
+    }
+
+    Ok(())
+}
+
+/*
+Check a result is submitted within its league's confirmation deadline
+The deadline runs from the end of the match slot. Administrators and league officials may still record
+a result once it has passed.
+*/
+fn check_result_deadline(match_obj: &Match) -> Result<(), String> {
+    let hours =
+        match match_league_rules(match_obj).and_then(|rules| rules.result_confirmation_hours) {
+            Some(hours) => hours,
+            None => return Ok(()),
+        };
+
+    let (_, slot_end) = slot_range(&match_obj.scheduled_date, &match_obj.kickoff_time)?;
+    if now() <= slot_end + Duration::hours(hours as i64) {
+        return Ok(());
+    }
+
+    let is_organiser = get_caller_user().is_ok_and(|user| {
+        is_competition_official(&user, match_obj.league_id, match_obj.sport_type)
+    });
+    if !is_organiser {
+        return Err(format!(
+            "Results must be confirmed within {} hours of the match; ask a league official to record it",
+            hours
+        ));
+    }
+
+    Ok(())
+}
+
+// Check a league's rules document before it is saved
+fn validate_league_rules(payload: &SetLeagueRulesPayload) -> Result<(), String> {
+    validate_standings_rules(&payload.standings)?;
+
+    let roster = &payload.roster;
+    if roster.min_starters == 0 {
+        return Err("A lineup must need at least one starter".to_string());
+    }
+    if roster
+        .max_starters
+        .is_some_and(|max_starters| max_starters < roster.min_starters)
+    {
+        return Err("The most starters cannot be fewer than the fewest".to_string());
+    }
+    if let Some(max_squad_size) = roster.max_squad_size {
+        if max_squad_size < roster.min_starters
+            || max_squad_size < payload.eligibility.minimum_members
+        {
+            return Err(
+                "The squad size limit must allow the fewest starters and the members needed to register"
+                    .to_string(),
+            );
+        }
+    }
+
+    if payload.eligibility.allowed_universities.len() > MAX_RULES_UNIVERSITIES
+        || payload
+            .eligibility
+            .allowed_universities
+            .iter()
+            .any(|university| university.len() > MAX_UNIVERSITY_NAME_LENGTH)
+    {
+        return Err(format!(
+            "Rules can allow at most {} universities of up to {} characters",
+            MAX_RULES_UNIVERSITIES, MAX_UNIVERSITY_NAME_LENGTH
+        ));
+    }
+
+    for (index, threshold) in payload.disciplinary_thresholds.iter().enumerate() {
+        if threshold.threshold == 0 || threshold.suspension_matches == 0 {
+            return Err("Threshold and suspension length must both be at least 1".to_string());
+        }
+        if payload.disciplinary_thresholds[..index]
+            .iter()
+            .any(|other| other.card_type == threshold.card_type)
+        {
+            return Err(format!(
+                "Card type {:?} has more than one threshold",
+                threshold.card_type
+            ));
+        }
+    }
+
+    if payload
+        .result_confirmation_hours
+        .is_some_and(|hours| hours == 0 || hours > MAX_RESULT_CONFIRMATION_HOURS)
+    {
+        return Err(format!(
+            "The result confirmation deadline must be between 1 and {} hours",
+            MAX_RESULT_CONFIRMATION_HOURS
+        ));
+    }
+
+    Ok(())
+}
+
+/**
+ * Function for administrators and league officials to save a new version of a league's rules
+ * Rules without a season apply league-wide; rules for a season apply to it alone and take precedence.
+ * Each save adds a version, so the rules a season was played under stay on record.
+ */
+#[ic_cdk::update]
+fn set_league_rules(payload: SetLeagueRulesPayload) -> Result<LeagueRules, String> {
+    let league = get_league(payload.league_id)?;
+    let user = ensure_league_official(&league)?;
+
+    if let Some(season_id) = payload.season_id {
+        let season = get_season(season_id)?;
+        if season.league_id != league.id {
+            return Err(format!(
+                "Season {} is not a season of this league",
+                season.name
+            ));
+        }
+        ensure_season_open(&season)?;
+    }
+
+    validate_league_rules(&payload)?;
+
+    let version = LEAGUE_RULES_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, rules)| rules)
+            .filter(|rules| rules.league_id == league.id && rules.season_id == payload.season_id)
+            .map(|rules| rules.version)
+            .max()
+            .unwrap_or_default()
+    }) + 1;
+
+    let mut eligibility = payload.eligibility;
+    eligibility.allowed_universities = eligibility
+        .allowed_universities
+        .into_iter()
+        .filter_map(|university| normalize_university(Some(university)))
+        .collect();
+
+    let rules = LeagueRules {
+        id: generate_uuid(),
+        league_id: league.id,
+        season_id: payload.season_id,
+        version,
+        standings: payload.standings,
+        roster: payload.roster,
+        eligibility,
+        disciplinary_thresholds: payload.disciplinary_thresholds,
+        result_confirmation_hours: payload.result_confirmation_hours,
+        set_by: user.id,
+        set_at: ic_cdk::api::time(),
+    };
+
+    LEAGUE_RULES_STORAGE.with(|storage| storage.borrow_mut().insert(rules.id, rules.clone()));
+
+    Ok(rules)
+}
+
+// Get the rules currently in force for a league, or for one of its seasons
+#[ic_cdk::query]
+fn get_league_rules(league_id: u64, season_id: Option<u64>) -> Result<LeagueRules, String> {
+    current_league_rules(league_id, season_id)
+        .ok_or_else(|| "No rules have been set for this league".to_string())
+}
+
+// Get every version of a league's rules, league-wide versions first and then by season
+#[ic_cdk::query]
+fn get_league_rules_history(league_id: u64) -> Result<Vec<LeagueRules>, String> {
+    let mut history: Vec<LeagueRules> = LEAGUE_RULES_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, rules)| rules)
+            .filter(|rules| rules.league_id == league_id)
+            .collect()
+    });
+
+    if history.is_empty() {
+        return Err("No rules have been set for this league".to_string());
+    }
+
+    history.sort_by_key(|rules| (rules.season_id, rules.version));
+    Ok(history)
+}
+
+/**
+ * Function for administrators to appoint a league official to a league, a sport, or a league in a sport
+ * The appointment scopes what the official may run; appointments are never deleted, only revoked.
+ */
+#[ic_cdk::update]
+fn appoint_league_official(payload: AppointOfficialPayload) -> Result<OfficialAppointment, String> {
+    let administrator = ensure_caller_role(&[UserRole::Administrator])?;
+
+    let official = get_user(payload.user_id)?;
+    if official.role != UserRole::LeagueOfficial {
+        return Err(format!("{} is not a league official", official.name));
+    }
+
+    match (payload.league_id, payload.sport_type) {
+        (None, None) => {
+            return Err("An appointment must name a league, a sport, or both".to_string());
+        }
+        (Some(league_id), sport_type) => {
+            let league = get_league(league_id)?;
+            if sport_type.is_some_and(|sport_type| sport_type != league.sport_type) {
+                return Err(format!(
+                    "League {} plays {:?}",
+                    league.name, league.sport_type
+                ));
+            }
+        }
+        (None, Some(_)) => {}
+    }
+
+    let already_appointed = APPOINTMENTS_STORAGE.with(|storage| {
+        storage.borrow().iter().any(|(_, appointment)| {
+            appointment.user_id == official.id
+                && appointment.revoked_at.is_none()
+                && appointment.league_id == payload.league_id
+                && appointment.sport_type == payload.sport_type
+        })
+    });
+    if already_appointed {
+        return Err(format!("{} already holds this appointment", official.name));
+    }
+
+    let appointment = OfficialAppointment {
+        id: generate_uuid(),
+        user_id: official.id,
+        league_id: payload.league_id,
+        sport_type: payload.sport_type,
+        appointed_by: administrator.id,
+        appointed_at: ic_cdk::api::time(),
+        revoked_by: None,
+        revoked_at: None,
+    };
+
+    APPOINTMENTS_STORAGE.with(|storage| {
+        storage
+            .borrow_mut()
+            .insert(appointment.id, appointment.clone())
+    });
+
+    Ok(appointment)
+}
+
+// Function for administrators to revoke a league official's appointment
+#[ic_cdk::update]
+fn revoke_league_official(appointment_id: u64) -> Result<OfficialAppointment, String> {
+    let administrator = ensure_caller_role(&[UserRole::Administrator])?;
+
+    let mut appointment = APPOINTMENTS_STORAGE
+        .with(|storage| storage.borrow().get(&appointment_id))
+        .ok_or_else(|| format!("Appointment with ID {} not found", appointment_id))?;
+
+    if appointment.revoked_at.is_some() {
+        return Err("Appointment has already been revoked".to_string());
+    }
+
+    appointment.revoked_by = Some(administrator.id);
+    appointment.revoked_at = Some(ic_cdk::api::time());
+
+    APPOINTMENTS_STORAGE.with(|storage| {
+        storage
+            .borrow_mut()
+            .insert(appointment.id, appointment.clone())
+    });
+
+    Ok(appointment)
+}
+
+// Get the appointments matching a filter, oldest first
+fn get_appointments(filter: impl Fn(&OfficialAppointment) -> bool) -> Vec<OfficialAppointment> {
+    APPOINTMENTS_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, appointment)| appointment)
+            .filter(|appointment| filter(appointment))
+            .collect()
+    })
+}
+
+// Get the appointment history of a league official, including revoked appointments
+#[ic_cdk::query]
+fn get_official_appointments(user_id: u64) -> Result<Vec<OfficialAppointment>, String> {
+    let appointments = get_appointments(|appointment| appointment.user_id == user_id);
+    if appointments.is_empty() {
+        Err("No appointments found for this user".to_string())
+    } else {
+        Ok(appointments)
+    }
+}
+
+// Get the active appointments covering a league, both to the league itself and to its sport
+#[ic_cdk::query]
+fn get_league_officials(league_id: u64) -> Result<Vec<OfficialAppointment>, String> {
+    let league = get_league(league_id)?;
+    let appointments = get_appointments(|appointment| {
+        appointment.revoked_at.is_none()
+            && match appointment.league_id {
+                Some(appointed_league_id) => appointed_league_id == league.id,
+                None => appointment.sport_type == Some(league.sport_type),
+            }
+    });
+    if appointments.is_empty() {
+        Err("No officials are appointed to this league".to_string())
+    } else {
+        Ok(appointments)
+    }
+}
+
+// Get every appointment ever made to a league, including revoked appointments
+#[ic_cdk::query]
+fn get_league_appointment_history(league_id: u64) -> Result<Vec<OfficialAppointment>, String> {
+    let appointments = get_appointments(|appointment| appointment.league_id == Some(league_id));
+    if appointments.is_empty() {
+        Err("No appointments have been made to this league".to_string())
+    } else {
+        Ok(appointments)
+    }
+}
+
+// Check a scale of placing points: at least one placing, and never more for a lower placing
+fn validate_placing_points(placing_points: &[u32]) -> Result<(), String> {
+    if placing_points.is_empty() || placing_points.len() > MAX_PLACING_POINTS {
+        return Err(format!(
+            "Placing points must cover between 1 and {} placings",
+            MAX_PLACING_POINTS
+        ));
+    }
+
+    if placing_points.windows(2).any(|pair| pair[1] > pair[0]) {
+        return Err("A lower placing cannot be worth more points than a higher one".to_string());
+    }
+
+    Ok(())
+}
+
+// Save an overall championship
+fn save_championship(championship: &Championship) {
+    CHAMPIONSHIPS_STORAGE.with(|storage| {
+        storage
+            .borrow_mut()
+            .insert(championship.id, championship.clone())
+    });
+}
+
+// Function for administrators to create an overall championship with its placing points, 1st place first
+#[ic_cdk::update]
+fn create_championship(payload: CreateChampionshipPayload) -> Result<Championship, String> {
+    let creator = ensure_caller_role(&[UserRole::Administrator])?;
+
+    let name = validate_tournament_name(&payload.name)?;
+    validate_placing_points(&payload.placing_points)?;
+
+    let championship = Championship {
+        id: generate_uuid(),
+        name,
+        placing_points: payload.placing_points,
+        competitions: Vec::new(),
+        created_by: creator.id,
+        created_at: ic_cdk::api::time(),
+        updated_at: None,
+    };
+
+    save_championship(&championship);
+
+    Ok(championship)
+}
+
+// Function for administrators to rename an overall championship or change its placing points
+#[ic_cdk::update]
+fn update_championship(payload: UpdateChampionshipPayload) -> Result<Championship, String> {
+    ensure_caller_role(&[UserRole::Administrator])?;
+
+    let mut championship = get_championship(payload.id)?;
+    championship.name = validate_tournament_name(&payload.name)?;
+    validate_placing_points(&payload.placing_points)?;
+    championship.placing_points = payload.placing_points;
+    championship.updated_at = Some(ic_cdk::api::time());
+
+    save_championship(&championship);
+
+    Ok(championship)
+}
+
+/**
+ * Function for administrators to count a league season towards an overall championship
+ * The season's final placings score the championship's placing points unless the season is given its own.
+ */
+#[ic_cdk::update]
+fn add_championship_competition(
+    payload: ChampionshipCompetitionPayload,
+) -> Result<Championship, String> {
+    ensure_caller_role(&[UserRole::Administrator])?;
+
+    let mut championship = get_championship(payload.championship_id)?;
+    let season = get_season(payload.season_id)?;
+
+    if championship
+        .competitions
+        .iter()
+        .any(|competition| competition.season_id == season.id)
+    {
+        return Err(format!(
+            "Season {} already counts towards this championship",
+            season.name
+        ));
+    }
+
+    if championship.competitions.len() >= MAX_CHAMPIONSHIP_COMPETITIONS {
+        return Err(format!(
+            "A championship can count at most {} competitions",
+            MAX_CHAMPIONSHIP_COMPETITIONS
+        ));
+    }
+
+    if let Some(placing_points) = &payload.placing_points {
+        validate_placing_points(placing_points)?;
+    }
+
+    championship.competitions.push(ChampionshipCompetition {
+        season_id: season.id,
+        placing_points: payload.placing_points,
+    });
+    championship.updated_at = Some(ic_cdk::api::time());
+    save_championship(&championship);
+
+    Ok(championship)
+}
+
+// Function for administrators to stop counting a league season towards an overall championship
+#[ic_cdk::update]
+fn remove_championship_competition(
+    championship_id: u64,
+    season_id: u64,
+) -> Result<Championship, String> {
+    ensure_caller_role(&[UserRole::Administrator])?;
+
+    let mut championship = get_championship(championship_id)?;
+
+    if !championship
+        .competitions
+        .iter()
+        .any(|competition| competition.season_id == season_id)
+    {
+        return Err("Season does not count towards this championship".to_string());
+    }
+
+    championship
+        .competitions
+        .retain(|competition| competition.season_id != season_id);
+    championship.updated_at = Some(ic_cdk::api::time());
+    save_championship(&championship);
+
+    Ok(championship)
+}
+
+// Fetch an overall championship by ID
+#[ic_cdk::query]
+fn get_championship(id: u64) -> Result<Championship, String> {
+    CHAMPIONSHIPS_STORAGE.with(|storage| match storage.borrow().get(&id) {
+        Some(championship) => Ok(championship.clone()),
+        None => Err(format!("Championship with ID {} not found", id)),
+    })
+}
+
+// Get all overall championships
+#[ic_cdk::query]
+fn get_all_championships() -> Result<Vec<Championship>, String> {
+    CHAMPIONSHIPS_STORAGE.with(|storage| {
+        let championships: Vec<Championship> = storage
+            .borrow()
+            .iter()
+            .map(|(_, championship)| championship.clone())
+            .collect();
+        if championships.is_empty() {
+            Err("No championships found".to_string())
+        } else {
+            Ok(championships)
+        }
+    })
+}
+
+// The final order of a season's teams: division by division from the top when it has divisions, otherwise its standings
+fn season_final_order(season: &Season) -> Result<Vec<u64>, String> {
+    let season_divisions = load_season_divisions(season.id);
+    if season_divisions.divisions.is_empty() {
+        return Ok(get_season_standings(season.id)?
+            .into_iter()
+            .map(|entry| entry.team_id)
+            .collect());
+    }
+
+    let mut order = Vec::new();
+    for division in get_divisions_for_league(season.league_id) {
+        if season_divisions
+            .divisions
+            .iter()
+            .any(|season_division| season_division.division_id == division.id)
+        {
+            order.extend(
+                division_standings(season, division.id)?
+                    .into_iter()
+                    .map(|entry| entry.team_id),
+            );
+        }
+    }
+    Ok(order)
+}
+
+/*
+Build the overall championship table from the placings in each counted season
+Only a university's best-placed team in a season scores, and teams without a university score nothing.
+Placings only count once their season is archived, unless provisional placings from seasons still under way
+are asked for. Universities level on points share a position.
+*/
+#[ic_cdk::query]
+fn get_championship_table(
+    championship_id: u64,
+    include_provisional: bool,
+) -> Result<Vec<ChampionshipStanding>, String> {
+    let championship = get_championship(championship_id)?;
+
+    let mut table: Vec<ChampionshipStanding> = Vec::new();
+
+    for competition in &championship.competitions {
+        let season = get_season(competition.season_id)?;
+        if !season.archived && !include_provisional {
+            continue;
+        }
+
+        let sport_type = get_league(season.league_id)?.sport_type;
+        let placing_points = competition
+            .placing_points
+            .as_ref()
+            .unwrap_or(&championship.placing_points);
+
+        let mut placed_universities: Vec<String> = Vec::new();
+        for (index, team_id) in season_final_order(&season)?.into_iter().enumerate() {
+            let university = match get_team(team_id)?.university {
+                Some(university) => university,
+                None => continue,
+            };
+            if placed_universities.contains(&university) {
+                continue;
+            }
+            placed_universities.push(university.clone());
+
+            let points = placing_points.get(index).copied().unwrap_or_default();
+            let placing = ChampionshipPlacing {
+                season_id: season.id,
+                sport_type,
+                team_id,
+                placing: index as u32 + 1,
+                points,
+                provisional: !season.archived,
+            };
+
+            let position = match table
+                .iter()
+                .position(|standing| standing.university == university)
+            {
+                Some(position) => position,
+                None => {
+                    table.push(ChampionshipStanding {
+                        position: 0,
+                        university,
+                        points: 0,
+                        by_sport: Vec::new(),
+                        placings: Vec::new(),
+                    });
+                    table.len() - 1
+                }
+            };
+
+            let standing = &mut table[position];
+            standing.points += points;
+            match standing
+                .by_sport
+                .iter_mut()
+                .find(|sport_points| sport_points.sport_type == sport_type)
+            {
+                Some(sport_points) => sport_points.points += points,
+                None => standing.by_sport.push(SportPoints { sport_type, points }),
+            }
+            standing.placings.push(placing);
+        }
+    }
+
+    table.sort_by(|a, b| {
+        b.points
+            .cmp(&a.points)
+            .then_with(|| a.university.cmp(&b.university))
+    });
+
+    for index in 0..table.len() {
+        table[index].position = if index > 0 && table[index].points == table[index - 1].points {
+            table[index - 1].position
+        } else {
+            index as u32 + 1
+        };
+    }
+
+    Ok(table)
+}
+
+// Get the matches of a tournament, ordered by round
+fn get_tournament_matches(tournament_id: u64) -> Vec<Match> {
+    get_indexed_matches(tournament_id, None)
+}
+
+// Add a match to the index under each competition it belongs to
+fn index_match(match_obj: &Match) {
+    let round = match_obj.round.unwrap_or_default();
+    for competition_id in [
+        match_obj.league_id,
+        match_obj.season_id,
+        match_obj.tournament_id,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        let key = MatchIndexKey {
+            competition_id,
+            round,
+            match_id: match_obj.id,
+        };
+        MATCH_INDEX_STORAGE.with(|storage| storage.borrow_mut().insert(key, match_obj.id));
+    }
+}
+
+// Rebuild the match index from every stored match; adding a match again leaves its keys unchanged
+fn index_all_matches() {
+    let matches: Vec<Match> = MATCHES_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, match_obj)| match_obj)
+            .collect()
+    });
+
+    for match_obj in &matches {
+        index_match(match_obj);
+    }
+}
+
+// Get the matches of a league, season or tournament from the index, optionally of one round, ordered by round
+fn get_indexed_matches(competition_id: u64, round: Option<u32>) -> Vec<Match> {
+    let start = MatchIndexKey {
+        competition_id,
+        round: round.unwrap_or(0),
+        match_id: 0,
+    };
+    let end = MatchIndexKey {
+        competition_id,
+        round: round.unwrap_or(u32::MAX),
+        match_id: u64::MAX,
+    };
+
+    let match_ids: Vec<u64> = MATCH_INDEX_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .range(start..=end)
+            .map(|(_, match_id)| match_id)
+            .collect()
+    });
+
+    MATCHES_STORAGE.with(|storage| {
+        let storage = storage.borrow();
+        match_ids
+            .iter()
+            .filter_map(|match_id| storage.get(match_id))
+            .collect()
+    })
+}
+
+/**
+ * Function to find the matches of a league, season or tournament
+ * Matches can be narrowed to one round, stage or leg, for example every match in round 3 of a tournament.
+ */
+#[ic_cdk::query]
+fn get_competition_matches(payload: CompetitionMatchesPayload) -> Result<Vec<Match>, String> {
+    let matches: Vec<Match> = get_indexed_matches(payload.competition_id, payload.round)
+        .into_iter()
+        .filter(|match_obj| payload.stage.is_none() || match_obj.stage == payload.stage)
+        .filter(|match_obj| payload.leg.is_none() || match_obj.leg == payload.leg)
+        .collect();
+
+    if matches.is_empty() {
+        Err("No matches found for this competition".to_string())
+    } else {
+        Ok(matches)
+    }
+}
+
+// Find the league a tournament is played in
+fn tournament_league_id(tournament_id: u64) -> Option<u64> {
+    LEAGUES_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .find(|(_, league)| league.tournament_ids.contains(&tournament_id))
+            .map(|(id, _)| id)
+    })
+}
+
+// Create and store a match between two teams of a tournament
+fn create_tournament_match(
+    tournament: &Tournament,
+    home_team_id: u64,
+    away_team_id: u64,
+    round: u32,
+    scheduled_date: &str,
+    kickoff_time: &Option<String>,
+    stage: MatchStage,
+) -> Result<Match, String> {
+    let season = tournament_season(tournament.id);
+    if let Some(season) = &season {
+        check_season_match_date(season, scheduled_date)?;
+    }
+
+    let match_obj = Match {
+        id: generate_uuid(),
+        home_team: get_team(home_team_id)?,
+        away_team: get_team(away_team_id)?,
+        sport_type: tournament.sport_type,
+        scheduled_date: scheduled_date.to_string(),
+        kickoff_time: kickoff_time.clone(),
+        result: None,
+        tournament_id: Some(tournament.id),
+        round: Some(round),
+        league_id: tournament_league_id(tournament.id),
+        season_id: season.map(|season| season.id),
+        stage: Some(stage),
+        leg: None,
+    };
+
+    MATCHES_STORAGE.with(|storage| storage.borrow_mut().insert(match_obj.id, match_obj.clone()));
+    index_match(&match_obj);
+
+    Ok(match_obj)
+}
+
+// Number a tournament match as a leg of a two-legged tie
+fn set_match_leg(mut match_obj: Match, leg: u32) {
+    match_obj.leg = Some(leg);
+    MATCHES_STORAGE.with(|storage| storage.borrow_mut().insert(match_obj.id, match_obj));
+}
+
+// Home and away team of every pairing in each round, with None standing for a bye
+type RoundPairings = Vec<Vec<(Option<u64>, Option<u64>)>>;
+
+/*
+Pair the teams of a round robin with the circle method
+One team stays fixed while the others rotate, and a placeholder team gives a bye when the count is odd.
+The fixed team alternates home and away each round and the remaining pairs host from the top row,
+so no team plays more than one more home match than away matches.
+A double round robin repeats every round with home and away swapped.
+*/
+fn round_robin_pairings(team_ids: &[u64], double_round_robin: bool) -> RoundPairings {
+    let mut slots: Vec<Option<u64>> = team_ids.iter().map(|id| Some(*id)).collect();
+    if slots.len() % 2 == 1 {
+        slots.push(None);
+    }
+
+    let size = slots.len();
+    let mut rounds = Vec::new();
+
+    for round in 0..size - 1 {
+        let mut pairings = Vec::new();
+        for index in 0..size / 2 {
+            let top = slots[index];
+            let bottom = slots[size - 1 - index];
+            if index == 0 && round % 2 == 1 {
+                pairings.push((bottom, top));
+            } else {
+                pairings.push((top, bottom));
+            }
+        }
+        rounds.push(pairings);
+
+        // Keep the first team fixed and rotate the rest
+        slots[1..].rotate_right(1);
+    }
+
+    if double_round_robin {
+        let return_legs: RoundPairings = rounds
+            .iter()
+            .map(|round| round.iter().map(|(home, away)| (*away, *home)).collect())
+            .collect();
+        rounds.extend(return_legs);
+    }
+
+    rounds
+}
+
+/**
+ * Function to generate the complete fixture list of a round-robin tournament
+ * A double round robin repeats every pairing with home and away swapped.
+ * Rounds are spread evenly over the match days between the start and end dates; an empty list of match days allows every day.
+ */
+#[ic_cdk::update]
+fn generate_round_robin(payload: GenerateRoundRobinPayload) -> Result<Vec<FixtureRound>, String> {
+    let tournament = get_tournament(payload.tournament_id)?;
+    ensure_tournament_official(&tournament)?;
+
+    if tournament.structure != TournamentStructure::RoundRobin {
+        return Err("Fixtures can only be generated for round-robin tournaments".to_string());
+    }
+
+    ensure_ready_for_draw(&tournament)?;
+
+    if !get_tournament_matches(tournament.id).is_empty() {
+        return Err("Fixtures have already been generated for this tournament".to_string());
+    }
+
+    slot_range(&payload.start_date, &payload.kickoff_time)?;
+
+    let pairings = round_robin_pairings(&tournament.team_ids, payload.double_round_robin);
+
+    let round_dates = spread_round_dates(
+        &payload.start_date,
+        &payload.end_date,
+        &payload.match_days,
+        &tournament_blackouts(tournament.id)?,
+        pairings.len(),
+    )?;
+
+    for (index, round) in pairings.iter().enumerate() {
+        for (home, away) in round {
+            if let (Some(home), Some(away)) = (home, away) {
+                create_tournament_match(
+                    &tournament,
+                    *home,
+                    *away,
+                    index as u32 + 1,
+                    &round_dates[index],
+                    &payload.kickoff_time,
+                    MatchStage::RoundRobin,
+                )?;
+            }
+        }
+    }
+
+    let tournament_id = tournament.id;
+    start_tournament(tournament);
+
+    get_tournament_fixtures(tournament_id)
+}
+
+// Mark a tournament as in progress once its fixtures exist, locking its entries
+fn start_tournament(mut tournament: Tournament) {
+    tournament.status = TournamentStatus::InProgress;
+    lock_registration(tournament.id);
+    TOURNAMENTS_STORAGE.with(|storage| storage.borrow_mut().insert(tournament.id, tournament));
+}
+
+// Lock a tournament's entries once its draw has been made
+fn lock_registration(tournament_id: u64) {
+    if let Some(mut registration) =
+        REGISTRATIONS_STORAGE.with(|storage| storage.borrow().get(&tournament_id))
+    {
+        if registration.locked {
+            return;
+        }
+        registration.locked = true;
+        registration.locked_at = Some(ic_cdk::api::time());
+        REGISTRATIONS_STORAGE
+            .with(|storage| storage.borrow_mut().insert(tournament_id, registration));
+    }
+}
+
+// A draw needs at least two teams, all registered for the tournament's season when it has one
+fn ensure_ready_for_draw(tournament: &Tournament) -> Result<(), String> {
+    if tournament.team_ids.len() < 2 {
+        return Err("A tournament needs at least 2 teams before its draw".to_string());
+    }
+
+    if let Some(season) = tournament_season(tournament.id) {
+        ensure_season_open(&season)?;
+        for team_id in &tournament.team_ids {
+            if !season.team_ids.contains(team_id) {
+                return Err(format!(
+                    "Team {} is not registered for season {}",
+                    get_team(*team_id)?.name,
+                    season.name
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Get a tournament's fixtures grouped by round, with the teams that have a bye in each round
+#[ic_cdk::query]
+fn get_tournament_fixtures(tournament_id: u64) -> Result<Vec<FixtureRound>, String> {
+    let tournament = get_tournament(tournament_id)?;
+
+    let mut rounds: BTreeMap<u32, Vec<Match>> = BTreeMap::new();
+    for match_obj in get_tournament_matches(tournament_id) {
+        rounds
+            .entry(match_obj.round.unwrap_or_default())
+            .or_default()
+            .push(match_obj);
+    }
+
+    if rounds.is_empty() {
+        return Err("No fixtures found for this tournament".to_string());
+    }
+
+    Ok(rounds
+        .into_iter()
+        .map(|(round, matches)| FixtureRound {
+            round,
+            scheduled_date: matches[0].scheduled_date.clone(),
+            match_ids: matches.iter().map(|match_obj| match_obj.id).collect(),
+            bye_team_ids: tournament
+                .team_ids
+                .iter()
+                .filter(|team_id| {
+                    !matches.iter().any(|match_obj| {
+                        match_obj.home_team.id == **team_id || match_obj.away_team.id == **team_id
+                    })
+                })
+                .copied()
+                .collect(),
+        })
+        .collect())
+}
+
+// Order the seeds of a bracket of the given size so that higher seeds only meet in later rounds
+fn bracket_seed_order(size: u32) -> Vec<u32> {
+    let mut order = vec![1];
+    while (order.len() as u32) < size {
+        let total = order.len() as u32 * 2 + 1;
+        order = order
+            .iter()
+            .flat_map(|seed| [*seed, total - seed])
+            .collect();
+    }
+    order
+}
+
+// Record the winner of a bracket node and place them into the node they advance to
+fn advance_in_bracket(bracket: &mut Bracket, node_id: u32, team_id: u64) {
+    let node = &mut bracket.nodes[node_id as usize - 1];
+    node.winner_team_id = Some(team_id);
+    let seed = if node.home_team_id == Some(team_id) {
+        node.home_seed
+    } else {
+        node.away_seed
+    };
+
+    if let Some(next_node_id) = node.next_node_id {
+        let next = &mut bracket.nodes[next_node_id as usize - 1];
+        if node_id % 2 == 0 {
+            next.home_team_id = Some(team_id);
+            next.home_seed = seed;
+        } else {
+            next.away_team_id = Some(team_id);
+            next.away_seed = seed;
+        }
+    }
+}
+
+// Create the matches of every bracket node whose two teams are known
+fn schedule_bracket_matches(tournament: &Tournament, bracket: &mut Bracket) -> Result<(), String> {
+    let round_dates = bracket.round_dates.clone();
+    let kickoff_time = bracket.kickoff_time.clone();
+    let round_offset = bracket.round_offset;
+    let rounds = bracket.rounds;
+    let two_legged = bracket.two_legged.clone();
+
+    for node in bracket
+        .nodes
+        .iter_mut()
+        .chain(bracket.third_place.iter_mut())
+    {
+        if node.match_id.is_some() || node.winner_team_id.is_some() {
+            continue;
+        }
+
+        if let (Some(home), Some(away)) = (node.home_team_id, node.away_team_id) {
+            let stage = match node.id {
+                0 => MatchStage::ThirdPlace,
+                1 => MatchStage::Final,
+                _ => MatchStage::Knockout,
+            };
+            let match_obj = create_tournament_match(
+                tournament,
+                home,
+                away,
+                round_offset + node.round,
+                &round_dates[node.round as usize - 1],
+                &kickoff_time,
+                stage,
+            )?;
+            node.match_id = Some(match_obj.id);
+
+            // The third-place match, and the final when it is played once, have a single leg
+            let second_leg_date = match &two_legged {
+                Some(rules)
+                    if node.id != 0 && !(rules.single_leg_final && node.round == rounds) =>
+                {
+                    Some(rules.second_leg_dates[node.round as usize - 1].clone())
+                }
+                _ => None,
+            };
+
+            if let Some(second_leg_date) = second_leg_date {
+                let second_leg = create_tournament_match(
+                    tournament,
+                    away,
+                    home,
+                    round_offset + node.round,
+                    &second_leg_date,
+                    &kickoff_time,
+                    stage,
+                )?;
+                set_match_leg(match_obj.clone(), 1);
+                set_match_leg(second_leg.clone(), 2);
+
+                let tie = TwoLeggedTie {
+                    id: generate_uuid(),
+                    tournament_id: tournament.id,
+                    team_a_id: home,
+                    team_b_id: away,
+                    first_leg_match_id: match_obj.id,
+                    second_leg_match_id: second_leg.id,
+                    away_goals: two_legged.as_ref().is_some_and(|rules| rules.away_goals),
+                    aggregate_team_a: 0,
+                    aggregate_team_b: 0,
+                    winner_team_id: None,
+                    decided_by: None,
+                };
+
+                TIES_STORAGE.with(|storage| storage.borrow_mut().insert(tie.id, tie.clone()));
+                node.tie_id = Some(tie.id);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Find the knockout bracket a match belongs to
+fn get_bracket_for_match(match_obj: &Match) -> Option<Bracket> {
+    let bracket =
+        BRACKETS_STORAGE.with(|storage| storage.borrow().get(&match_obj.tournament_id?))?;
+    let tie_id = get_tie_for_match(match_obj).map(|tie| tie.id);
+
+    let in_bracket = bracket
+        .nodes
+        .iter()
+        .chain(bracket.third_place.iter())
+        .any(|node| {
+            node.match_id == Some(match_obj.id) || (tie_id.is_some() && node.tie_id == tie_id)
+        });
+
+    if in_bracket {
+        Some(bracket)
+    } else {
+        None
+    }
+}
+
+// Knockout and double-elimination matches must produce a winner from the two teams playing, on the score
+// or a shoot-out, except the legs of a two-legged tie, which is decided on aggregate
+fn validate_knockout_result(match_obj: &Match, result: &MatchResult) -> Result<(), String> {
+    if let Some(tie) = get_tie_for_match(match_obj) {
+        return validate_tie_leg(&tie, match_obj, result);
+    }
+
+    let in_double_elimination = get_double_elimination_for_match(match_obj).is_some();
+    if get_bracket_for_match(match_obj).is_none() && !in_double_elimination {
+        return Ok(());
+    }
+
+    let shootout = result
+        .extra_time
+        .as_ref()
+        .is_some_and(|extra_time| extra_time.penalties.is_some());
+    if result.score_team_a == result.score_team_b && !shootout {
+        return Err(
+            "A knockout match level on the score must be decided by a penalty shoot-out"
+                .to_string(),
+        );
+    }
+
+    let winner = winner_from_scores(
+        match_obj,
+        result.score_team_a,
+        result.score_team_b,
+        result.extra_time.as_ref(),
+    );
+    if winner.is_none() || result.winner_team_id != winner {
+        return Err("Knockout matches must have a winner from the two teams playing".to_string());
+    }
+
+    Ok(())
+}
+
+/*
+Advance the winner of a completed knockout match
+The winner moves into the next round's tie and its match is created once both teams are known;
+beaten semi-finalists move into the third-place match when the bracket has one.
+The tournament is completed with its champion once the final, and any third-place match, is decided.
+*/
+fn advance_bracket(match_obj: &Match) -> Result<(), String> {
+    let mut bracket = match get_bracket_for_match(match_obj) {
+        Some(bracket) => bracket,
+        None => return Ok(()),
+    };
+
+    let tie = get_tie_for_match(match_obj);
+    let tie_id = tie.as_ref().map(|tie| tie.id);
+
+    let winner_team_id = match tie {
+        Some(tie) => match complete_tie_leg(tie)? {
+            Some(winner_team_id) => winner_team_id,
+            None => return Ok(()),
+        },
+        None => match match_obj
+            .result
+            .as_ref()
+            .and_then(|result| result.winner_team_id)
+        {
+            Some(winner_team_id)
+                if winner_team_id == match_obj.home_team.id
+                    || winner_team_id == match_obj.away_team.id =>
+            {
+                winner_team_id
+            }
+            _ => {
+                return Err(
+                    "Knockout matches must have a winner from the two teams playing".to_string(),
+                )
+            }
+        },
+    };
+
+    let loser_team_id = if winner_team_id == match_obj.home_team.id {
+        match_obj.away_team.id
+    } else {
+        match_obj.home_team.id
+    };
+
+    let mut tournament = get_tournament(bracket.tournament_id)?;
+
+    match bracket.third_place.as_mut() {
+        Some(third_place) if third_place.match_id == Some(match_obj.id) => {
+            third_place.winner_team_id = Some(winner_team_id);
+        }
+        _ => {
+            let node_id = bracket
+                .nodes
+                .iter()
+                .find(|node| {
+                    node.match_id == Some(match_obj.id)
+                        || (tie_id.is_some() && node.tie_id == tie_id)
+                })
+                .map(|node| node.id)
+                .ok_or_else(|| "Match is not part of the bracket".to_string())?;
+
+            let previous_winner = bracket.nodes[node_id as usize - 1].winner_team_id;
+            match previous_winner {
+                Some(previous_winner) if previous_winner == winner_team_id => {}
+                // A corrected result changes who goes through
+                Some(previous_winner) => {
+                    replace_bracket_winner(&mut bracket, node_id, previous_winner, winner_team_id)?
+                }
+                None => advance_in_bracket(&mut bracket, node_id, winner_team_id),
+            }
+
+            // Node 2 and 3 are the semi-finals
+            if previous_winner.is_none() && (node_id == 2 || node_id == 3) {
+                let semi_final = &bracket.nodes[node_id as usize - 1];
+                let seed = if semi_final.home_team_id == Some(loser_team_id) {
+                    semi_final.home_seed
+                } else {
+                    semi_final.away_seed
+                };
+                if let Some(third_place) = bracket.third_place.as_mut() {
+                    if node_id == 2 {
+                        third_place.home_team_id = Some(loser_team_id);
+                        third_place.home_seed = seed;
+                    } else {
+                        third_place.away_team_id = Some(loser_team_id);
+                        third_place.away_seed = seed;
+                    }
+                }
+            }
+
+            if node_id == 1 {
+                tournament.champion_team_id = Some(winner_team_id);
+            }
+        }
+    }
+
+    schedule_bracket_matches(&tournament, &mut bracket)?;
+
+    let final_decided = bracket.nodes[0].winner_team_id.is_some();
+    let third_place_decided = match &bracket.third_place {
+        Some(third_place) => third_place.winner_team_id.is_some(),
+        None => true,
+    };
+
+    if final_decided && third_place_decided {
+        tournament.status = TournamentStatus::Completed;
+    }
+
+    TOURNAMENTS_STORAGE.with(|storage| storage.borrow_mut().insert(tournament.id, tournament));
+    BRACKETS_STORAGE.with(|storage| storage.borrow_mut().insert(bracket.tournament_id, bracket));
+
+    Ok(())
+}
+
+/*
+Replace the winner of a bracket node whose result was corrected
+The new winner takes the old winner's place in the next round and, after a semi-final, the old winner takes the
+new winner's place in the third-place match. Matches already scheduled for those places are updated,
+but the correction is refused once any of them has been played.
+*/
+fn replace_bracket_winner(
+    bracket: &mut Bracket,
+    node_id: u32,
+    previous_winner: u64,
+    winner_team_id: u64,
+) -> Result<(), String> {
+    let node = &mut bracket.nodes[node_id as usize - 1];
+    node.winner_team_id = Some(winner_team_id);
+    let seed_of = |team_id: u64| {
+        if node.home_team_id == Some(team_id) {
+            node.home_seed
+        } else {
+            node.away_seed
+        }
+    };
+    let (seed, previous_seed) = (seed_of(winner_team_id), seed_of(previous_winner));
+
+    if let Some(next_node_id) = node.next_node_id {
+        replace_bracket_team(
+            &mut bracket.nodes[next_node_id as usize - 1],
+            previous_winner,
+            winner_team_id,
+            seed,
+        )?;
+    }
+
+    if node_id == 2 || node_id == 3 {
+        if let Some(third_place) = bracket.third_place.as_mut() {
+            replace_bracket_team(third_place, winner_team_id, previous_winner, previous_seed)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Put a team in another's place in a bracket node, along with the matches already scheduled for the node
+fn replace_bracket_team(
+    node: &mut BracketNode,
+    old_team_id: u64,
+    new_team_id: u64,
+    seed: Option<u32>,
+) -> Result<(), String> {
+    if node.winner_team_id.is_some() {
+        return Err(
+            "The result can no longer be corrected, as the next round has been decided".to_string(),
+        );
+    }
+
+    if node.home_team_id == Some(old_team_id) {
+        node.home_team_id = Some(new_team_id);
+        node.home_seed = seed;
+    } else if node.away_team_id == Some(old_team_id) {
+        node.away_team_id = Some(new_team_id);
+        node.away_seed = seed;
+    } else {
+        return Ok(());
+    }
+
+    let match_ids = match node.tie_id {
+        Some(tie_id) => {
+            let mut tie = get_two_legged_tie(tie_id)?;
+            if tie.team_a_id == old_team_id {
+                tie.team_a_id = new_team_id;
+            } else if tie.team_b_id == old_team_id {
+                tie.team_b_id = new_team_id;
+            }
+            TIES_STORAGE.with(|storage| storage.borrow_mut().insert(tie.id, tie.clone()));
+            vec![tie.first_leg_match_id, tie.second_leg_match_id]
+        }
+        None => node.match_id.into_iter().collect(),
+    };
+
+    for match_id in match_ids {
+        replace_match_team(match_id, old_team_id, new_team_id)?;
+    }
+
+    Ok(())
+}
+
+// Put a team in another's place in a match that has not been played yet
+fn replace_match_team(match_id: u64, old_team_id: u64, new_team_id: u64) -> Result<(), String> {
+    let mut match_obj = get_match(match_id)?;
+
+    if match_obj.result.is_some() {
+        return Err(
+            "The result can no longer be corrected, as the next round has been played".to_string(),
+        );
+    }
+
+    let team = get_team(new_team_id)?;
+    if match_obj.home_team.id == old_team_id {
+        match_obj.home_team = team;
+    } else if match_obj.away_team.id == old_team_id {
+        match_obj.away_team = team;
+    }
+
+    MATCHES_STORAGE.with(|storage| storage.borrow_mut().insert(match_id, match_obj));
+
+    Ok(())
+}
+
+// Check a seeding lists every team of the tournament once, defaulting to the tournament's order
+fn validate_seeding(
+    tournament: &Tournament,
+    seeded_team_ids: Vec<u64>,
+) -> Result<Vec<u64>, String> {
+    if seeded_team_ids.is_empty() {
+        return Ok(tournament.team_ids.clone());
+    }
+
+    let mut given = seeded_team_ids.clone();
+    let mut expected = tournament.team_ids.clone();
+    given.sort_unstable();
+    expected.sort_unstable();
+    if given != expected {
+        return Err("The seeding must list every team of the tournament once".to_string());
+    }
+
+    Ok(seeded_team_ids)
+}
+
+// Check that round dates are valid and in chronological order
+fn validate_round_dates(round_dates: &[String]) -> Result<(), String> {
+    let mut previous_date = NaiveDate::MIN;
+    for date in round_dates {
+        let date = parse_date(date)?;
+        if date < previous_date {
+            return Err("Round dates must be in chronological order".to_string());
+        }
+        previous_date = date;
+    }
+    Ok(())
+}
+
+/*
+Spread a number of rounds evenly over the match days between two dates
+An empty list of match days allows every day. Days inside any of the blackout periods are skipped.
+*/
+fn spread_round_dates(
+    start_date: &str,
+    end_date: &str,
+    match_days: &[Weekday],
+    blackouts: &[(NaiveDate, NaiveDate)],
+    rounds: usize,
+) -> Result<Vec<String>, String> {
+    let start = parse_date(start_date)?;
+    let end = parse_date(end_date)?;
+
+    if end < start {
+        return Err("End date must not be before the start date".to_string());
+    }
+
+    let match_dates: Vec<NaiveDate> = start
+        .iter_days()
+        .take_while(|date| *date <= end)
+        .filter(|date| match_days.is_empty() || match_days.contains(&to_weekday(date.weekday())))
+        .filter(|date| {
+            !blackouts
+                .iter()
+                .any(|(start, end)| start <= date && date <= end)
+        })
+        .collect();
+
+    if match_dates.len() < rounds {
+        return Err(format!(
+            "The schedule needs {} match days but the date range only has {}",
+            rounds,
+            match_dates.len()
+        ));
+    }
+
+    Ok((0..rounds)
+        .map(|index| {
+            match_dates[index * match_dates.len() / rounds]
+                .format("%Y-%m-%d")
+                .to_string()
+        })
+        .collect())
+}
+
+// Check the extra time of a result fits inside its score and any shoot-out produced a winner
+fn validate_extra_time(result: &MatchResult) -> Result<(), String> {
+    let extra_time = match &result.extra_time {
+        Some(extra_time) => extra_time,
+        None => return Ok(()),
+    };
+
+    if extra_time.score_team_a > result.score_team_a
+        || extra_time.score_team_b > result.score_team_b
+    {
+        return Err(
+            "Goals scored in extra time must also be counted in the match score".to_string(),
+        );
+    }
+
+    if let Some(penalties) = &extra_time.penalties {
+        if penalties.score_team_a == penalties.score_team_b {
+            return Err("A penalty shoot-out must produce a winner".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+// Check the second-leg dates of a bracket: one per two-legged round, each after its first leg
+fn validate_second_leg_dates(round_dates: &[String], rules: &TwoLeggedRules) -> Result<(), String> {
+    let rounds = round_dates.len();
+    let two_legged_rounds = if rules.single_leg_final {
+        rounds - 1
+    } else {
+        rounds
+    };
+
+    if rules.second_leg_dates.len() != two_legged_rounds {
+        return Err(format!(
+            "{} rounds are played over two legs, so {} second-leg dates are needed",
+            two_legged_rounds, two_legged_rounds
+        ));
+    }
+
+    let mut leg_dates = Vec::new();
+    for (index, first_leg_date) in round_dates.iter().enumerate() {
+        leg_dates.push(first_leg_date.clone());
+        if let Some(second_leg_date) = rules.second_leg_dates.get(index) {
+            if parse_date(second_leg_date)? <= parse_date(first_leg_date)? {
+                return Err(format!(
+                    "The second leg of round {} must be played after the first leg",
+                    index + 1
+                ));
+            }
+            leg_dates.push(second_leg_date.clone());
+        }
+    }
+
+    validate_round_dates(&leg_dates)
+}
+
+// Find the two-legged tie a match is a leg of
+fn get_tie_for_match(match_obj: &Match) -> Option<TwoLeggedTie> {
+    match_obj.tournament_id?;
+    TIES_STORAGE.with(|storage| {
+        storage.borrow().iter().map(|(_, tie)| tie).find(|tie| {
+            tie.first_leg_match_id == match_obj.id || tie.second_leg_match_id == match_obj.id
+        })
+    })
+}
+
+// Team A and team B goals in a leg; team B is at home in the second leg
+fn leg_goals(
+    tie: &TwoLeggedTie,
+    match_obj: &Match,
+    score_team_a: u32,
+    score_team_b: u32,
+) -> (u32, u32) {
+    if match_obj.home_team.id == tie.team_a_id {
+        (score_team_a, score_team_b)
+    } else {
+        (score_team_b, score_team_a)
+    }
+}
+
+// The team ahead on aggregate, then on away goals when the rule applies
+fn leading_team(
+    tie: &TwoLeggedTie,
+    aggregate: (u32, u32),
+    away_goals: (u32, u32),
+) -> Option<(u64, TieDecision)> {
+    match aggregate.0.cmp(&aggregate.1) {
+        std::cmp::Ordering::Greater => return Some((tie.team_a_id, TieDecision::Aggregate)),
+        std::cmp::Ordering::Less => return Some((tie.team_b_id, TieDecision::Aggregate)),
+        std::cmp::Ordering::Equal => {}
+    }
+
+    if !tie.away_goals {
+        return None;
+    }
+
+    match away_goals.0.cmp(&away_goals.1) {
+        std::cmp::Ordering::Greater => Some((tie.team_a_id, TieDecision::AwayGoals)),
+        std::cmp::Ordering::Less => Some((tie.team_b_id, TieDecision::AwayGoals)),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+/*
+Decide a two-legged tie from the results of its legs
+The aggregate score decides the tie, then away goals when the rule applies.
+A tie still level after normal time of the second leg goes to extra time, where away goals count again,
+and then to penalties.
+*/
+fn resolve_tie(
+    tie: &TwoLeggedTie,
+    first_leg: &MatchResult,
+    second_leg_match: &Match,
+    second_leg: &MatchResult,
+) -> Result<(u64, TieDecision), String> {
+    // Team A is at home in the first leg, so team B is at home in the second
+    let (first_a, first_b) = (first_leg.score_team_a, first_leg.score_team_b);
+    let (second_a, second_b) = leg_goals(
+        tie,
+        second_leg_match,
+        second_leg.score_team_a,
+        second_leg.score_team_b,
+    );
+    let (extra_a, extra_b) = match &second_leg.extra_time {
+        Some(extra_time) => leg_goals(
+            tie,
+            second_leg_match,
+            extra_time.score_team_a,
+            extra_time.score_team_b,
+        ),
+        None => (0, 0),
+    };
+    let (normal_a, normal_b) = (
+        second_a.saturating_sub(extra_a),
+        second_b.saturating_sub(extra_b),
+    );
+
+    let normal_time = leading_team(
+        tie,
+        (first_a + normal_a, first_b + normal_b),
+        (normal_a, first_b),
+    );
+
+    if let Some(decided) = normal_time {
+        if second_leg.extra_time.is_some() {
+            return Err(
+                "The tie was decided in normal time, so no extra time is played".to_string(),
+            );
+        }
+        return Ok(decided);
+    }
+
+    let extra_time = second_leg.extra_time.as_ref().ok_or_else(|| {
+        "The tie is level after normal time, so the second leg needs extra time".to_string()
+    })?;
+
+    let after_extra_time = leading_team(
+        tie,
+        (first_a + second_a, first_b + second_b),
+        (second_a, first_b),
+    );
+
+    match (after_extra_time, &extra_time.penalties) {
+        (Some((winner_team_id, _)), None) => Ok((winner_team_id, TieDecision::ExtraTime)),
+        (Some(_), Some(_)) => {
+            Err("The tie was decided in extra time, so there are no penalties".to_string())
+        }
+        (None, None) => {
+            Err("The tie is level after extra time, so penalties are needed".to_string())
+        }
+        (None, Some(penalties)) => {
+            let (penalties_a, penalties_b) = leg_goals(
+                tie,
+                second_leg_match,
+                penalties.score_team_a,
+                penalties.score_team_b,
+            );
+            let winner_team_id = if penalties_a > penalties_b {
+                tie.team_a_id
+            } else {
+                tie.team_b_id
+            };
+            Ok((winner_team_id, TieDecision::Penalties))
+        }
+    }
+}
+
+// The first leg is played before the second and never goes to extra time; the second leg must decide the tie
+fn validate_tie_leg(
+    tie: &TwoLeggedTie,
+    match_obj: &Match,
+    result: &MatchResult,
+) -> Result<(), String> {
+    if match_obj.id == tie.first_leg_match_id {
+        if result.extra_time.is_some() {
+            return Err("Extra time is only played in the second leg".to_string());
+        }
+
+        // A corrected first leg must still leave the tie decided by its second leg
+        let second_leg_match = get_match(tie.second_leg_match_id)?;
+        if let Some(second_leg) = &second_leg_match.result {
+            resolve_tie(tie, result, &second_leg_match, second_leg)?;
+        }
+        return Ok(());
+    }
+
+    let first_leg = get_match(tie.first_leg_match_id)?
+        .result
+        .ok_or_else(|| "The first leg must be played before the second leg".to_string())?;
+
+    resolve_tie(tie, &first_leg, match_obj, result)?;
+
+    Ok(())
+}
+
+// Recompute a tie's aggregate from its played legs, returning the team that goes through once the second leg is played
+fn complete_tie_leg(mut tie: TwoLeggedTie) -> Result<Option<u64>, String> {
+    let first_leg = get_match(tie.first_leg_match_id)?;
+    let second_leg = get_match(tie.second_leg_match_id)?;
+
+    tie.aggregate_team_a = 0;
+    tie.aggregate_team_b = 0;
+    for leg in [&first_leg, &second_leg] {
+        if let Some(result) = &leg.result {
+            let (goals_a, goals_b) = leg_goals(&tie, leg, result.score_team_a, result.score_team_b);
+            tie.aggregate_team_a += goals_a;
+            tie.aggregate_team_b += goals_b;
+        }
+    }
+
+    (tie.winner_team_id, tie.decided_by) = match (&first_leg.result, &second_leg.result) {
+        (Some(first_leg_result), Some(second_leg_result)) => {
+            let (winner_team_id, decided_by) =
+                resolve_tie(&tie, first_leg_result, &second_leg, second_leg_result)?;
+            (Some(winner_team_id), Some(decided_by))
+        }
+        (None, Some(_)) => {
+            return Err("The first leg must be played before the second leg".to_string())
+        }
+        _ => (None, None),
+    };
+
+    TIES_STORAGE.with(|storage| storage.borrow_mut().insert(tie.id, tie.clone()));
+
+    Ok(tie.winner_team_id)
+}
+
+// Fetch a two-legged tie
+#[ic_cdk::query]
+fn get_two_legged_tie(tie_id: u64) -> Result<TwoLeggedTie, String> {
+    TIES_STORAGE.with(|storage| match storage.borrow().get(&tie_id) {
+        Some(tie) => Ok(tie.clone()),
+        None => Err(format!("Tie with ID {} not found", tie_id)),
+    })
+}
+
+// Get the two-legged ties of a tournament
+#[ic_cdk::query]
+fn get_tournament_ties(tournament_id: u64) -> Result<Vec<TwoLeggedTie>, String> {
+    let ties: Vec<TwoLeggedTie> = TIES_STORAGE.with(|storage| {
+        storage
+            .borrow()
+            .iter()
+            .map(|(_, tie)| tie)
+            .filter(|tie| tie.tournament_id == tournament_id)
+            .collect()
+    });
+
+    if ties.is_empty() {
+        Err("No two-legged ties found for this tournament".to_string())
+    } else {
+        Ok(ties)
+    }
+}
+
+/*
+Build, schedule and store the knockout bracket of a tournament
+The field is padded to a power of two with byes, which go to the top seeds,
+and seeds are placed so that the top two can only meet in the final, the top four in the semi-finals and so on.
+Match rounds are numbered after any earlier stage through the round offset.
+*/
+fn build_bracket(
+    tournament: &Tournament,
+    seeded_team_ids: Vec<u64>,
+    round_dates: Vec<String>,
+    kickoff_time: Option<String>,
+    third_place_match: bool,
+    round_offset: u32,
+    two_legged: Option<TwoLeggedRules>,
+) -> Result<Bracket, String> {
+    let size = (seeded_team_ids.len() as u32).next_power_of_two();
+    let rounds = size.trailing_zeros();
+
+    if round_dates.len() != rounds as usize {
+        return Err(format!(
+            "The bracket has {} rounds, so {} round dates are needed",
+            rounds, rounds
+        ));
+    }
+
+    validate_round_dates(&round_dates)?;
+    slot_range(&round_dates[0], &kickoff_time)?;
+
+    if third_place_match && seeded_team_ids.len() < 4 {
+        return Err("A third-place match needs at least 4 teams".to_string());
+    }
+
+    if let Some(rules) = &two_legged {
+        validate_second_leg_dates(&round_dates, rules)?;
+    }
+
+    // Node n plays in the round counted back from the final, at depth log2(n)
+    let mut nodes: Vec<BracketNode> = (1..size)
+        .map(|id| BracketNode {
+            id,
+            round: rounds - (31 - id.leading_zeros()),
+            home_team_id: None,
+            away_team_id: None,
+            home_seed: None,
+            away_seed: None,
+            match_id: None,
+            winner_team_id: None,
+            next_node_id: if id == 1 { None } else { Some(id / 2) },
+            tie_id: None,
+        })
+        .collect();
+
+    // Place the seeds into the first round, leaving byes where a seed has no team
+    let seed_order = bracket_seed_order(size);
+    let seeded = |seed: u32| {
+        seeded_team_ids
+            .get(seed as usize - 1)
+            .map(|team_id| (*team_id, seed))
+    };
+
+    for (index, pair) in seed_order.chunks(2).enumerate() {
+        let node = &mut nodes[size as usize / 2 - 1 + index];
+        if let Some((team_id, seed)) = seeded(pair[0]) {
+            node.home_team_id = Some(team_id);
+            node.home_seed = Some(seed);
+        }
+        if let Some((team_id, seed)) = seeded(pair[1]) {
+            node.away_team_id = Some(team_id);
+            node.away_seed = Some(seed);
+        }
+    }
+
+    let mut bracket = Bracket {
+        tournament_id: tournament.id,
+        rounds,
+        seeded_team_ids,
+        round_dates,
+        kickoff_time,
+        nodes,
+        third_place: None,
+        round_offset,
+        two_legged,
+    };
+
+    if third_place_match {
+        bracket.third_place = Some(BracketNode {
+            id: 0,
+            round: rounds,
+            home_team_id: None,
+            away_team_id: None,
+            home_seed: None,
+            away_seed: None,
+            match_id: None,
+            winner_team_id: None,
+            next_node_id: None,
+            tie_id: None,
+        });
+    }
+
+    // Teams drawn against a bye go straight through to the second round
+    for id in size / 2..size {
+        let node = bracket.nodes[id as usize - 1].clone();
+        match (node.home_team_id, node.away_team_id) {
+            (Some(team_id), None) | (None, Some(team_id)) => {
+                advance_in_bracket(&mut bracket, id, team_id)
+            }
+            _ => {}
+        }
+    }
+
+    schedule_bracket_matches(tournament, &mut bracket)?;
+
+    BRACKETS_STORAGE.with(|storage| storage.borrow_mut().insert(tournament.id, bracket.clone()));
+
+    Ok(bracket)
+}
+
+/**
+ * Function to build the bracket of a knockout tournament
+ * Byes go to the top seeds and the top seeds are kept apart until the late rounds.
+ * One date is needed per round; first-round matches are created straight away.
+ */
+#[ic_cdk::update]
+fn generate_knockout_bracket(payload: GenerateKnockoutPayload) -> Result<Bracket, String> {
+    let tournament = get_tournament(payload.tournament_id)?;
+    ensure_tournament_official(&tournament)?;
+
+    if tournament.structure != TournamentStructure::Knockout {
+        return Err("Brackets can only be generated for knockout tournaments".to_string());
+    }
+
+    ensure_ready_for_draw(&tournament)?;
+
+    if BRACKETS_STORAGE.with(|storage| storage.borrow().contains_key(&tournament.id)) {
+        return Err("A bracket has already been generated for this tournament".to_string());
+    }
+
+    let seeded_team_ids = validate_seeding(&tournament, payload.seeded_team_ids)?;
+
+    let bracket = build_bracket(
+        &tournament,
+        seeded_team_ids,
+        payload.round_dates,
+        payload.kickoff_time,
+        payload.third_place_match,
+        0,
+        payload.two_legged,
+    )?;
+
+    start_tournament(tournament);
+
+    Ok(bracket)
+}
+
+// Fetch the knockout bracket of a tournament
+#[ic_cdk::query]
+fn get_bracket(tournament_id: u64) -> Result<Bracket, String> {
+    BRACKETS_STORAGE.with(|storage| match storage.borrow().get(&tournament_id) {
+        Some(bracket) => Ok(bracket.clone()),
+        None => Err(format!(
+            "No bracket found for tournament with ID {}",
+            tournament_id
+        )),
+    })
+}
+
+// Name a knockout round by how far it is from the final
+fn bracket_round_name(round: u32, rounds: u32) -> String {
+    match rounds - round {
+        0 => "Final".to_string(),
+        1 => "Semi-finals".to_string(),
+        2 => "Quarter-finals".to_string(),
+        from_final => format!("Round of {}", 2u32 << from_final),
+    }
+}
+
+// Describe one side of a bracket node, with the score of its match or the aggregate of its tie
+fn bracket_slot_team(
+    team_id: Option<u64>,
+    seed: Option<u32>,
+    score: Option<u32>,
+    winner_team_id: Option<u64>,
+) -> Option<BracketSlotTeam> {
+    let team_id = team_id?;
+    Some(BracketSlotTeam {
+        team_id,
+        name: get_team(team_id).map(|team| team.name).unwrap_or_default(),
+        seed,
+        score,
+        winner: winner_team_id == Some(team_id),
+    })
+}
+
+// Describe a bracket node as a slot of an exported bracket
+fn bracket_slot(node: &BracketNode) -> BracketSlot {
+    let match_obj = node.match_id.and_then(|match_id| get_match(match_id).ok());
+    let tie = node
+        .tie_id
+        .and_then(|tie_id| TIES_STORAGE.with(|storage| storage.borrow().get(&tie_id)));
+
+    let (home_score, away_score) = match (&tie, &match_obj) {
+        (Some(tie), Some(match_obj)) if match_obj.result.is_some() => {
+            (Some(tie.aggregate_team_a), Some(tie.aggregate_team_b))
+        }
+        (None, Some(match_obj)) => match &match_obj.result {
+            Some(result) => (Some(result.score_team_a), Some(result.score_team_b)),
+            None => (None, None),
+        },
+        _ => (None, None),
+    };
+
+    BracketSlot {
+        node_id: node.id,
+        match_id: node.match_id,
+        tie_id: node.tie_id,
+        scheduled_date: match_obj.map(|match_obj| match_obj.scheduled_date),
+        home: bracket_slot_team(
+            node.home_team_id,
+            node.home_seed,
+            home_score,
+            node.winner_team_id,
+        ),
+        away: bracket_slot_team(
+            node.away_team_id,
+            node.away_seed,
+            away_score,
+            node.winner_team_id,
+        ),
+        decided_by: tie.and_then(|tie| tie.decided_by),
+        next_node_id: node.next_node_id,
+    }
+}
+
+// Build the exported tree of a tournament's knockout bracket, with the first round first
+fn build_bracket_tree(tournament_id: u64) -> Result<BracketTree, String> {
+    let tournament = get_tournament(tournament_id)?;
+    let bracket = get_bracket(tournament_id)?;
+
+    let rounds = (1..=bracket.rounds)
+        .map(|round| BracketRound {
+            round: bracket.round_offset + round,
+            name: bracket_round_name(round, bracket.rounds),
+            slots: bracket
+                .nodes
+                .iter()
+                .filter(|node| node.round == round)
+                .map(bracket_slot)
+                .collect(),
+        })
+        .collect();
+
+    Ok(BracketTree {
+        tournament_id: tournament.id,
+        tournament_name: tournament.name,
+        rounds,
+        third_place: bracket.third_place.as_ref().map(bracket_slot),
+        champion_team_id: tournament.champion_team_id,
+    })
+}
+
+// Fetch a tournament's knockout bracket as a JSON tree of rounds, slots, teams and scores
+#[ic_cdk::query]
+fn get_bracket_json(tournament_id: u64) -> Result<String, String> {
+    let tree = build_bracket_tree(tournament_id)?;
+    serde_json::to_string(&tree).map_err(|err| format!("Could not export the bracket: {}", err))
+}
+
+// Escape text for use inside an SVG document
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// Draw one slot of a bracket as two rows, one per team, with its top-left corner at the given point
+fn svg_bracket_slot(svg: &mut String, slot: &BracketSlot, x: u32, y: u32, first_round: bool) {
+    const WIDTH: u32 = BRACKET_SVG_SLOT_WIDTH;
+    const ROW: u32 = BRACKET_SVG_SLOT_HEIGHT / 2;
+
+    for (row, team) in [&slot.home, &slot.away].into_iter().enumerate() {
+        let top = y + row as u32 * ROW;
+        let (label, score, bold) = match team {
+            Some(team) => {
+                let seed = team
+                    .seed
+                    .map(|seed| format!("{} ", seed))
+                    .unwrap_or_default();
+                let score = team
+                    .score
+                    .map(|score| score.to_string())
+                    .unwrap_or_default();
+                (format!("{}{}", seed, team.name), score, team.winner)
+            }
+            // A missing team in the first round is a bye, and later on is still to be decided
+            None if first_round => ("Bye".to_string(), String::new(), false),
+            None => ("TBD".to_string(), String::new(), false),
+        };
+
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#888\"/>",
+            x,
+            top,
+            WIDTH,
+            ROW,
+            if bold { "#e8f4e8" } else { "#fff" }
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" font-weight=\"{}\">{}</text>",
+            x + 6,
+            top + 17,
+            if bold { "bold" } else { "normal" },
+            escape_xml(&label)
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>",
+            x + WIDTH - 6,
+            top + 17,
+            score
+        ));
+    }
+}
+
+/*
+Draw a tournament's knockout bracket as a self-contained SVG document
+Rounds are drawn as columns from the first round to the final. First-round slots are spaced evenly
+and every later slot is centred between the two slots that feed it, joined to them by connector lines.
+The third-place match is drawn below the final.
+*/
+fn render_bracket_svg(tree: &BracketTree) -> String {
+    const WIDTH: u32 = BRACKET_SVG_SLOT_WIDTH;
+    const HEIGHT: u32 = BRACKET_SVG_SLOT_HEIGHT;
+    const GAP: u32 = 40;
+    const SPACING: u32 = HEIGHT + 20;
+    const MARGIN: u32 = 20;
+    const HEADER: u32 = 30;
+
+    let rounds = tree.rounds.len() as u32;
+    let first_round_slots = 1u32 << (rounds - 1);
+
+    // Node n sits at depth log2(n) below the final, in position n - 2^depth of its round
+    let slot_position = |node_id: u32| -> (u32, u32) {
+        let depth = 31 - node_id.leading_zeros();
+        let column = rounds - 1 - depth;
+        let position = node_id - (1 << depth);
+        let band = SPACING << column;
+        let x = MARGIN + column * (WIDTH + GAP);
+        let y = MARGIN + HEADER + position * band + band / 2 - HEIGHT / 2;
+        (x, y)
+    };
+
+    let tree_height = first_round_slots * SPACING;
+    let third_place_height = if tree.third_place.is_some() {
+        HEADER + SPACING
+    } else {
+        0
+    };
+    let width = 2 * MARGIN + rounds * (WIDTH + GAP) - GAP;
+    let height = 2 * MARGIN + HEADER + tree_height + third_place_height;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" font-family=\"sans-serif\" font-size=\"12\">",
+        width, height, width, height
+    );
+    svg.push_str(&format!(
+        "<title>{}</title><rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>",
+        escape_xml(&tree.tournament_name)
+    ));
+
+    for (column, round) in tree.rounds.iter().enumerate() {
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" font-weight=\"bold\">{}</text>",
+            MARGIN + column as u32 * (WIDTH + GAP),
+            MARGIN + 12,
+            escape_xml(&round.name)
+        ));
+
+        for slot in &round.slots {
+            let (x, y) = slot_position(slot.node_id);
+            svg_bracket_slot(&mut svg, slot, x, y, column == 0);
+
+            if let Some(next_node_id) = slot.next_node_id {
+                let (next_x, next_y) = slot_position(next_node_id);
+                let middle = x + WIDTH + GAP / 2;
+                svg.push_str(&format!(
+                    "<path d=\"M{} {} H{} V{} H{}\" fill=\"none\" stroke=\"#888\"/>",
+                    x + WIDTH,
+                    y + HEIGHT / 2,
+                    middle,
+                    next_y + HEIGHT / 2,
+                    next_x
+                ));
+            }
+        }
+    }
+
+    if let Some(third_place) = &tree.third_place {
+        let x = MARGIN + (rounds - 1) * (WIDTH + GAP);
+        let y = MARGIN + HEADER + tree_height + HEADER;
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" font-weight=\"bold\">Third place</text>",
+            x,
+            y - 8
+        ));
+        svg_bracket_slot(&mut svg, third_place, x, y, false);
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+// Fetch a tournament's knockout bracket drawn as a self-contained SVG document
+#[ic_cdk::query]
+fn get_bracket_svg(tournament_id: u64) -> Result<String, String> {
+    let tree = build_bracket_tree(tournament_id)?;
+    Ok(render_bracket_svg(&tree))
+}
+
+// Move a tournament on after one of its matches is completed, following the rules of its format
+fn advance_tournament(match_obj: &Match) -> Result<(), String> {
+    let tournament = match match_obj.tournament_id {
+        Some(tournament_id) => get_tournament(tournament_id)?,
+        None => return Ok(()),
+    };
+
+    match tournament.structure {
+        TournamentStructure::RoundRobin => {
+            complete_round_robin(tournament);
+            Ok(())
+        }
+        TournamentStructure::Knockout => advance_bracket(match_obj),
+        TournamentStructure::GroupsThenKnockout => {
+            advance_bracket(match_obj)?;
+            advance_group_stage(&tournament)
+        }
+        TournamentStructure::DoubleElimination => advance_double_elimination(match_obj),
+        TournamentStructure::Swiss => advance_swiss(&tournament),
+    }
+}
+
+// Name a group by its position: Group A, Group B and so on
+fn group_name(index: usize) -> String {
+    format!("Group {}", (b'A' + index as u8) as char)
+}
+
+// A group stage has between 1 and 26 groups of at least 2 teams
+fn validate_group_count(team_count: usize, group_count: usize) -> Result<(), String> {
+    if group_count == 0 || group_count > 26 {
+        return Err("A group stage needs between 1 and 26 groups".to_string());
+    }
+
+    if team_count < group_count * 2 {
+        return Err("Every group needs at least 2 teams".to_string());
+    }
+
+    Ok(())
+}
+
+// Two teams clash when they share a pot, a university that is kept apart or a conference
+fn draw_clash(first: &DrawEntrant, second: &DrawEntrant, constraints: &DrawConstraints) -> bool {
+    if first.pot == second.pot {
+        return true;
+    }
+
+    let (first_university, second_university) = match (&first.university, &second.university) {
+        (Some(first_university), Some(second_university)) => (first_university, second_university),
+        _ => return false,
+    };
+
+    (constraints.separate_universities && first_university == second_university)
+        || constraints.conferences.iter().any(|conference| {
+            conference.universities.contains(first_university)
+                && conference.universities.contains(second_university)
+        })
+}
+
+// Place the drawn teams from the given position on, each into the first group that keeps the rest of the draw possible
+fn place_drawn_teams(
+    entrants: &[DrawEntrant],
+    draw_order: &[usize],
+    position: usize,
+    groups: &mut Vec<Vec<usize>>,
+    constraints: &DrawConstraints,
+    steps: &mut u32,
+) -> bool {
+    if position == draw_order.len() {
+        return true;
+    }
+
+    for group in 0..groups.len() {
+        *steps += 1;
+        if *steps > MAX_DRAW_STEPS {
+            return false;
+        }
+
+        let entrant = &entrants[draw_order[position]];
+        let clashes = groups[group]
+            .iter()
+            .any(|drawn| draw_clash(&entrants[*drawn], entrant, constraints));
+        if clashes {
+            continue;
+        }
+
+        groups[group].push(draw_order[position]);
+        if place_drawn_teams(
+            entrants,
+            draw_order,
+            position + 1,
+            groups,
+            constraints,
+            steps,
+        ) {
+            return true;
+        }
+        groups[group].pop();
+    }
+
+    false
+}
+
+// Step of the splitmix64 generator that turns a draw's seed into its random numbers
+fn next_draw_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut value = *state;
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+    value ^ (value >> 31)
+}
+
+/*
+Make a group draw from a seed, so that the same seed and entrants always give the same groups
+The seed is folded into a splitmix64 generator eight little-endian bytes at a time.
+Each pot is shuffled with Fisher-Yates, pot 1 first, and the teams are drawn pot by pot in that order.
+Every team goes into the first group, alphabetically, without a team it clashes with
+that still lets the remaining teams be placed.
+*/
+fn run_group_draw(
+    entrants: &[DrawEntrant],
+    group_count: usize,
+    constraints: &DrawConstraints,
+    seed: &[u8],
+) -> Result<(Vec<u64>, Vec<TournamentGroup>), String> {
+    let mut state = 0u64;
+    for chunk in seed.chunks(8) {
+        let mut word = [0u8; 8];
+        word[..chunk.len()].copy_from_slice(chunk);
+        state ^= u64::from_le_bytes(word);
+        next_draw_random(&mut state);
+    }
+
+    let pots = entrants
+        .iter()
+        .map(|entrant| entrant.pot)
+        .max()
+        .unwrap_or_default();
+
+    let mut draw_order = Vec::new();
+    for pot in 1..=pots {
+        let mut pot_order: Vec<usize> = (0..entrants.len())
+            .filter(|index| entrants[*index].pot == pot)
+            .collect();
+        for index in (1..pot_order.len()).rev() {
+            let swap = (next_draw_random(&mut state) % (index as u64 + 1)) as usize;
+            pot_order.swap(index, swap);
+        }
+        draw_order.extend(pot_order);
+    }
+
+    let mut groups = vec![Vec::new(); group_count];
+    let mut steps = 0;
+    if !place_drawn_teams(
+        entrants,
+        &draw_order,
+        0,
+        &mut groups,
+        constraints,
+        &mut steps,
+    ) {
+        return Err("No draw keeps every clashing team apart".to_string());
+    }
+
+    Ok((
+        draw_order
+            .iter()
+            .map(|index| entrants[*index].team_id)
+            .collect(),
+        groups
+            .iter()
+            .enumerate()
+            .map(|(index, group)| TournamentGroup {
+                name: group_name(index),
+                team_ids: group.iter().map(|drawn| entrants[*drawn].team_id).collect(),
+            })
+            .collect(),
+    ))
+}
+
+// Check a draw can be made and put the teams into pots by seeding
+fn prepare_group_draw(
+    payload: &DrawGroupsPayload,
+) -> Result<(Tournament, Vec<DrawEntrant>, DrawConstraints), String> {
+    let tournament = get_tournament(payload.tournament_id)?;
+
+    if tournament.structure != TournamentStructure::GroupsThenKnockout {
+        return Err("Groups can only be drawn for group-stage tournaments".to_string());
+    }
+
+    ensure_ready_for_draw(&tournament)?;
+
+    if GROUP_DRAWS_STORAGE.with(|storage| storage.borrow().contains_key(&tournament.id))
+        || GROUP_STAGES_STORAGE.with(|storage| storage.borrow().contains_key(&tournament.id))
+    {
+        return Err("The groups of this tournament have already been drawn".to_string());
+    }
+
+    let group_count = payload.group_count as usize;
+    validate_group_count(tournament.team_ids.len(), group_count)?;
+    let seeded_team_ids = validate_seeding(&tournament, payload.seeded_team_ids.clone())?;
+
+    let mut entrants = Vec::new();
+    for (index, team_id) in seeded_team_ids.iter().enumerate() {
+        entrants.push(DrawEntrant {
+            team_id: *team_id,
+            pot: (index / group_count) as u32 + 1,
+            university: get_team(*team_id)?.university,
+        });
+    }
+
+    let mut constraints = payload.constraints.clone();
+    for conference in constraints.conferences.iter_mut() {
+        conference.universities = conference
+            .universities
+            .drain(..)
+            .filter_map(|university| normalize_university(Some(university)))
+            .collect();
+    }
+
+    Ok((tournament, entrants, constraints))
+}
+
+// Make and record a group draw with the given randomness
+fn make_group_draw(payload: &DrawGroupsPayload, seed: &[u8]) -> Result<GroupDraw, String> {
+    let (tournament, entrants, constraints) = prepare_group_draw(payload)?;
+    let user = ensure_tournament_official(&tournament)?;
+
+    let (draw_order, groups) =
+        run_group_draw(&entrants, payload.group_count as usize, &constraints, seed)?;
+
+    let draw = GroupDraw {
+        tournament_id: tournament.id,
+        group_count: payload.group_count,
+        entrants,
+        constraints,
+        seed: seed.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        draw_order,
+        groups,
+        drawn_by: user.id,
+        drawn_at: ic_cdk::api::time(),
+    };
+
+    GROUP_DRAWS_STORAGE.with(|storage| storage.borrow_mut().insert(tournament.id, draw.clone()));
+    lock_registration(tournament.id);
+
+    Ok(draw)
+}
+
+/**
+ * Function to draw the groups of a group-stage tournament from pots
+ * Teams are placed into pots by seeding and drawn with randomness from the IC, keeping teams from the same pot,
+ * and optionally the same university or conference, in different groups.
+ * The seed is recorded with the draw so anyone can repeat it, and the entries are locked.
+ */
+#[ic_cdk::update]
+async fn draw_groups(payload: DrawGroupsPayload) -> Result<GroupDraw, String> {
+    ensure_tournament_official(&get_tournament(payload.tournament_id)?)?;
+    prepare_group_draw(&payload)?;
+
+    let (seed,) = ic_cdk::api::management_canister::main::raw_rand()
+        .await
+        .map_err(|(_, message)| format!("Could not get randomness for the draw: {}", message))?;
+
+    // The tournament may have changed while waiting, so the draw is checked again before it is made
+    make_group_draw(&payload, &seed)
+}
+
+// Fetch the group draw of a tournament
+#[ic_cdk::query]
+fn get_group_draw(tournament_id: u64) -> Result<GroupDraw, String> {
+    GROUP_DRAWS_STORAGE.with(|storage| match storage.borrow().get(&tournament_id) {
+        Some(draw) => Ok(draw.clone()),
+        None => Err(format!(
+            "No group draw found for tournament with ID {}",
+            tournament_id
+        )),
+    })
+}
+
+// Repeat a tournament's group draw from its recorded seed and entrants, and check it gives the recorded groups
+#[ic_cdk::query]
+fn verify_group_draw(tournament_id: u64) -> Result<bool, String> {
+    let draw = get_group_draw(tournament_id)?;
+
+    let seed = (0..draw.seed.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&draw.seed[index..index + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| "The recorded seed is not valid hex".to_string())?;
+
+    let (draw_order, groups) = run_group_draw(
+        &draw.entrants,
+        draw.group_count as usize,
+        &draw.constraints,
+        &seed,
+    )?;
+
+    let same_groups = groups.len() == draw.groups.len()
+        && groups
+            .iter()
+            .zip(draw.groups.iter())
+            .all(|(repeated, recorded)| repeated.team_ids == recorded.team_ids);
+
+    Ok(draw_order == draw.draw_order && same_groups)
+}
+
+/**
+ * Function to split a group-stage tournament into groups and schedule the group matches
+ * The groups of a draw are used when one has been made. Otherwise seeded teams are spread over the groups
+ * in a serpentine order so each group gets one team from each band of seeds.
+ * Every group plays a round robin; once all group matches are played the top teams of each group
+ * are seeded into a knockout bracket, group winners first, so teams from the same group meet as late as possible.
+ */
+#[ic_cdk::update]
+fn generate_group_stage(payload: GenerateGroupStagePayload) -> Result<GroupStage, String> {
+    let tournament = get_tournament(payload.tournament_id)?;
+    ensure_tournament_official(&tournament)?;
+
+    if tournament.structure != TournamentStructure::GroupsThenKnockout {
+        return Err("Groups can only be generated for group-stage tournaments".to_string());
+    }
+
+    ensure_ready_for_draw(&tournament)?;
+
+    if GROUP_STAGES_STORAGE.with(|storage| storage.borrow().contains_key(&tournament.id)) {
+        return Err("Groups have already been generated for this tournament".to_string());
+    }
+
+    let draw = GROUP_DRAWS_STORAGE.with(|storage| storage.borrow().get(&tournament.id));
+    let group_count = payload.group_count as usize;
+    validate_group_count(tournament.team_ids.len(), group_count)?;
+
+    let groups = match draw {
+        Some(draw) => {
+            let mut drawn_team_ids: Vec<u64> = draw
+                .entrants
+                .iter()
+                .map(|entrant| entrant.team_id)
+                .collect();
+            let mut team_ids = tournament.team_ids.clone();
+            drawn_team_ids.sort_unstable();
+            team_ids.sort_unstable();
+
+            if drawn_team_ids != team_ids {
+                return Err("The tournament's teams have changed since the draw".to_string());
+            }
+            if !payload.seeded_team_ids.is_empty() {
+                return Err("The groups come from the draw, so no seeding is given".to_string());
+            }
+            if draw.group_count as usize != group_count {
+                return Err(format!(
+                    "The draw was made into {} groups",
+                    draw.group_count
+                ));
+            }
+            draw.groups
+        }
+        None => {
+            let seeded_team_ids = validate_seeding(&tournament, payload.seeded_team_ids)?;
+
+            // Deal the seeds into the groups, reversing direction on every pass
+            let mut groups: Vec<TournamentGroup> = (0..group_count)
+                .map(|index| TournamentGroup {
+                    name: group_name(index),
+                    team_ids: Vec::new(),
+                })
+                .collect();
+
+            for (index, team_id) in seeded_team_ids.iter().enumerate() {
+                let position = index % group_count;
+                let group = if (index / group_count) % 2 == 0 {
+                    position
+                } else {
+                    group_count - 1 - position
+                };
+                groups[group].team_ids.push(*team_id);
+            }
+            groups
+        }
+    };
+
+    let smallest_group = tournament.team_ids.len() / group_count;
+    let qualifiers = group_count * payload.qualifiers_per_group as usize;
+
+    if payload.qualifiers_per_group == 0 || payload.qualifiers_per_group as usize > smallest_group {
+        return Err(format!(
+            "Between 1 and {} teams per group can qualify",
+            smallest_group
+        ));
+    }
+
+    if qualifiers < 2 {
+        return Err("At least 2 teams must qualify for the knockout stage".to_string());
+    }
+
+    let knockout_rounds = (qualifiers as u32).next_power_of_two().trailing_zeros() as usize;
+    if payload.knockout_round_dates.len() != knockout_rounds {
+        return Err(format!(
+            "The knockout stage has {} rounds, so {} round dates are needed",
+            knockout_rounds, knockout_rounds
+        ));
+    }
+
+    validate_round_dates(&payload.knockout_round_dates)?;
+
+    if payload.third_place_match && qualifiers < 4 {
+        return Err("A third-place match needs at least 4 qualifiers".to_string());
+    }
+
+    slot_range(&payload.start_date, &payload.kickoff_time)?;
+
+    // Every group plays its own round robin, with round n of all groups on the same day
+    let group_pairings: Vec<RoundPairings> = groups
+        .iter()
+        .map(|group| round_robin_pairings(&group.team_ids, payload.double_round_robin))
+        .collect();
+
+    let rounds = group_pairings
+        .iter()
+        .map(|pairings| pairings.len())
+        .max()
+        .unwrap_or_default();
+
+    let round_dates = spread_round_dates(
+        &payload.start_date,
+        &payload.end_date,
+        &payload.match_days,
+        &tournament_blackouts(tournament.id)?,
+        rounds,
+    )?;
+
+    if parse_date(&payload.knockout_round_dates[0])? <= parse_date(&round_dates[rounds - 1])? {
+        return Err("The knockout stage must start after the last group round".to_string());
+    }
+
+    for pairings in &group_pairings {
+        for (index, round) in pairings.iter().enumerate() {
+            for (home, away) in round {
+                if let (Some(home), Some(away)) = (home, away) {
+                    create_tournament_match(
+                        &tournament,
+                        *home,
+                        *away,
+                        index as u32 + 1,
+                        &round_dates[index],
+                        &payload.kickoff_time,
+                        MatchStage::Group,
+                    )?;
+                }
+            }
+        }
+    }
+
+    let group_stage = GroupStage {
+        tournament_id: tournament.id,
+        groups,
+        rounds: rounds as u32,
+        qualifiers_per_group: payload.qualifiers_per_group,
+        knockout_round_dates: payload.knockout_round_dates,
+        kickoff_time: payload.kickoff_time,
+        third_place_match: payload.third_place_match,
+    };
+
+    GROUP_STAGES_STORAGE.with(|storage| {
+        storage
+            .borrow_mut()
+            .insert(tournament.id, group_stage.clone())
+    });
+
+    start_tournament(tournament);
+
+    Ok(group_stage)
+}
+
+// Fetch the groups of a group-stage tournament
+#[ic_cdk::query]
+fn get_group_stage(tournament_id: u64) -> Result<GroupStage, String> {
+    GROUP_STAGES_STORAGE.with(|storage| match storage.borrow().get(&tournament_id) {
+        Some(group_stage) => Ok(group_stage.clone()),
+        None => Err(format!(
+            "No group stage found for tournament with ID {}",
+            tournament_id
+        )),
+    })
+}
+
+// Get the standings table of every group of a group-stage tournament
+#[ic_cdk::query]
+fn get_group_tables(tournament_id: u64) -> Result<Vec<GroupTable>, String> {
+    let group_stage = get_group_stage(tournament_id)?;
+    let tournament = get_tournament(tournament_id)?;
+    Ok(compute_group_tables(
+        &group_stage,
+        &tournament_standings_rules(&tournament),
+    ))
+}
+
+// Complete a round-robin tournament once every match has a result, crowning the top of the standings
+fn complete_round_robin(mut tournament: Tournament) {
+    let matches = get_tournament_matches(tournament.id);
+    if matches.iter().any(|match_obj| match_obj.result.is_none()) {
+        return;
+    }
+
+    tournament.champion_team_id = compute_standings(
+        &tournament.team_ids,
+        &matches,
+        &tournament_standings_rules(&tournament),
+        tournament.id,
+    )
+    .first()
+    .map(|entry| entry.team_id);
+    tournament.status = TournamentStatus::Completed;
+
+    TOURNAMENTS_STORAGE.with(|storage| storage.borrow_mut().insert(tournament.id, tournament));
+}
+
+// Build the group tables from the group matches played so far
+fn compute_group_tables(group_stage: &GroupStage, rules: &StandingsRules) -> Vec<GroupTable> {
+    let group_matches: Vec<Match> = get_tournament_matches(group_stage.tournament_id)
+        .into_iter()
+        .filter(|match_obj| match_obj.round.unwrap_or_default() <= group_stage.rounds)
+        .collect();
+
+    group_stage
+        .groups
+        .iter()
+        .map(|group| GroupTable {
+            name: group.name.clone(),
+            standings: compute_standings(
+                &group.team_ids,
+                &group_matches,
+                rules,
+                group_stage.tournament_id,
+            ),
+        })
+        .collect()
+}
+
+// Seed the group qualifiers into the knockout bracket once every group match has been played
+fn advance_group_stage(tournament: &Tournament) -> Result<(), String> {
+    let group_stage =
+        match GROUP_STAGES_STORAGE.with(|storage| storage.borrow().get(&tournament.id)) {
+            Some(group_stage) => group_stage,
+            None => return Ok(()),
+        };
+
+    if BRACKETS_STORAGE.with(|storage| storage.borrow().contains_key(&tournament.id)) {
+        return Ok(());
+    }
+
+    let groups_finished = get_tournament_matches(tournament.id)
+        .iter()
+        .all(|match_obj| match_obj.result.is_some());
+
+    if !groups_finished {
+        return Ok(());
+    }
+
+    // Group winners are seeded first, then runners-up and so on
+    let tables = compute_group_tables(&group_stage, &tournament_standings_rules(tournament));
+    let mut qualifiers = Vec::new();
+    for position in 0..group_stage.qualifiers_per_group as usize {
+        for table in &tables {
+            qualifiers.push(table.standings[position].team_id);
+        }
+    }
+
+    build_bracket(
+        tournament,
+        qualifiers,
+        group_stage.knockout_round_dates,
+        group_stage.kickoff_time,
+        group_stage.third_place_match,
+        group_stage.rounds,
+        None,
+    )?;
+
+    Ok(())
+}
+
+// Append a node to a double-elimination bracket, played the round after the latest node it depends on
+fn add_elimination_node(
+    nodes: &mut Vec<EliminationNode>,
+    side: EliminationSide,
+    home_source: SlotSource,
+    away_source: SlotSource,
+) -> u32 {
+    let id = nodes.len() as u32 + 1;
+
+    let depends_on = |source: SlotSource| match source {
+        SlotSource::Seed(_) => 0,
+        SlotSource::WinnerOf(node_id) | SlotSource::LoserOf(node_id) => {
+            nodes[node_id as usize - 1].round
+        }
     };
+    let round = depends_on(home_source).max(depends_on(away_source)) + 1;
 
-    USERS_STORAGE.with(
-        |storage| match storage.borrow_mut().insert(payload.id, user.clone()) {
-            Some(_) => Ok(user),
-            None => Err("User not found".to_string()),
-        },
+    nodes.push(EliminationNode {
+        id,
+        side,
+        round,
+        home_source,
+        away_source,
+        home_team_id: None,
+        away_team_id: None,
+        match_id: None,
+        winner_team_id: None,
+        loser_team_id: None,
+        decided: false,
+    });
+    id
+}
+
+/*
+Lay out a double-elimination bracket for the given number of first-round slots
+The winners bracket is a normal knockout. Losers of its first round meet in the losers bracket,
+which then alternates between rounds taking in the losers of the next winners round and rounds
+pairing its own survivors. The grand final pits the two bracket winners against each other,
+with an optional reset match when the losers-bracket winner takes the first one.
+*/
+fn layout_double_elimination(size: u32, bracket_reset: bool) -> Vec<EliminationNode> {
+    let mut nodes: Vec<EliminationNode> = Vec::new();
+
+    // Winners bracket
+    let seed_order = bracket_seed_order(size);
+    let mut winners_rounds: Vec<Vec<u32>> = vec![seed_order
+        .chunks(2)
+        .map(|pair| {
+            add_elimination_node(
+                &mut nodes,
+                EliminationSide::Winners,
+                SlotSource::Seed(pair[0]),
+                SlotSource::Seed(pair[1]),
+            )
+        })
+        .collect()];
+
+    while winners_rounds.last().unwrap().len() > 1 {
+        let previous = winners_rounds.last().unwrap().clone();
+        let round = previous
+            .chunks(2)
+            .map(|pair| {
+                add_elimination_node(
+                    &mut nodes,
+                    EliminationSide::Winners,
+                    SlotSource::WinnerOf(pair[0]),
+                    SlotSource::WinnerOf(pair[1]),
+                )
+            })
+            .collect();
+        winners_rounds.push(round);
+    }
+
+    let winners_final = winners_rounds.last().unwrap()[0];
+
+    // Losers bracket
+    let losers_champion = if winners_rounds.len() == 1 {
+        SlotSource::LoserOf(winners_final)
+    } else {
+        let mut previous: Vec<u32> = winners_rounds[0]
+            .chunks(2)
+            .map(|pair| {
+                add_elimination_node(
+                    &mut nodes,
+                    EliminationSide::Losers,
+                    SlotSource::LoserOf(pair[0]),
+                    SlotSource::LoserOf(pair[1]),
+                )
+            })
+            .collect();
+
+        for (index, winners_round) in winners_rounds.iter().enumerate().skip(1) {
+            // Drop-ins arrive in reverse order on alternate rounds to avoid early rematches
+            let mut dropping: Vec<u32> = winners_round.clone();
+            if index % 2 == 1 {
+                dropping.reverse();
+            }
+
+            previous = previous
+                .iter()
+                .zip(dropping)
+                .map(|(survivor, dropped)| {
+                    add_elimination_node(
+                        &mut nodes,
+                        EliminationSide::Losers,
+                        SlotSource::WinnerOf(*survivor),
+                        SlotSource::LoserOf(dropped),
+                    )
+                })
+                .collect();
+
+            if previous.len() > 1 {
+                previous = previous
+                    .chunks(2)
+                    .map(|pair| {
+                        add_elimination_node(
+                            &mut nodes,
+                            EliminationSide::Losers,
+                            SlotSource::WinnerOf(pair[0]),
+                            SlotSource::WinnerOf(pair[1]),
+                        )
+                    })
+                    .collect();
+            }
+        }
+
+        SlotSource::WinnerOf(previous[0])
+    };
+
+    // Grand final, and the reset played when the losers-bracket winner takes it
+    add_elimination_node(
+        &mut nodes,
+        EliminationSide::GrandFinal,
+        SlotSource::WinnerOf(winners_final),
+        losers_champion,
+    );
+
+    if bracket_reset {
+        add_elimination_node(
+            &mut nodes,
+            EliminationSide::GrandFinalReset,
+            SlotSource::WinnerOf(winners_final),
+            losers_champion,
+        );
+    }
+
+    nodes
+}
+
+/*
+Fill in the teams of every double-elimination node whose sources are decided
+Nodes with two teams get their match, nodes with one team are byes and nodes with none are skipped.
+The reset is not needed when the winners-bracket champion wins the grand final.
+*/
+fn resolve_double_elimination(
+    tournament: &Tournament,
+    bracket: &mut DoubleEliminationBracket,
+) -> Result<(), String> {
+    loop {
+        let mut changed = false;
+
+        for index in 0..bracket.nodes.len() {
+            let node = bracket.nodes[index].clone();
+            if node.decided || node.match_id.is_some() {
+                continue;
+            }
+
+            if node.side == EliminationSide::GrandFinalReset {
+                let grand_final = &bracket.nodes[index - 1];
+                if !grand_final.decided {
+                    continue;
+                }
+                if grand_final.winner_team_id == grand_final.home_team_id {
+                    bracket.nodes[index].decided = true;
+                    changed = true;
+                    continue;
+                }
+            }
+
+            let resolve = |source: SlotSource| match source {
+                SlotSource::Seed(seed) => {
+                    Some(bracket.seeded_team_ids.get(seed as usize - 1).copied())
+                }
+                SlotSource::WinnerOf(node_id) => {
+                    let source_node = &bracket.nodes[node_id as usize - 1];
+                    source_node.decided.then_some(source_node.winner_team_id)
+                }
+                SlotSource::LoserOf(node_id) => {
+                    let source_node = &bracket.nodes[node_id as usize - 1];
+                    source_node.decided.then_some(source_node.loser_team_id)
+                }
+            };
+
+            let (home, away) = match (resolve(node.home_source), resolve(node.away_source)) {
+                (Some(home), Some(away)) => (home, away),
+                _ => continue,
+            };
+
+            let node = &mut bracket.nodes[index];
+            node.home_team_id = home;
+            node.away_team_id = away;
+            changed = true;
+
+            match (home, away) {
+                (Some(home), Some(away)) => {
+                    let stage = match node.side {
+                        EliminationSide::Winners => MatchStage::WinnersBracket,
+                        EliminationSide::Losers => MatchStage::LosersBracket,
+                        EliminationSide::GrandFinal | EliminationSide::GrandFinalReset => {
+                            MatchStage::GrandFinal
+                        }
+                    };
+                    let match_obj = create_tournament_match(
+                        tournament,
+                        home,
+                        away,
+                        node.round,
+                        &bracket.round_dates[node.round as usize - 1],
+                        &bracket.kickoff_time,
+                        stage,
+                    )?;
+                    node.match_id = Some(match_obj.id);
+                }
+                (Some(team_id), None) | (None, Some(team_id)) => {
+                    node.winner_team_id = Some(team_id);
+                    node.decided = true;
+                }
+                (None, None) => node.decided = true,
+            }
+        }
+
+        if !changed {
+            return Ok(());
+        }
+    }
+}
+
+/**
+ * Function to build the bracket of a double-elimination tournament
+ * Teams are only knocked out after their second defeat. Rounds are spread over the match days
+ * between the start and end dates, and matches are created as soon as both of their teams are known.
+ */
+#[ic_cdk::update]
+fn generate_double_elimination(
+    payload: GenerateDoubleEliminationPayload,
+) -> Result<DoubleEliminationBracket, String> {
+    let tournament = get_tournament(payload.tournament_id)?;
+    ensure_tournament_official(&tournament)?;
+
+    if tournament.structure != TournamentStructure::DoubleElimination {
+        return Err(
+            "Double-elimination brackets can only be generated for double-elimination tournaments"
+                .to_string(),
+        );
+    }
+
+    ensure_ready_for_draw(&tournament)?;
+
+    if DOUBLE_ELIMINATION_STORAGE.with(|storage| storage.borrow().contains_key(&tournament.id)) {
+        return Err("A bracket has already been generated for this tournament".to_string());
+    }
+
+    let seeded_team_ids = validate_seeding(&tournament, payload.seeded_team_ids)?;
+    slot_range(&payload.start_date, &payload.kickoff_time)?;
+
+    let size = (seeded_team_ids.len() as u32).next_power_of_two();
+    let nodes = layout_double_elimination(size, payload.bracket_reset);
+    let rounds = nodes
+        .iter()
+        .map(|node| node.round)
+        .max()
+        .unwrap_or_default();
+
+    let round_dates = spread_round_dates(
+        &payload.start_date,
+        &payload.end_date,
+        &payload.match_days,
+        &tournament_blackouts(tournament.id)?,
+        rounds as usize,
+    )?;
+
+    let mut bracket = DoubleEliminationBracket {
+        tournament_id: tournament.id,
+        seeded_team_ids,
+        round_dates,
+        kickoff_time: payload.kickoff_time,
+        nodes,
+    };
+
+    resolve_double_elimination(&tournament, &mut bracket)?;
+
+    DOUBLE_ELIMINATION_STORAGE
+        .with(|storage| storage.borrow_mut().insert(tournament.id, bracket.clone()));
+
+    start_tournament(tournament);
+
+    Ok(bracket)
+}
+
+// Fetch the double-elimination bracket of a tournament
+#[ic_cdk::query]
+fn get_double_elimination_bracket(tournament_id: u64) -> Result<DoubleEliminationBracket, String> {
+    DOUBLE_ELIMINATION_STORAGE.with(|storage| match storage.borrow().get(&tournament_id) {
+        Some(bracket) => Ok(bracket.clone()),
+        None => Err(format!(
+            "No double-elimination bracket found for tournament with ID {}",
+            tournament_id
+        )),
+    })
+}
+
+// Find the double-elimination bracket a match belongs to
+fn get_double_elimination_for_match(match_obj: &Match) -> Option<DoubleEliminationBracket> {
+    let bracket = DOUBLE_ELIMINATION_STORAGE
+        .with(|storage| storage.borrow().get(&match_obj.tournament_id?))?;
+
+    if bracket
+        .nodes
+        .iter()
+        .any(|node| node.match_id == Some(match_obj.id))
+    {
+        Some(bracket)
+    } else {
+        None
+    }
+}
+
+// Record the result of a double-elimination match and schedule the matches it unlocks
+fn advance_double_elimination(match_obj: &Match) -> Result<(), String> {
+    let mut bracket = match get_double_elimination_for_match(match_obj) {
+        Some(bracket) => bracket,
+        None => return Ok(()),
+    };
+
+    let winner_team_id = match match_obj
+        .result
+        .as_ref()
+        .and_then(|result| result.winner_team_id)
+    {
+        Some(winner_team_id)
+            if winner_team_id == match_obj.home_team.id
+                || winner_team_id == match_obj.away_team.id =>
+        {
+            winner_team_id
+        }
+        _ => return Ok(()),
+    };
+
+    let mut tournament = get_tournament(bracket.tournament_id)?;
+
+    let loser_team_id = if winner_team_id == match_obj.home_team.id {
+        match_obj.away_team.id
+    } else {
+        match_obj.home_team.id
+    };
+
+    if let Some(index) = bracket
+        .nodes
+        .iter()
+        .position(|node| node.match_id == Some(match_obj.id))
+    {
+        let node = &mut bracket.nodes[index];
+        if node.decided && node.winner_team_id != Some(winner_team_id) {
+            // A corrected result swaps the winner and loser everywhere they went next
+            replace_elimination_result(&mut bracket, index, winner_team_id, loser_team_id)?;
+        } else {
+            node.winner_team_id = Some(winner_team_id);
+            node.loser_team_id = Some(loser_team_id);
+            node.decided = true;
+        }
+    }
+
+    resolve_double_elimination(&tournament, &mut bracket)?;
+
+    // The champion wins the reset if it was played, otherwise the grand final
+    let finals: Vec<&EliminationNode> = bracket
+        .nodes
+        .iter()
+        .filter(|node| {
+            node.side == EliminationSide::GrandFinal
+                || node.side == EliminationSide::GrandFinalReset
+        })
+        .collect();
+
+    if finals.iter().all(|node| node.decided) {
+        tournament.champion_team_id = finals.iter().rev().find_map(|node| node.winner_team_id);
+        tournament.status = TournamentStatus::Completed;
+        TOURNAMENTS_STORAGE.with(|storage| storage.borrow_mut().insert(tournament.id, tournament));
+    } else if tournament.status == TournamentStatus::Completed {
+        // A corrected grand final can call for the reset after all
+        tournament.champion_team_id = None;
+        tournament.status = TournamentStatus::InProgress;
+        TOURNAMENTS_STORAGE.with(|storage| storage.borrow_mut().insert(tournament.id, tournament));
+    }
+
+    DOUBLE_ELIMINATION_STORAGE
+        .with(|storage| storage.borrow_mut().insert(bracket.tournament_id, bracket));
+
+    Ok(())
+}
+
+/*
+Swap the winner and loser of a double-elimination node whose result was corrected
+Each takes the other's place in the nodes they went on to, following byes. Matches already scheduled for those
+places are updated, but the correction is refused once any of them has been played. A corrected grand final can
+call for the reset, but not cancel one already scheduled.
+*/
+fn replace_elimination_result(
+    bracket: &mut DoubleEliminationBracket,
+    index: usize,
+    winner_team_id: u64,
+    loser_team_id: u64,
+) -> Result<(), String> {
+    let node = &mut bracket.nodes[index];
+    node.winner_team_id = Some(winner_team_id);
+    node.loser_team_id = Some(loser_team_id);
+    let (node_id, side, home_team_id) = (node.id, node.side, node.home_team_id);
+
+    if side == EliminationSide::GrandFinal {
+        if let Some(reset) = bracket.nodes.get_mut(index + 1) {
+            if reset.match_id.is_some() {
+                return Err(
+                    "The result can no longer be corrected, as the grand final reset has been scheduled"
+                        .to_string(),
+                );
+            }
+            // The reset is only played when the away team, from the losers' bracket, wins the grand final
+            reset.decided = home_team_id == Some(winner_team_id);
+        }
+    }
+
+    replace_elimination_team(
+        bracket,
+        SlotSource::WinnerOf(node_id),
+        loser_team_id,
+        winner_team_id,
+    )?;
+    replace_elimination_team(
+        bracket,
+        SlotSource::LoserOf(node_id),
+        winner_team_id,
+        loser_team_id,
     )
 }
 
-// Fetch a user by ID
+// Put a team in another's place in the double-elimination nodes fed from a source, following any byes it was given
+fn replace_elimination_team(
+    bracket: &mut DoubleEliminationBracket,
+    source: SlotSource,
+    old_team_id: u64,
+    new_team_id: u64,
+) -> Result<(), String> {
+    for index in 0..bracket.nodes.len() {
+        let node = &mut bracket.nodes[index];
+        if node.home_source == source && node.home_team_id == Some(old_team_id) {
+            node.home_team_id = Some(new_team_id);
+        } else if node.away_source == source && node.away_team_id == Some(old_team_id) {
+            node.away_team_id = Some(new_team_id);
+        } else {
+            continue;
+        }
+
+        match node.match_id {
+            Some(match_id) => replace_match_team(match_id, old_team_id, new_team_id)?,
+            None if node.winner_team_id == Some(old_team_id) => {
+                node.winner_team_id = Some(new_team_id);
+                let node_id = node.id;
+                replace_elimination_team(
+                    bracket,
+                    SlotSource::WinnerOf(node_id),
+                    old_team_id,
+                    new_team_id,
+                )?;
+            }
+            None => {}
+        }
+    }
+
+    Ok(())
+}
+
+// Compute the Swiss standings, ranked by points, then Buchholz score, then seeding
+fn compute_swiss_standings(swiss: &SwissTournament, rules: &StandingsRules) -> Vec<SwissStanding> {
+    let matches = get_tournament_matches(swiss.tournament_id);
+
+    let mut standings: Vec<SwissStanding> = swiss
+        .seeded_team_ids
+        .iter()
+        .map(|team_id| SwissStanding {
+            team_id: *team_id,
+            ..Default::default()
+        })
+        .collect();
+
+    let position = |team_id: u64| {
+        swiss
+            .seeded_team_ids
+            .iter()
+            .position(|seeded| *seeded == team_id)
+    };
+
+    for bye in &swiss.byes {
+        if let Some(index) = position(bye.team_id) {
+            standings[index].byes += 1;
+            standings[index].points += rules.points_for_win;
+        }
+    }
+
+    for match_obj in &matches {
+        let result = match &match_obj.result {
+            Some(result) => result,
+            None => continue,
+        };
+
+        for (team_id, scored, conceded) in [
+            (
+                match_obj.home_team.id,
+                result.score_team_a,
+                result.score_team_b,
+            ),
+            (
+                match_obj.away_team.id,
+                result.score_team_b,
+                result.score_team_a,
+            ),
+        ] {
+            if let Some(index) = position(team_id) {
+                let entry = &mut standings[index];
+                entry.played += 1;
+                entry.points += result_points(
+                    rules,
+                    scored,
+                    conceded,
+                    result.forfeited_by == Some(team_id),
+                );
+                match scored.cmp(&conceded) {
+                    std::cmp::Ordering::Greater => entry.won += 1,
+                    std::cmp::Ordering::Equal => entry.drawn += 1,
+                    std::cmp::Ordering::Less => entry.lost += 1,
+                }
+            }
+        }
+    }
+
+    // Buchholz: the sum of the points of every opponent faced
+    let points: Vec<u32> = standings.iter().map(|entry| entry.points).collect();
+    for match_obj in matches
+        .iter()
+        .filter(|match_obj| match_obj.result.is_some())
+    {
+        if let (Some(home), Some(away)) = (
+            position(match_obj.home_team.id),
+            position(match_obj.away_team.id),
+        ) {
+            standings[home].buchholz += points[away];
+            standings[away].buchholz += points[home];
+        }
+    }
+
+    standings.sort_by(|a, b| {
+        b.points
+            .cmp(&a.points)
+            .then(b.buchholz.cmp(&a.buchholz))
+            .then(position(a.team_id).cmp(&position(b.team_id)))
+    });
+
+    standings
+}
+
+// Pair teams in ranking order without rematches, backtracking when a team is left without an opponent
+fn pair_without_rematches(
+    teams: &[u64],
+    played: &BTreeSet<(u64, u64)>,
+    steps: &mut u32,
+) -> Option<Vec<(u64, u64)>> {
+    if teams.is_empty() {
+        return Some(Vec::new());
+    }
+
+    for index in 1..teams.len() {
+        *steps += 1;
+        if *steps > MAX_SWISS_PAIRING_STEPS {
+            return None;
+        }
+
+        let (first, opponent) = (teams[0], teams[index]);
+        if played.contains(&(first.min(opponent), first.max(opponent))) {
+            continue;
+        }
+
+        let rest: Vec<u64> = teams[1..]
+            .iter()
+            .filter(|team_id| **team_id != opponent)
+            .copied()
+            .collect();
+
+        if let Some(mut pairs) = pair_without_rematches(&rest, played, steps) {
+            pairs.insert(0, (first, opponent));
+            return Some(pairs);
+        }
+    }
+
+    None
+}
+
+/*
+Pair the next round of a Swiss tournament
+Teams are paired down the standings, avoiding rematches where possible. With an odd number of teams
+the lowest-ranked team that has not had a bye sits out and is awarded a win.
+The team that has hosted fewer matches plays at home.
+*/
+fn pair_swiss_round(tournament: &Tournament, swiss: &mut SwissTournament) -> Result<(), String> {
+    let round = swiss.current_round + 1;
+    let matches = get_tournament_matches(tournament.id);
+
+    let mut ranked: Vec<u64> =
+        compute_swiss_standings(swiss, &tournament_standings_rules(tournament))
+            .iter()
+            .map(|entry| entry.team_id)
+            .collect();
+
+    if ranked.len() % 2 == 1 {
+        let bye_index = ranked
+            .iter()
+            .rposition(|team_id| !swiss.byes.iter().any(|bye| bye.team_id == *team_id))
+            .unwrap_or(ranked.len() - 1);
+        let team_id = ranked.remove(bye_index);
+        swiss.byes.push(SwissBye { round, team_id });
+    }
+
+    let played: BTreeSet<(u64, u64)> = matches
+        .iter()
+        .map(|match_obj| {
+            let (home, away) = (match_obj.home_team.id, match_obj.away_team.id);
+            (home.min(away), home.max(away))
+        })
+        .collect();
+
+    let pairs = pair_without_rematches(&ranked, &played, &mut 0)
+        .unwrap_or_else(|| ranked.chunks(2).map(|pair| (pair[0], pair[1])).collect());
+
+    let home_matches = |team_id: u64| {
+        matches
+            .iter()
+            .filter(|match_obj| match_obj.home_team.id == team_id)
+            .count()
+    };
+
+    for (higher, lower) in pairs {
+        let (home, away) = if home_matches(lower) < home_matches(higher) {
+            (lower, higher)
+        } else {
+            (higher, lower)
+        };
+
+        create_tournament_match(
+            tournament,
+            home,
+            away,
+            round,
+            &swiss.round_dates[round as usize - 1],
+            &swiss.kickoff_time,
+            MatchStage::Swiss,
+        )?;
+    }
+
+    swiss.current_round = round;
+
+    Ok(())
+}
+
+/**
+ * Function to start a Swiss-system tournament
+ * One round is played per round date. The first round is paired now, and each
+ * following round is paired on the standings once every match of the previous round has a result.
+ */
+#[ic_cdk::update]
+fn generate_swiss(payload: GenerateSwissPayload) -> Result<SwissTournament, String> {
+    let tournament = get_tournament(payload.tournament_id)?;
+    ensure_tournament_official(&tournament)?;
+
+    if tournament.structure != TournamentStructure::Swiss {
+        return Err("Swiss rounds can only be generated for Swiss tournaments".to_string());
+    }
+
+    ensure_ready_for_draw(&tournament)?;
+
+    if SWISS_STORAGE.with(|storage| storage.borrow().contains_key(&tournament.id)) {
+        return Err("Swiss rounds have already been generated for this tournament".to_string());
+    }
+
+    let seeded_team_ids = validate_seeding(&tournament, payload.seeded_team_ids)?;
+
+    if payload.round_dates.is_empty() || payload.round_dates.len() >= seeded_team_ids.len() {
+        return Err(format!(
+            "A Swiss tournament of {} teams needs between 1 and {} rounds",
+            seeded_team_ids.len(),
+            seeded_team_ids.len() - 1
+        ));
+    }
+
+    validate_round_dates(&payload.round_dates)?;
+    slot_range(&payload.round_dates[0], &payload.kickoff_time)?;
+
+    let mut swiss = SwissTournament {
+        tournament_id: tournament.id,
+        seeded_team_ids,
+        total_rounds: payload.round_dates.len() as u32,
+        current_round: 0,
+        round_dates: payload.round_dates,
+        kickoff_time: payload.kickoff_time,
+        byes: Vec::new(),
+    };
+
+    pair_swiss_round(&tournament, &mut swiss)?;
+
+    SWISS_STORAGE.with(|storage| storage.borrow_mut().insert(tournament.id, swiss.clone()));
+
+    start_tournament(tournament);
+
+    Ok(swiss)
+}
+
+// Fetch the progress of a Swiss tournament
 #[ic_cdk::query]
-fn get_user(id: u64) -> Result<User, String> {
-    USERS_STORAGE.with(|storage| match storage.borrow().get(&id) {
-        Some(user) => Ok(user.clone()),
-        None => Err(format!("User with ID {} not found", id)),
+fn get_swiss_tournament(tournament_id: u64) -> Result<SwissTournament, String> {
+    SWISS_STORAGE.with(|storage| match storage.borrow().get(&tournament_id) {
+        Some(swiss) => Ok(swiss.clone()),
+        None => Err(format!(
+            "No Swiss rounds found for tournament with ID {}",
+            tournament_id
+        )),
+    })
+}
+
+// Get the current standings of a Swiss tournament
+#[ic_cdk::query]
+fn get_swiss_standings(tournament_id: u64) -> Result<Vec<SwissStanding>, String> {
+    let swiss = get_swiss_tournament(tournament_id)?;
+    let tournament = get_tournament(tournament_id)?;
+    Ok(compute_swiss_standings(
+        &swiss,
+        &tournament_standings_rules(&tournament),
+    ))
+}
+
+// Pair the next Swiss round once the current one is complete, or finish the tournament after the last round
+fn advance_swiss(tournament: &Tournament) -> Result<(), String> {
+    let mut swiss = match SWISS_STORAGE.with(|storage| storage.borrow().get(&tournament.id)) {
+        Some(swiss) => swiss,
+        None => return Ok(()),
+    };
+
+    let round_complete = get_tournament_matches(tournament.id)
+        .iter()
+        .filter(|match_obj| match_obj.round == Some(swiss.current_round))
+        .all(|match_obj| match_obj.result.is_some());
+
+    if !round_complete {
+        return Ok(());
+    }
+
+    // A corrected result in a completed tournament only recomputes its champion
+    if swiss.current_round < swiss.total_rounds && tournament.status != TournamentStatus::Completed
+    {
+        pair_swiss_round(tournament, &mut swiss)?;
+        SWISS_STORAGE.with(|storage| storage.borrow_mut().insert(tournament.id, swiss));
+    } else {
+        let mut tournament = tournament.clone();
+        tournament.champion_team_id =
+            compute_swiss_standings(&swiss, &tournament_standings_rules(&tournament))
+                .first()
+                .map(|entry| entry.team_id);
+        tournament.status = TournamentStatus::Completed;
+        TOURNAMENTS_STORAGE.with(|storage| storage.borrow_mut().insert(tournament.id, tournament));
+    }
+
+    Ok(())
+}
+
+// Fetch the entry period of a tournament
+#[ic_cdk::query]
+fn get_tournament_registration(tournament_id: u64) -> Result<TournamentRegistration, String> {
+    REGISTRATIONS_STORAGE.with(|storage| match storage.borrow().get(&tournament_id) {
+        Some(registration) => Ok(registration.clone()),
+        None => Err(format!(
+            "Tournament with ID {} has no entry period",
+            tournament_id
+        )),
     })
 }
 
-// Function to get user by owner
-#[ic_cdk::query]
-fn get_user_by_owner() -> Result<User, String> {
-    USERS_STORAGE.with(|users| {
-        let users = users.borrow();
-        let user = users
-            .iter()
-            .find(|(_, user)| user.owner.to_text() == caller().to_text());
-        match user {
-            Some((_, client)) => Ok(client.clone()),
-            None => Err(format!("User not found for caller: {}", caller().to_text())),
-        }
-    })
+/**
+ * Function for administrators and league officials to open or change a tournament's entry period
+ * Dates are inclusive and in the format YYYY-MM-DD. The capacity counts the teams already in the tournament.
+ * Entries can no longer change once the draw has been made.
+ */
+#[ic_cdk::update]
+fn open_tournament_registration(
+    payload: OpenRegistrationPayload,
+) -> Result<TournamentRegistration, String> {
+    let tournament = get_tournament(payload.tournament_id)?;
+    ensure_tournament_official(&tournament)?;
+
+    if tournament.status != TournamentStatus::Planned {
+        return Err("Entries are locked once the draw has been made".to_string());
+    }
+
+    let opens_on = parse_date(&payload.opens_on)?;
+    let closes_on = parse_date(&payload.closes_on)?;
+
+    if closes_on < opens_on {
+        return Err("The entry period must close on or after the day it opens".to_string());
+    }
+
+    if payload.capacity < 2 || payload.capacity as usize > MAX_TOURNAMENT_TEAMS {
+        return Err(format!(
+            "Capacity must be between 2 and {} teams",
+            MAX_TOURNAMENT_TEAMS
+        ));
+    }
+
+    if (payload.capacity as usize) < tournament.team_ids.len() {
+        return Err("Capacity cannot be below the number of teams already entered".to_string());
+    }
+
+    let mut eligibility = payload.eligibility;
+    eligibility.allowed_universities = eligibility
+        .allowed_universities
+        .into_iter()
+        .filter_map(|university| normalize_university(Some(university)))
+        .collect();
+
+    if eligibility.allowed_universities.len() > MAX_RULES_UNIVERSITIES
+        || eligibility
+            .allowed_universities
+            .iter()
+            .any(|university| university.len() > MAX_UNIVERSITY_NAME_LENGTH)
+    {
+        return Err(format!(
+            "Entries can be limited to at most {} universities of up to {} characters",
+            MAX_RULES_UNIVERSITIES, MAX_UNIVERSITY_NAME_LENGTH
+        ));
+    }
+
+    let registration = TournamentRegistration {
+        tournament_id: tournament.id,
+        opens_on: payload.opens_on,
+        closes_on: payload.closes_on,
+        capacity: payload.capacity,
+        eligibility,
+        locked: false,
+        locked_at: None,
+    };
+
+    REGISTRATIONS_STORAGE.with(|storage| {
+        storage
+            .borrow_mut()
+            .insert(tournament.id, registration.clone())
+    });
+
+    Ok(registration)
+}
+
+// Check a team against a tournament's sport and eligibility rules
+fn check_entry_eligibility(
+    tournament: &Tournament,
+    registration: &TournamentRegistration,
+    team: &Team,
+) -> Result<(), String> {
+    if team.sport_type != tournament.sport_type {
+        return Err(format!(
+            "Team {} plays {:?}, not {:?}",
+            team.name, team.sport_type, tournament.sport_type
+        ));
+    }
+
+    check_team_eligibility(&registration.eligibility, team)
+}
+
+// Check a team against a set of eligibility rules
+fn check_team_eligibility(eligibility: &EntryEligibility, team: &Team) -> Result<(), String> {
+    if !eligibility.allowed_universities.is_empty()
+        && !team
+            .university
+            .as_ref()
+            .is_some_and(|university| eligibility.allowed_universities.contains(university))
+    {
+        return Err(format!(
+            "Team {} is not from a university allowed to enter",
+            team.name
+        ));
+    }
+
+    if (team.members.len() as u32) < eligibility.minimum_members {
+        return Err(format!(
+            "Team {} needs at least {} members to enter",
+            team.name, eligibility.minimum_members
+        ));
+    }
+
+    if eligibility.require_coach && team.coaches.is_empty() {
+        return Err(format!("Team {} needs a coach to enter", team.name));
+    }
+
+    Ok(())
+}
+
+// Save a tournament entry
+fn save_entry(entry: &TournamentEntry) {
+    TOURNAMENT_ENTRIES_STORAGE.with(|storage| storage.borrow_mut().insert(entry.id, entry.clone()));
 }
 
-// Function to retrieve all users and throw an error if no users are found
-#[ic_cdk::query]
-fn get_all_users() -> Result<Vec<User>, String> {
-    USERS_STORAGE.with(|storage| {
-        let users: Vec<User> = storage
+// Get the entries of a tournament in the order they applied
+fn get_entries_for_tournament(tournament_id: u64) -> Vec<TournamentEntry> {
+    let mut entries: Vec<TournamentEntry> = TOURNAMENT_ENTRIES_STORAGE.with(|storage| {
+        storage
             .borrow()
             .iter()
-            .map(|(_, user)| user.clone())
-            .collect();
-        if users.is_empty() {
-            Err("No users found".to_string())
-        } else {
-            Ok(users)
-        }
-    })
+            .map(|(_, entry)| entry)
+            .filter(|entry| entry.tournament_id == tournament_id)
+            .collect()
+    });
+    entries.sort_by_key(|entry| (entry.applied_at, entry.id));
+    entries
 }
 
-// FUnction to get user by name, putting into consideration the case sensitivity
-#[ic_cdk::query]
-fn get_user_by_name(name: String) -> Result<User, String> {
-    USERS_STORAGE.with(|storage| {
-        let users = storage.borrow();
-        let user = users
+// Fetch the registration of a tournament whose entries can still change
+fn get_open_registration(tournament_id: u64) -> Result<TournamentRegistration, String> {
+    let registration = get_tournament_registration(tournament_id)?;
+    if registration.locked {
+        return Err("Entries are locked once the draw has been made".to_string());
+    }
+    Ok(registration)
+}
+
+/**
+ * Function for a team's coach to apply for a tournament
+ * Applications before the entry period opens are refused, and applications after it closes go straight to the waitlist.
+ */
+#[ic_cdk::update]
+fn apply_for_tournament(payload: ApplyForTournamentPayload) -> Result<TournamentEntry, String> {
+    let applicant = get_caller_user()?;
+    let tournament = get_tournament(payload.tournament_id)?;
+    let registration = get_open_registration(tournament.id)?;
+    let team = get_team(payload.team_id)?;
+
+    if applicant.role != UserRole::Administrator && !team.coaches.contains(&applicant.id) {
+        return Err("Only the team's coaches can enter it into a tournament".to_string());
+    }
+
+    if team.sport_type != tournament.sport_type {
+        return Err(format!(
+            "Team {} plays {:?}, not {:?}",
+            team.name, team.sport_type, tournament.sport_type
+        ));
+    }
+
+    let already_entered = tournament.team_ids.contains(&team.id)
+        || get_entries_for_tournament(tournament.id)
             .iter()
-            .find(|(_, user)| user.name.to_lowercase() == name.to_lowercase());
-        match user {
-            Some((_, client)) => Ok(client.clone()),
-            None => Err(format!("User with name {} not found", name)),
-        }
-    })
+            .any(|entry| {
+                entry.team_id == team.id
+                    && entry.status != EntryStatus::Rejected
+                    && entry.status != EntryStatus::Withdrawn
+            });
+
+    if already_entered {
+        return Err(format!(
+            "Team {} has already entered this tournament",
+            team.name
+        ));
+    }
+
+    let today = today();
+    if today < parse_date(&registration.opens_on)? {
+        return Err(format!("Entries open on {}", registration.opens_on));
+    }
+
+    let late = today > parse_date(&registration.closes_on)?;
+
+    let entry = TournamentEntry {
+        id: generate_uuid(),
+        tournament_id: tournament.id,
+        team_id: team.id,
+        applied_by: applicant.id,
+        applied_at: ic_cdk::api::time(),
+        late,
+        status: if late {
+            EntryStatus::Waitlisted
+        } else {
+            EntryStatus::Pending
+        },
+        reviewed_by: None,
+        reviewed_at: None,
+        rejection_reason: None,
+    };
+
+    save_entry(&entry);
+
+    Ok(entry)
 }
 
 /**
- * Function to create a new team
- * This function takes a CreateTeamPayload as input and returns a Result containing either a Team or a String error message.
- * It generates a unique ID for the new team, creates a Team object, and stores it in the TEAMS_STORAGE.
- * Performs validation checks on input data and handles potential registration errors.
+ * Function for administrators and league officials to approve or reject a pending or waitlisted entry
+ * Approved teams join the tournament. An eligible entry approved while the tournament is full is waitlisted instead.
  */
+#[ic_cdk::update]
+fn review_tournament_entry(payload: ReviewEntryPayload) -> Result<TournamentEntry, String> {
+    let mut entry = TOURNAMENT_ENTRIES_STORAGE
+        .with(|storage| storage.borrow().get(&payload.entry_id))
+        .ok_or_else(|| format!("Entry with ID {} not found", payload.entry_id))?;
+    let reviewer = ensure_tournament_official(&get_tournament(entry.tournament_id)?)?;
+
+    if entry.status != EntryStatus::Pending && entry.status != EntryStatus::Waitlisted {
+        return Err(format!(
+            "Only pending or waitlisted entries can be reviewed, this entry is {:?}",
+            entry.status
+        ));
+    }
+
+    if payload
+        .reason
+        .as_ref()
+        .is_some_and(|reason| reason.len() > MAX_REJECTION_REASON_LENGTH)
+    {
+        return Err(format!(
+            "The reason cannot exceed {} characters",
+            MAX_REJECTION_REASON_LENGTH
+        ));
+    }
+
+    let registration = get_open_registration(entry.tournament_id)?;
+    let mut tournament = get_tournament(entry.tournament_id)?;
+
+    entry.reviewed_by = Some(reviewer.id);
+    entry.reviewed_at = Some(ic_cdk::api::time());
+
+    if !payload.approve {
+        entry.status = EntryStatus::Rejected;
+        entry.rejection_reason = payload.reason;
+        save_entry(&entry);
+        return Ok(entry);
+    }
+
+    let team = get_team(entry.team_id)?;
+    check_entry_eligibility(&tournament, &registration, &team)?;
+
+    if tournament.team_ids.len() >= registration.capacity as usize {
+        entry.status = EntryStatus::Waitlisted;
+    } else {
+        entry.status = EntryStatus::Approved;
+        tournament.team_ids.push(entry.team_id);
+        TOURNAMENTS_STORAGE.with(|storage| storage.borrow_mut().insert(tournament.id, tournament));
+    }
+
+    save_entry(&entry);
+
+    Ok(entry)
+}
 
+/**
+ * Function for a team's coach to withdraw its entry before the draw
+ * A withdrawn approved team leaves the tournament and its place goes to the longest-waiting eligible team approved onto
+ * the waitlist. Failing that, the longest-waiting late entry goes back to pending so it can be reviewed.
+ */
 #[ic_cdk::update]
-fn create_team(payload: CreateTeamPayload) -> Result<Team, String> {
-    // Validate the team payload to ensure all required fields are present
-    if payload.name.is_empty() {
-        return Err("Name is a required field".to_string());
+fn withdraw_tournament_entry(entry_id: u64) -> Result<TournamentEntry, String> {
+    let user = get_caller_user()?;
+
+    let mut entry = TOURNAMENT_ENTRIES_STORAGE
+        .with(|storage| storage.borrow().get(&entry_id))
+        .ok_or_else(|| format!("Entry with ID {} not found", entry_id))?;
+
+    if user.role != UserRole::Administrator && !is_team_coach(entry.team_id, user.id) {
+        return Err("Only the team's coaches can withdraw its entry".to_string());
     }
 
-    // Generate unique ID
-    let id = generate_uuid();
+    if entry.status == EntryStatus::Rejected || entry.status == EntryStatus::Withdrawn {
+        return Err(format!("This entry is already {:?}", entry.status));
+    }
 
-    // Create team object
-    let team = Team {
-        id,
-        name: payload.name,
-        sport_type: payload.sport_type,
-        members: Vec::new(),
-        coaches: Vec::new(),
-    };
+    let registration = get_open_registration(entry.tournament_id)?;
+    let mut tournament = get_tournament(entry.tournament_id)?;
 
-    // Store team in storage
-    TEAMS_STORAGE.with(|teams| {
-        teams.borrow_mut().insert(id, team.clone());
-        Ok(team)
-    })
+    let was_approved = entry.status == EntryStatus::Approved;
+    entry.status = EntryStatus::Withdrawn;
+    save_entry(&entry);
+
+    if was_approved {
+        tournament
+            .team_ids
+            .retain(|team_id| *team_id != entry.team_id);
+
+        // Entries waitlisted by a review were approved while the tournament was full; late entries were never reviewed
+        let (approved, late): (Vec<TournamentEntry>, Vec<TournamentEntry>) =
+            get_entries_for_tournament(tournament.id)
+                .into_iter()
+                .filter(|waiting| waiting.status == EntryStatus::Waitlisted)
+                .partition(|waiting| waiting.reviewed_by.is_some());
+
+        let promoted = approved.into_iter().find(|waiting| {
+            get_team(waiting.team_id).is_ok_and(|team| {
+                check_entry_eligibility(&tournament, &registration, &team).is_ok()
+            })
+        });
+
+        if let Some(mut promoted) = promoted {
+            promoted.status = EntryStatus::Approved;
+            promoted.reviewed_by = Some(user.id);
+            promoted.reviewed_at = Some(ic_cdk::api::time());
+            tournament.team_ids.push(promoted.team_id);
+            save_entry(&promoted);
+        } else if let Some(mut reopened) = late.into_iter().next() {
+            reopened.status = EntryStatus::Pending;
+            save_entry(&reopened);
+        }
+
+        TOURNAMENTS_STORAGE.with(|storage| storage.borrow_mut().insert(tournament.id, tournament));
+    }
+
+    Ok(entry)
 }
 
-// Fetch a team by ID
+// Get the entries of a tournament, in the order they applied
 #[ic_cdk::query]
-fn get_team(id: u64) -> Result<Team, String> {
-    TEAMS_STORAGE.with(|storage| match storage.borrow().get(&id) {
-        Some(team) => Ok(team.clone()),
-        None => Err(format!("Team with ID {} not found", id)),
-    })
+fn get_tournament_entries(tournament_id: u64) -> Result<Vec<TournamentEntry>, String> {
+    let entries = get_entries_for_tournament(tournament_id);
+    if entries.is_empty() {
+        Err("No entries found for this tournament".to_string())
+    } else {
+        Ok(entries)
+    }
 }
 
-// Function to get all teams
-#[ic_cdk::query]
-fn get_all_teams() -> Result<Vec<Team>, String> {
-    TEAMS_STORAGE.with(|storage| {
-        let teams: Vec<Team> = storage
-            .borrow()
-            .iter()
-            .map(|(_, team)| team.clone())
-            .collect();
-        if teams.is_empty() {
-            Err("No teams found".to_string())
-        } else {
-            Ok(teams)
+// Candid generator for exporting the Candid interface
+ic_cdk::export_candid!();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+
+    // Largest amount the mock payment backend will pay out, in the smallest currency unit
+    const MOCK_PAYMENT_LIMIT: u64 = 1_000_000;
+
+    // Mock backend, completing every payment up to MOCK_PAYMENT_LIMIT and declining larger ones
+    struct MockPaymentBackend;
+
+    impl PaymentBackend for MockPaymentBackend {
+        fn pay(&self, entry: &PaymentEntry, payee: &Referee) -> Error {
+            if entry.amount > MOCK_PAYMENT_LIMIT {
+                Error::PaymentFailed {
+                    msg: format!(
+                        "Payment of {} to {} exceeds the limit of {}",
+                        entry.amount, payee.name, MOCK_PAYMENT_LIMIT
+                    ),
+                }
+            } else {
+                Error::PaymentCompleted {
+                    msg: format!("MOCK-{}", entry.id),
+                }
+            }
         }
-    })
-}
+    }
 
-//  Function to add member to a team
-#[ic_cdk::update]
-fn add_member_to_team(payload: AddMemberPayload) -> Result<Team, String> {
-    let team_id = payload.team_id;
-    let member_id = payload.member_id;
+    fn insert_referee(id: u64) {
+        let referee = Referee {
+            id,
+            owner: Principal::anonymous(),
+            name: "Official".to_string(),
+            email: "official@example.com".to_string(),
+            address: "Campus".to_string(),
+            university: Some("Leeds".to_string()),
+            conflicts_of_interest: Vec::new(),
+            certifications: Vec::new(),
+            performance_rating: 0.0,
+            total_rating: 0.0,
+            total_ratings: 0,
+            total_matches: 1,
+        };
+        REFEREE_STORAGE.with(|storage| storage.borrow_mut().insert(id, referee));
+    }
 
-    // Check if team exists
-    let team = TEAMS_STORAGE.with(|storage| match storage.borrow().get(&team_id) {
-        Some(team) => Some(team.clone()),
-        None => None,
-    });
+    fn insert_payment(id: u64, referee_id: u64, amount: u64, status: PaymentStatus) {
+        let entry = PaymentEntry {
+            id,
+            referee_id,
+            match_id: 100,
+            assignment_id: 200,
+            kind: PaymentKind::MatchFee,
+            amount,
+            billed_university: Some("Leeds".to_string()),
+            status,
+            created_at: 1,
+            approved_by: None,
+            approved_at: None,
+            paid_at: None,
+            payment_reference: None,
+            failure_reason: None,
+        };
+        PAYMENTS_STORAGE.with(|storage| storage.borrow_mut().insert(id, entry));
+    }
 
-    if team.is_none() {
-        return Err("Team not found".to_string());
+    fn get_payment(id: u64) -> PaymentEntry {
+        PAYMENTS_STORAGE
+            .with(|storage| storage.borrow().get(&id))
+            .unwrap()
     }
 
-    // Check if member exists
-    let member = USERS_STORAGE.with(|storage| match storage.borrow().get(&member_id) {
-        Some(user) => Some(user.clone()),
-        None => None,
-    });
+    #[test]
+    fn approved_payments_are_paid_through_the_backend() {
+        insert_referee(1);
+        insert_payment(10, 1, 5_000, PaymentStatus::Approved);
+        insert_payment(11, 1, MOCK_PAYMENT_LIMIT + 1, PaymentStatus::Approved);
+        insert_payment(12, 99, 5_000, PaymentStatus::Approved);
+        insert_payment(13, 1, 5_000, PaymentStatus::Owed);
+        install_payment_backend(Box::new(MockPaymentBackend));
 
-    if member.is_none() {
-        return Err(format!("Member with ID {} not found", member_id));
+        let processed = pay_approved_payments(42).unwrap();
+        assert_eq!(processed.len(), 3);
+
+        let paid = get_payment(10);
+        assert_eq!(paid.status, PaymentStatus::Paid);
+        assert_eq!(paid.paid_at, Some(42));
+        assert_eq!(paid.payment_reference.as_deref(), Some("MOCK-10"));
+
+        let declined = get_payment(11);
+        assert_eq!(declined.status, PaymentStatus::Failed);
+        assert!(declined
+            .failure_reason
+            .unwrap()
+            .contains("exceeds the limit"));
+
+        let unknown_payee = get_payment(12);
+        assert_eq!(unknown_payee.status, PaymentStatus::Failed);
+        assert!(unknown_payee.failure_reason.unwrap().contains("not found"));
+
+        assert_eq!(get_payment(13).status, PaymentStatus::Owed);
+        assert!(pay_approved_payments(43).is_err());
     }
 
-    // Check if member is already part of a team
-    let member_already_in_team = TEAMS_STORAGE.with(|storage| {
-        storage
-            .borrow()
+    #[test]
+    fn payments_are_refused_without_a_backend() {
+        insert_referee(1);
+        insert_payment(10, 1, 5_000, PaymentStatus::Approved);
+
+        let err = pay_approved_payments(42).unwrap_err();
+        assert!(err.contains("No payment backend"));
+        assert_eq!(get_payment(10).status, PaymentStatus::Approved);
+    }
+
+    #[test]
+    fn chosen_backend_pays_and_is_restored_after_an_upgrade() {
+        insert_referee(1);
+        insert_payment(10, 1, 5_000, PaymentStatus::Approved);
+        insert_payment(11, 1, 20_000, PaymentStatus::Approved);
+
+        assert!(
+            select_payment_backend(PaymentBackendKind::Sandbox { payout_limit: 0 }, 7, 1).is_err()
+        );
+        let settings = select_payment_backend(
+            PaymentBackendKind::Sandbox {
+                payout_limit: 10_000,
+            },
+            7,
+            1,
+        )
+        .unwrap();
+        assert_eq!(settings.set_by, Some(7));
+
+        pay_approved_payments(42).unwrap();
+        assert_eq!(
+            get_payment(10).payment_reference.as_deref(),
+            Some("SANDBOX-10")
+        );
+        assert_eq!(get_payment(11).status, PaymentStatus::Failed);
+
+        // An upgrade clears the installed backend; the stored choice brings it back
+        select_payment_backend(PaymentBackendKind::Manual, 7, 2).unwrap();
+        PAYMENT_BACKEND.with(|backend| *backend.borrow_mut() = None);
+        restore_payment_backend();
+
+        insert_payment(12, 1, 20_000, PaymentStatus::Approved);
+        pay_approved_payments(43).unwrap();
+        assert_eq!(get_payment(12).status, PaymentStatus::Paid);
+        assert_eq!(
+            get_payment(12).payment_reference.as_deref(),
+            Some("MANUAL-12")
+        );
+        assert_eq!(
+            get_payment_settings().backend,
+            Some(PaymentBackendKind::Manual)
+        );
+    }
+
+    #[test]
+    fn referees_move_out_of_their_original_storage_on_upgrade() {
+        insert_referee(1);
+        let referee = REFEREE_STORAGE
+            .with(|storage| storage.borrow_mut().remove(&1))
+            .unwrap();
+        MEMORY_MANAGER.with(|m| {
+            let mut legacy: StableBTreeMap<u64, LegacyStored<Referee>, Memory> =
+                StableBTreeMap::init(m.borrow().get(MemoryId::new(4)));
+            legacy.insert(1, LegacyStored(referee));
+        });
+
+        relocate_storage(MemoryId::new(4), &REFEREE_STORAGE);
+        assert_eq!(get_referee(1).unwrap().name, "Official");
+
+        // Nothing is left behind, so a later upgrade moves nothing
+        let remaining = MEMORY_MANAGER.with(|m| {
+            let legacy: StableBTreeMap<u64, LegacyStored<Referee>, Memory> =
+                StableBTreeMap::init(m.borrow().get(MemoryId::new(4)));
+            legacy.len()
+        });
+        assert_eq!(remaining, 0);
+        relocate_storage(MemoryId::new(4), &REFEREE_STORAGE);
+        assert!(get_referee(1).is_ok());
+    }
+
+    // The largest team its storage accepts, filled with members up to the team bound
+    fn largest_team(id: u64) -> Team {
+        let mut team = Team {
+            id: u64::MAX - id,
+            name: "Team".to_string(),
+            coaches: vec![u64::MAX],
+            sport_type: SportType::TableTennis,
+            members: Vec::new(),
+            university: Some("University".to_string()),
+        };
+        while team.to_bytes().len() + 32 <= Team::MAX_SIZE as usize {
+            team.members.push(u64::MAX);
+        }
+        team
+    }
+
+    #[test]
+    fn largest_match_fits_its_storage_bound() {
+        let match_obj = Match {
+            id: u64::MAX,
+            home_team: largest_team(1),
+            away_team: largest_team(2),
+            sport_type: SportType::TableTennis,
+            scheduled_date: "2024-12-31".to_string(),
+            kickoff_time: Some("23:59".to_string()),
+            result: Some(MatchResult {
+                winner_team_id: Some(u64::MAX),
+                score_team_a: u32::MAX,
+                score_team_b: u32::MAX,
+                notes: "n".repeat(MAX_RESULT_NOTES_LENGTH),
+                extra_time: Some(ExtraTime {
+                    score_team_a: u32::MAX,
+                    score_team_b: u32::MAX,
+                    penalties: Some(PenaltyShootout {
+                        score_team_a: u32::MAX,
+                        score_team_b: u32::MAX,
+                    }),
+                }),
+                forfeited_by: Some(u64::MAX),
+            }),
+            tournament_id: Some(u64::MAX),
+            round: Some(u32::MAX),
+            league_id: Some(u64::MAX),
+            season_id: Some(u64::MAX),
+            stage: Some(MatchStage::WinnersBracket),
+            leg: Some(u32::MAX),
+        };
+
+        assert!(match_obj.to_bytes().len() <= Match::MAX_SIZE as usize);
+        MATCHES_STORAGE.with(|storage| storage.borrow_mut().insert(match_obj.id, match_obj));
+    }
+
+    fn test_team(id: u64) -> Team {
+        Team {
+            id,
+            name: format!("Team {}", id),
+            coaches: Vec::new(),
+            sport_type: SportType::Football,
+            members: Vec::new(),
+            university: None,
+        }
+    }
+
+    fn played_match(
+        id: u64,
+        home_team_id: u64,
+        away_team_id: u64,
+        (home_score, away_score): (u32, u32),
+        winner_team_id: Option<u64>,
+    ) -> Match {
+        Match {
+            id,
+            home_team: test_team(home_team_id),
+            away_team: test_team(away_team_id),
+            sport_type: SportType::Football,
+            scheduled_date: "2024-03-01".to_string(),
+            kickoff_time: None,
+            result: Some(MatchResult {
+                winner_team_id,
+                score_team_a: home_score,
+                score_team_b: away_score,
+                notes: String::new(),
+                extra_time: None,
+                forfeited_by: None,
+            }),
+            tournament_id: None,
+            round: None,
+            league_id: None,
+            season_id: None,
+            stage: None,
+            leg: None,
+        }
+    }
+
+    fn winner_of(match_id: u64) -> Option<u64> {
+        get_match(match_id).unwrap().result.unwrap().winner_team_id
+    }
+
+    #[test]
+    fn drawn_results_lose_their_winner_on_upgrade() {
+        // Installs whose IDs started at 0 hold a team 0, so a recorded winner of 0 is only a draw on a level score
+        let matches = [
+            played_match(1, 0, 5, (1, 1), Some(0)),
+            played_match(2, 0, 5, (2, 1), Some(0)),
+            played_match(3, 5, 6, (0, 3), Some(0)),
+            played_match(4, 5, 6, (3, 0), Some(5)),
+        ];
+        for match_obj in matches {
+            MATCHES_STORAGE.with(|storage| storage.borrow_mut().insert(match_obj.id, match_obj));
+        }
+
+        migrate_drawn_results();
+        assert_eq!(winner_of(1), None);
+        assert_eq!(winner_of(2), Some(0));
+        assert_eq!(winner_of(3), Some(6));
+        assert_eq!(winner_of(4), Some(5));
+    }
+
+    #[test]
+    fn round_robin_pairs_every_team_once_with_balanced_venues() {
+        for team_count in [5u64, 6] {
+            let team_ids: Vec<u64> = (1..=team_count).collect();
+            let rounds = round_robin_pairings(&team_ids, false);
+            let slots = team_count + team_count % 2;
+            assert_eq!(rounds.len() as u64, slots - 1);
+
+            let mut meetings = BTreeSet::new();
+            let mut byes: BTreeMap<u64, u32> = BTreeMap::new();
+            let mut home_minus_away: BTreeMap<u64, i32> = BTreeMap::new();
+            for round in &rounds {
+                assert_eq!(round.len() as u64, slots / 2);
+                let mut playing = BTreeSet::new();
+                for pairing in round {
+                    match *pairing {
+                        (Some(home), Some(away)) => {
+                            assert!(meetings.insert((home.min(away), home.max(away))));
+                            *home_minus_away.entry(home).or_default() += 1;
+                            *home_minus_away.entry(away).or_default() -= 1;
+                            assert!(playing.insert(home) && playing.insert(away));
+                        }
+                        (Some(team), None) | (None, Some(team)) => {
+                            *byes.entry(team).or_default() += 1;
+                            assert!(playing.insert(team));
+                        }
+                        (None, None) => panic!("a pairing without teams"),
+                    }
+                }
+                assert_eq!(playing.len() as u64, team_count);
+            }
+
+            assert_eq!(meetings.len() as u64, team_count * (team_count - 1) / 2);
+            if team_count % 2 == 1 {
+                assert!(team_ids.iter().all(|id| byes.get(id) == Some(&1)));
+            } else {
+                assert!(byes.is_empty());
+            }
+            assert!(home_minus_away.values().all(|balance| balance.abs() <= 1));
+        }
+    }
+
+    #[test]
+    fn double_round_robin_repeats_rounds_with_venues_swapped() {
+        let rounds = round_robin_pairings(&[1, 2, 3, 4], true);
+        assert_eq!(rounds.len(), 6);
+
+        for (first, second) in rounds[..3].iter().zip(&rounds[3..]) {
+            let swapped: Vec<_> = first.iter().map(|(home, away)| (*away, *home)).collect();
+            assert_eq!(&swapped, second);
+        }
+    }
+
+    fn test_tournament(id: u64, structure: TournamentStructure, team_ids: Vec<u64>) -> Tournament {
+        for team_id in &team_ids {
+            TEAMS_STORAGE
+                .with(|storage| storage.borrow_mut().insert(*team_id, test_team(*team_id)));
+        }
+
+        Tournament {
+            id,
+            name: "Cup".to_string(),
+            structure,
+            team_ids,
+            sport_type: SportType::Football,
+            created_by: 1,
+            created_at: 0,
+            updated_at: None,
+            status: TournamentStatus::InProgress,
+            champion_team_id: None,
+            standings_rules: StandingsRules::default(),
+        }
+    }
+
+    #[test]
+    fn top_seeds_only_meet_in_late_rounds() {
+        assert_eq!(bracket_seed_order(2), vec![1, 2]);
+        assert_eq!(bracket_seed_order(8), vec![1, 8, 4, 5, 2, 7, 3, 6]);
+
+        // Every first-round pair adds up to one more than the bracket size, and each quarter holds one top-four seed
+        let order = bracket_seed_order(16);
+        assert!(order.chunks(2).all(|pair| pair[0] + pair[1] == 17));
+        for quarter in order.chunks(4) {
+            assert_eq!(quarter.iter().filter(|seed| **seed <= 4).count(), 1);
+        }
+        assert!(order[..8].contains(&1) && order[8..].contains(&2));
+    }
+
+    #[test]
+    fn byes_go_to_the_top_seeds() {
+        let team_ids: Vec<u64> = (101..=106).collect();
+        let tournament = test_tournament(1, TournamentStructure::Knockout, team_ids.clone());
+        let round_dates = vec![
+            "2024-03-01".to_string(),
+            "2024-03-08".to_string(),
+            "2024-03-15".to_string(),
+        ];
+
+        assert!(build_bracket(
+            &tournament,
+            team_ids.clone(),
+            round_dates[..2].to_vec(),
+            None,
+            false,
+            0,
+            None
+        )
+        .is_err());
+
+        let bracket =
+            build_bracket(&tournament, team_ids, round_dates, None, false, 0, None).unwrap();
+        assert_eq!(bracket.rounds, 3);
+        assert_eq!(bracket.nodes.len(), 7);
+
+        // Seeds 1 and 2 have byes into the semi-finals, on opposite sides of the bracket
+        assert_eq!(bracket.nodes[1].home_team_id, Some(101));
+        assert_eq!(bracket.nodes[1].home_seed, Some(1));
+        assert_eq!(bracket.nodes[2].home_team_id, Some(102));
+        assert!(bracket.nodes[3].away_team_id.is_none() && bracket.nodes[3].match_id.is_none());
+        assert!(bracket.nodes[5].away_team_id.is_none() && bracket.nodes[5].match_id.is_none());
+
+        // The other four seeds play the first round, the fourth against the fifth and the third against the sixth
+        let first_round = |node: &BracketNode| (node.home_team_id, node.away_team_id);
+        assert_eq!(first_round(&bracket.nodes[4]), (Some(104), Some(105)));
+        assert_eq!(first_round(&bracket.nodes[6]), (Some(103), Some(106)));
+        for node in [&bracket.nodes[4], &bracket.nodes[6]] {
+            let match_obj = get_match(node.match_id.unwrap()).unwrap();
+            assert_eq!(match_obj.round, Some(1));
+            assert_eq!(match_obj.scheduled_date, "2024-03-01");
+        }
+        assert_eq!(get_tournament_matches(1).len(), 2);
+    }
+
+    #[test]
+    fn double_elimination_drops_every_winners_loser_once() {
+        let nodes = layout_double_elimination(8, true);
+        let on_side = |side: EliminationSide| nodes.iter().filter(|node| node.side == side).count();
+        assert_eq!(on_side(EliminationSide::Winners), 7);
+        assert_eq!(on_side(EliminationSide::Losers), 6);
+        assert_eq!(on_side(EliminationSide::GrandFinal), 1);
+        assert_eq!(on_side(EliminationSide::GrandFinalReset), 1);
+
+        let sources: Vec<SlotSource> = nodes
             .iter()
-            .any(|(_, team)| team.members.contains(&member_id))
-    });
+            .flat_map(|node| [node.home_source, node.away_source])
+            .collect();
+        for node in nodes
+            .iter()
+            .filter(|node| node.side == EliminationSide::Winners)
+        {
+            let drops = sources
+                .iter()
+                .filter(|source| **source == SlotSource::LoserOf(node.id))
+                .count();
+            assert_eq!(drops, 1);
+        }
 
-    if member_already_in_team {
-        return Err("Member is already part of a team".to_string());
+        // A node is played after every node it takes a team from
+        for node in &nodes {
+            for source in [node.home_source, node.away_source] {
+                if let SlotSource::WinnerOf(id) | SlotSource::LoserOf(id) = source {
+                    assert!(nodes[id as usize - 1].round < node.round);
+                }
+            }
+        }
+
+        let grand_final = &nodes[nodes.len() - 2];
+        assert_eq!(grand_final.home_source, SlotSource::WinnerOf(7));
+        assert_eq!(layout_double_elimination(8, false).len(), nodes.len() - 1);
+    }
+
+    #[test]
+    fn swiss_pairing_avoids_rematches() {
+        let mut steps = 0;
+        let played = BTreeSet::from([(1, 2), (3, 4)]);
+        let pairs = pair_without_rematches(&[1, 2, 3, 4], &played, &mut steps).unwrap();
+        assert_eq!(pairs, vec![(1, 3), (2, 4)]);
+
+        // The leader has met everyone, so the round cannot be paired without a rematch
+        let played = BTreeSet::from([(1, 2), (1, 3), (1, 4)]);
+        assert!(pair_without_rematches(&[1, 2, 3, 4], &played, &mut steps).is_none());
+
+        let mut steps = MAX_SWISS_PAIRING_STEPS;
+        assert!(pair_without_rematches(&[1, 2], &BTreeSet::new(), &mut steps).is_none());
+    }
+
+    // A played match whose winner follows from the score
+    fn decided_match(id: u64, home_team_id: u64, away_team_id: u64, score: (u32, u32)) -> Match {
+        let match_obj = played_match(id, home_team_id, away_team_id, score, None);
+        let winner_team_id = winner_from_scores(&match_obj, score.0, score.1, None);
+        played_match(id, home_team_id, away_team_id, score, winner_team_id)
     }
 
-    // Ensure the member is a player
-    if member.as_ref().unwrap().role != UserRole::Player {
-        return Err("Member must be a player".to_string());
+    fn ranking(standings: &[StandingsEntry]) -> Vec<u64> {
+        standings.iter().map(|entry| entry.team_id).collect()
     }
 
-    // Add member to team
-    TEAMS_STORAGE.with(|teams| {
-        let mut teams = teams.borrow_mut();
-        match teams.get(&team_id) {
-            Some(existing_team) => {
-                let mut team = existing_team.clone();
-                if team.members.contains(&member_id) {
-                    return Err("Member is already in this team".to_string());
-                }
-                team.members.push(member_id);
-                teams.insert(team_id, team.clone());
-                Ok(team)
-            }
-            None => Err("Team not found".to_string()),
+    fn rules_with(tiebreakers: Vec<Tiebreaker>) -> StandingsRules {
+        StandingsRules {
+            tiebreakers,
+            ..StandingsRules::default()
         }
-    })
-}
-// Schedule a match
-#[ic_cdk::update]
-pub fn schedule_match(payload: ScheduleMatchPayload) -> Result<Match, String> {
-    // Validate the match payload to ensure all required fields are present
-    if payload.scheduled_date.is_empty() {
-        return Err("Ensure scheduled date is provided in the format YYYY-MM-DD".to_string());
     }
 
-    let sport_type = payload.sport_type.clone();
-    let home_team_id = payload.home_team_id;
-    let away_team_id = payload.away_team_id;
-    let scheduled_date = payload.scheduled_date;
+    #[test]
+    fn head_to_head_ranks_level_teams_before_goal_difference() {
+        let matches = [
+            decided_match(11, 1, 2, (1, 0)),
+            decided_match(12, 3, 1, (1, 0)),
+            decided_match(13, 2, 3, (4, 0)),
+            decided_match(14, 3, 4, (0, 0)),
+        ];
 
-    // Check if home team exists
-    let home_team = TEAMS_STORAGE.with(|storage| match storage.borrow().get(&home_team_id) {
-        Some(team) => Some(team.clone()),
-        None => None,
-    });
+        let standings = compute_standings(&[1, 2, 3, 4], &matches, &StandingsRules::default(), 1);
+        assert_eq!(ranking(&standings), vec![3, 1, 2, 4]);
+        assert_eq!((standings[1].points, standings[2].points), (3, 3));
+        assert!(standings[2].goal_difference > standings[1].goal_difference);
+        assert_eq!(
+            standings
+                .iter()
+                .map(|entry| entry.position)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
 
-    if home_team.is_none() {
-        return Err("Home team not found".to_string());
+        // Without head-to-head, the better goal difference comes first
+        let rules = rules_with(vec![Tiebreaker::GoalDifference]);
+        let standings = compute_standings(&[1, 2, 3, 4], &matches, &rules, 1);
+        assert_eq!(ranking(&standings), vec![3, 2, 1, 4]);
     }
 
-    // Check if away team exists
-    let away_team = TEAMS_STORAGE.with(|storage| match storage.borrow().get(&away_team_id) {
-        Some(team) => Some(team.clone()),
-        None => None,
-    });
+    #[test]
+    fn goal_difference_then_goals_scored_follow_the_configured_order() {
+        // Teams 1 and 2 never meet; 1 wins 4-3 and 2 wins 2-0
+        let matches = [
+            decided_match(11, 1, 3, (4, 3)),
+            decided_match(12, 2, 4, (2, 0)),
+        ];
 
-    if away_team.is_none() {
-        return Err("Away team not found".to_string());
+        let standings = compute_standings(&[1, 2, 3, 4], &matches, &StandingsRules::default(), 1);
+        assert_eq!(ranking(&standings)[..2], [2, 1]);
+
+        let rules = rules_with(vec![Tiebreaker::GoalsScored, Tiebreaker::GoalDifference]);
+        let standings = compute_standings(&[1, 2, 3, 4], &matches, &rules, 1);
+        assert_eq!(ranking(&standings)[..2], [1, 2]);
     }
 
-    // Ensure the teams are not the same
-    if home_team_id == away_team_id {
-        return Err("Home team and away team cannot be the same".to_string());
+    #[test]
+    fn fair_play_then_drawing_of_lots_separate_identical_records() {
+        let matches = [
+            decided_match(11, 1, 3, (1, 0)),
+            decided_match(12, 2, 4, (1, 0)),
+        ];
+        let card = Card {
+            id: 20,
+            match_id: 11,
+            player_id: 30,
+            team_id: 1,
+            sport_type: SportType::Football,
+            season: "2024".to_string(),
+            season_id: None,
+            card_type: CardType::Yellow,
+            minute: None,
+            offence: "Dissent".to_string(),
+            issued_by: 1,
+            issued_at: 0,
+        };
+        CARDS_STORAGE.with(|storage| storage.borrow_mut().insert(card.id, card));
+
+        let standings = compute_standings(&[1, 2, 3, 4], &matches, &StandingsRules::default(), 1);
+        assert_eq!(ranking(&standings)[..2], [2, 1]);
+        assert_eq!(standings[1].fair_play_points, 1);
+
+        // Without fair play the lots decide, the same way whatever order the teams are given in
+        let rules = rules_with(vec![Tiebreaker::DrawingOfLots]);
+        let by_lot = if drawn_lot(1, 1) < drawn_lot(1, 2) {
+            [1, 2]
+        } else {
+            [2, 1]
+        };
+        for team_ids in [[1, 2, 3, 4], [2, 1, 4, 3]] {
+            let standings = compute_standings(&team_ids, &matches, &rules, 1);
+            assert_eq!(ranking(&standings)[..2], by_lot);
+        }
+
+        // With no tiebreakers, level teams keep the order they were given in
+        let standings = compute_standings(&[2, 1, 3, 4], &matches, &rules_with(Vec::new()), 1);
+        assert_eq!(ranking(&standings)[..2], [2, 1]);
     }
 
-    // Generate unique ID for the match
-    let id = generate_uuid();
+    fn test_tie(away_goals: bool) -> TwoLeggedTie {
+        TwoLeggedTie {
+            id: 1,
+            tournament_id: 1,
+            team_a_id: 1,
+            team_b_id: 2,
+            first_leg_match_id: 11,
+            second_leg_match_id: 12,
+            away_goals,
+            aggregate_team_a: 0,
+            aggregate_team_b: 0,
+            winner_team_id: None,
+            decided_by: None,
+        }
+    }
 
-    // Create match object
-    let match_obj = Match {
-        id,
-        sport_type,
-        home_team: home_team.unwrap(),
-        away_team: away_team.unwrap(),
-        scheduled_date,
-        result: None,
-    };
+    // A leg's result from the home side's point of view, with extra time and penalties as (home, away)
+    fn leg_result(
+        (home, away): (u32, u32),
+        extra_time: Option<(u32, u32)>,
+        penalties: Option<(u32, u32)>,
+    ) -> MatchResult {
+        MatchResult {
+            winner_team_id: None,
+            score_team_a: home,
+            score_team_b: away,
+            notes: String::new(),
+            extra_time: extra_time.map(|(home, away)| ExtraTime {
+                score_team_a: home,
+                score_team_b: away,
+                penalties: penalties.map(|(home, away)| PenaltyShootout {
+                    score_team_a: home,
+                    score_team_b: away,
+                }),
+            }),
+            forfeited_by: None,
+        }
+    }
 
-    // Store match in storage
-    MATCHES_STORAGE.with(|matches| {
-        matches.borrow_mut().insert(id, match_obj.clone());
-        Ok(match_obj)
-    })
-}
+    #[test]
+    fn ties_are_decided_on_aggregate_then_away_goals() {
+        // Team 2 hosts the second leg
+        let second_leg_match = played_match(12, 2, 1, (0, 0), None);
 
-// Submit match result
-#[ic_cdk::update]
-pub fn submit_match_result(payload: MatchResultPayload) -> Result<Match, String> {
-    let match_id = payload.match_id;
-    let result = payload.result;
+        let decision = resolve_tie(
+            &test_tie(true),
+            &leg_result((2, 0), None, None),
+            &second_leg_match,
+            &leg_result((2, 1), None, None),
+        );
+        assert_eq!(decision, Ok((1, TieDecision::Aggregate)));
 
-    // Check if match exists
-    let existing_match = MATCHES_STORAGE.with(|storage| match storage.borrow().get(&match_id) {
-        Some(match_obj) => Some(match_obj.clone()),
-        None => None,
-    });
+        // 3-3 on aggregate, with three away goals for team 2 against one for team 1
+        let first_leg = leg_result((2, 3), None, None);
+        let second_leg = leg_result((0, 1), None, None);
+        let decision = resolve_tie(&test_tie(true), &first_leg, &second_leg_match, &second_leg);
+        assert_eq!(decision, Ok((2, TieDecision::AwayGoals)));
 
-    if existing_match.is_none() {
-        return Err(format!("Match with ID {} not found", match_id));
+        // Without the away goals rule the same tie needs extra time
+        let err = resolve_tie(&test_tie(false), &first_leg, &second_leg_match, &second_leg);
+        assert!(err.unwrap_err().contains("needs extra time"));
     }
 
-    // Check if result is already submitted
-    if existing_match.as_ref().unwrap().result.is_some() {
-        return Err("Match result has already been submitted".to_string());
+    #[test]
+    fn level_ties_go_to_extra_time_then_penalties() {
+        let second_leg_match = played_match(12, 2, 1, (0, 0), None);
+        let first_leg = leg_result((1, 0), None, None);
+
+        // Team 2 levels the tie in normal time and scores the winner in extra time
+        let decision = resolve_tie(
+            &test_tie(false),
+            &first_leg,
+            &second_leg_match,
+            &leg_result((2, 0), Some((1, 0)), None),
+        );
+        assert_eq!(decision, Ok((2, TieDecision::ExtraTime)));
+
+        // Away goals count again in extra time: 3-3 on aggregate, with two away goals for team 1 against one
+        let decision = resolve_tie(
+            &test_tie(true),
+            &leg_result((1, 1), None, None),
+            &second_leg_match,
+            &leg_result((2, 2), Some((1, 1)), None),
+        );
+        assert_eq!(decision, Ok((1, TieDecision::ExtraTime)));
+
+        let decision = resolve_tie(
+            &test_tie(false),
+            &first_leg,
+            &second_leg_match,
+            &leg_result((1, 0), Some((0, 0)), Some((3, 4))),
+        );
+        assert_eq!(decision, Ok((1, TieDecision::Penalties)));
+
+        let level = resolve_tie(
+            &test_tie(false),
+            &first_leg,
+            &second_leg_match,
+            &leg_result((1, 0), Some((0, 0)), None),
+        );
+        assert!(level.unwrap_err().contains("penalties are needed"));
+
+        // Extra time is only played when normal time leaves the tie level
+        let decided = resolve_tie(
+            &test_tie(false),
+            &first_leg,
+            &second_leg_match,
+            &leg_result((3, 0), Some((1, 0)), None),
+        );
+        assert!(decided.unwrap_err().contains("decided in normal time"));
     }
 
-    // Submit match result
-    MATCHES_STORAGE.with(|matches| {
-        let mut matches = matches.borrow_mut();
-        match matches.get(&match_id) {
-            Some(existing_match_obj) => {
-                let mut updated_match = existing_match_obj.clone();
-                updated_match.result = Some(result);
-                matches.insert(match_id.clone(), updated_match.clone());
-                Ok(updated_match)
-            }
-            None => Err("Match not found".to_string()),
+    fn entrant(team_id: u64, pot: u32, university: Option<&str>) -> DrawEntrant {
+        DrawEntrant {
+            team_id,
+            pot,
+            university: university.map(str::to_string),
         }
-    })
-}
+    }
 
-// Get leaderboard for a specific sport type
+    #[test]
+    fn group_draws_keep_pots_universities_and_conferences_apart() {
+        let entrants = vec![
+            entrant(1, 1, Some("Leeds")),
+            entrant(2, 1, Some("York")),
+            entrant(3, 2, Some("Leeds")),
+            entrant(4, 2, Some("York")),
+            entrant(5, 3, Some("Durham")),
+            entrant(6, 3, None),
+            entrant(7, 4, Some("Newcastle")),
+            entrant(8, 4, None),
+        ];
+        let constraints = DrawConstraints {
+            separate_universities: true,
+            conferences: vec![DrawConference {
+                name: "North East".to_string(),
+                universities: vec!["Durham".to_string(), "Newcastle".to_string()],
+            }],
+        };
+        let by_team = |team_id: u64| &entrants[team_id as usize - 1];
 
-/**
- * Function to assign a coach to a team
- * This function takes an AssignCoachPayload as input and returns a Result containing either a Team or a String error message.
- * It fetches the team and coach by their IDs, validates their existence, and assigns the coach to the team.
- * Handles potential errors such as missing team or coach, and duplicate coach assignment.
- */
-#[ic_cdk::update]
-fn assign_coach(payload: AssignCoachPayload) -> Result<Team, String> {
-    let team_id = payload.team_id;
-    let coach_id = payload.coach_id;
+        for seed in 0u8..20 {
+            let (draw_order, groups) =
+                run_group_draw(&entrants, 2, &constraints, &[seed; 32]).unwrap();
 
-    // Check if team exists
-    let team = TEAMS_STORAGE.with(|storage| match storage.borrow().get(&team_id) {
-        Some(team) => Some(team.clone()),
-        None => None,
-    });
+            // Pots are drawn in order, and every group takes one team from each pot
+            let pots: Vec<u32> = draw_order.iter().map(|id| by_team(*id).pot).collect();
+            assert!(pots.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert_eq!(groups.len(), 2);
+            for group in &groups {
+                assert_eq!(group.team_ids.len(), 4);
+                for (index, team_id) in group.team_ids.iter().enumerate() {
+                    for other in &group.team_ids[index + 1..] {
+                        assert!(!draw_clash(
+                            by_team(*team_id),
+                            by_team(*other),
+                            &constraints
+                        ));
+                    }
+                }
+            }
 
-    if team.is_none() {
-        return Err("Team not found".to_string());
+            let repeated = run_group_draw(&entrants, 2, &constraints, &[seed; 32]).unwrap();
+            assert_eq!(repeated.0, draw_order);
+        }
     }
 
-    // Check if coach exists
-    let coach = USERS_STORAGE.with(|storage| match storage.borrow().get(&coach_id) {
-        Some(user) => Some(user.clone()),
-        None => None,
-    });
+    #[test]
+    fn group_draws_fail_when_constraints_cannot_be_met() {
+        let entrants = vec![
+            entrant(1, 1, Some("Leeds")),
+            entrant(2, 1, Some("Leeds")),
+            entrant(3, 2, Some("Leeds")),
+            entrant(4, 2, Some("Leeds")),
+        ];
+        let constraints = DrawConstraints {
+            separate_universities: true,
+            conferences: Vec::new(),
+        };
 
-    if coach.is_none() {
-        return Err(format!("Coach with ID {} not found", coach_id));
+        let err = run_group_draw(&entrants, 2, &constraints, &[7; 32]).unwrap_err();
+        assert!(err.contains("No draw"));
+        assert!(run_group_draw(&entrants, 2, &DrawConstraints::default(), &[7; 32]).is_ok());
     }
 
-    // Check if coach is already part of a team
-    let coach_already_in_team = TEAMS_STORAGE.with(|storage| {
-        storage
-            .borrow()
-            .iter()
-            .any(|(_, team)| team.coaches.contains(&coach_id))
-    });
+    fn test_league(id: u64) -> League {
+        League {
+            id,
+            name: "Northern League".to_string(),
+            sport_type: SportType::Football,
+            team_ids: Vec::new(),
+            tournament_ids: Vec::new(),
+            created_by: 1,
+            created_at: 0,
+            updated_at: None,
+            standings_rules: StandingsRules::default(),
+        }
+    }
 
-    if coach_already_in_team {
-        return Err("Coach is already part of a team".to_string());
+    fn test_season(id: u64, league_id: u64) -> Season {
+        let period = |name: &str| SeasonPeriod {
+            name: name.to_string(),
+            start_date: "2024-09-01".to_string(),
+            end_date: "2024-09-30".to_string(),
+        };
+
+        Season {
+            id,
+            league_id,
+            name: "2024-25".to_string(),
+            start_date: "2024-09-01".to_string(),
+            end_date: "2025-06-30".to_string(),
+            registration_window: period("Registration"),
+            transfer_windows: Vec::new(),
+            exam_blackouts: vec![period("Exams")],
+            team_ids: Vec::new(),
+            tournament_ids: Vec::new(),
+            created_by: 1,
+            created_at: 0,
+            updated_at: None,
+            archived: false,
+            archived_at: None,
+        }
     }
 
-    // Ensure the coach is a coach
-    if coach.as_ref().unwrap().role != UserRole::Coach {
-        return Err("Coach must have Coach role".to_string());
+    fn test_division(
+        id: u64,
+        level: u32,
+        (promotion, playoff, relegation): (u32, u32, u32),
+    ) -> Division {
+        Division {
+            id,
+            league_id: 50,
+            name: format!("Division {}", level),
+            level,
+            promotion_spots: promotion,
+            playoff_spots: playoff,
+            relegation_spots: relegation,
+            created_at: 0,
+            updated_at: None,
+        }
     }
 
-    // Assign coach to team
-    TEAMS_STORAGE.with(|teams| {
-        let mut teams = teams.borrow_mut();
-        match teams.get(&team_id) {
-            Some(existing_team) => {
-                let mut team = existing_team.clone();
-                if team.coaches.contains(&coach_id) {
-                    return Err("Coach is already in this team".to_string());
-                }
-                team.coaches.push(coach_id);
-                teams.insert(team_id, team.clone());
-                Ok(team)
+    // Play every pairing of a season's division once, each team beating those listed after it
+    fn play_in_order(season_id: u64, team_ids: &[u64], next_match_id: &mut u64) {
+        for (index, home) in team_ids.iter().enumerate() {
+            for away in &team_ids[index + 1..] {
+                let mut match_obj = decided_match(*next_match_id, *home, *away, (1, 0));
+                match_obj.season_id = Some(season_id);
+                index_match(&match_obj);
+                MATCHES_STORAGE
+                    .with(|storage| storage.borrow_mut().insert(match_obj.id, match_obj));
+                *next_match_id += 1;
             }
-            None => Err("Team not found".to_string()),
         }
-    })
-}
-// Get all matches
-#[ic_cdk::query]
-fn get_all_matches() -> Result<Vec<Match>, String> {
-    MATCHES_STORAGE.with(|storage| {
-        let matches: Vec<Match> = storage
-            .borrow()
+    }
+
+    fn movement_of(
+        movements: &[DivisionMovement],
+        team_id: u64,
+    ) -> Option<(u64, u64, MovementKind)> {
+        movements
             .iter()
-            .map(|(_, matches)| matches.clone())
-            .collect();
-        if matches.is_empty() {
-            Err("No matches found".to_string())
-        } else {
-            Ok(matches)
+            .find(|movement| movement.team_id == team_id)
+            .map(|movement| {
+                (
+                    movement.from_division_id,
+                    movement.to_division_id,
+                    movement.kind,
+                )
+            })
+    }
+
+    #[test]
+    fn divisions_promote_and_relegate_from_final_standings() {
+        LEAGUES_STORAGE.with(|storage| storage.borrow_mut().insert(50, test_league(50)));
+        // Division 62 sends its winner up, its second and third to a playoff and its last down
+        save_division(&test_division(61, 1, (1, 0, 1)));
+        save_division(&test_division(62, 2, (1, 2, 1)));
+        save_division(&test_division(63, 3, (1, 0, 1)));
+
+        let split = [
+            (61, vec![1, 2, 3]),
+            (62, vec![4, 5, 6, 7]),
+            (63, vec![8, 9, 10]),
+        ];
+        let mut next_match_id = 100;
+        for (_, team_ids) in &split {
+            play_in_order(70, team_ids, &mut next_match_id);
         }
-    })
-}
+        save_season_divisions(&SeasonDivisions {
+            season_id: 70,
+            divisions: split
+                .iter()
+                .map(|(division_id, team_ids)| SeasonDivision {
+                    division_id: *division_id,
+                    team_ids: team_ids.clone(),
+                })
+                .collect(),
+            movements: Vec::new(),
+        });
+        let season = test_season(70, 50);
 
-// Get match by ID
-#[ic_cdk::query]
-fn get_match(id: u64) -> Result<Match, String> {
-    MATCHES_STORAGE.with(|storage| match storage.borrow().get(&id) {
-        Some(match_obj) => Ok(match_obj.clone()),
-        None => Err(format!("Match with ID {} not found", id)),
-    })
-}
+        let movements = compute_division_movements(&season, &[6]).unwrap();
+        assert_eq!(movements.len(), 5);
+        assert_eq!(
+            movement_of(&movements, 3),
+            Some((61, 62, MovementKind::Relegated))
+        );
+        assert_eq!(
+            movement_of(&movements, 4),
+            Some((62, 61, MovementKind::Promoted))
+        );
+        assert_eq!(
+            movement_of(&movements, 6),
+            Some((62, 61, MovementKind::PromotedViaPlayoff))
+        );
+        assert_eq!(
+            movement_of(&movements, 7),
+            Some((62, 63, MovementKind::Relegated))
+        );
+        assert_eq!(
+            movement_of(&movements, 8),
+            Some((63, 62, MovementKind::Promoted))
+        );
 
-// Get all matches for a specific team
-#[ic_cdk::query]
-fn get_matches_by_team(team_id: u64) -> Result<Vec<Match>, String> {
-    MATCHES_STORAGE.with(|storage| {
-        let matches: Vec<Match> = storage
-            .borrow()
-            .iter()
-            .filter(|(_, match_obj)| {
-                match_obj.home_team.id == team_id || match_obj.away_team.id == team_id
-            })
-            .map(|(_, match_obj)| match_obj.clone())
-            .collect();
-        if matches.is_empty() {
-            Err("No matches found for this team".to_string())
-        } else {
-            Ok(matches)
+        // The top division promotes no one and the bottom division relegates no one
+        assert_eq!(movement_of(&movements, 1), None);
+        assert_eq!(movement_of(&movements, 10), None);
+
+        // The playoff needs exactly one winner, who finished in a playoff place
+        let err = compute_division_movements(&season, &[]).unwrap_err();
+        assert!(err.contains("exactly one playoff winner"));
+        let err = compute_division_movements(&season, &[5, 9]).unwrap_err();
+        assert!(err.contains("did not finish in a playoff place"));
+    }
+
+    // Store a season of a league whose teams finish in the order given, each from the university named
+    fn finished_season(
+        season_id: u64,
+        league: League,
+        teams: &[(u64, Option<&str>)],
+        archived: bool,
+        next_match_id: &mut u64,
+    ) {
+        let team_ids: Vec<u64> = teams.iter().map(|(team_id, _)| *team_id).collect();
+        for (team_id, university) in teams {
+            let team = Team {
+                university: university.map(str::to_string),
+                sport_type: league.sport_type,
+                ..test_team(*team_id)
+            };
+            TEAMS_STORAGE.with(|storage| storage.borrow_mut().insert(team.id, team));
         }
-    })
-}
+        play_in_order(season_id, &team_ids, next_match_id);
 
-// Get all matches for a specific sport type
-#[ic_cdk::query]
-fn get_matches_by_sport_type(sport_type: SportType) -> Result<Vec<Match>, String> {
-    MATCHES_STORAGE.with(|storage| {
-        let matches: Vec<Match> = storage
-            .borrow()
+        save_season(&Season {
+            team_ids,
+            archived,
+            ..test_season(season_id, league.id)
+        });
+        LEAGUES_STORAGE.with(|storage| storage.borrow_mut().insert(league.id, league));
+    }
+
+    #[test]
+    fn championship_counts_each_universitys_best_placing() {
+        let mut next_match_id = 100;
+        finished_season(
+            70,
+            test_league(50),
+            &[
+                (1, Some("Leeds")),
+                (2, Some("Leeds")),
+                (3, Some("York")),
+                (4, None),
+            ],
+            true,
+            &mut next_match_id,
+        );
+        finished_season(
+            71,
+            League {
+                sport_type: SportType::Basketball,
+                ..test_league(51)
+            },
+            &[(5, Some("York")), (6, Some("Durham"))],
+            false,
+            &mut next_match_id,
+        );
+        save_championship(&Championship {
+            id: 80,
+            name: "Northern Universities".to_string(),
+            placing_points: vec![10, 6, 3],
+            competitions: vec![
+                ChampionshipCompetition {
+                    season_id: 70,
+                    placing_points: None,
+                },
+                ChampionshipCompetition {
+                    season_id: 71,
+                    placing_points: Some(vec![7, 3]),
+                },
+            ],
+            created_by: 1,
+            created_at: 0,
+            updated_at: None,
+        });
+
+        // Leeds' second team scores nothing, so York's team in third place takes the points for third
+        let table = get_championship_table(80, false).unwrap();
+        let rows: Vec<(u32, &str, u32)> = table
             .iter()
-            .filter(|(_, match_obj)| match_obj.sport_type == sport_type)
-            .map(|(_, match_obj)| match_obj.clone())
+            .map(|row| (row.position, row.university.as_str(), row.points))
             .collect();
-        if matches.is_empty() {
-            Err("No matches found for this sport type".to_string())
-        } else {
-            Ok(matches)
-        }
-    })
-}
+        assert_eq!(rows, vec![(1, "Leeds", 10), (2, "York", 3)]);
+        assert_eq!(table[1].placings[0].placing, 3);
 
-// Get all matches for a specific date
-#[ic_cdk::query]
-fn get_matches_by_date(date: String) -> Result<Vec<Match>, String> {
-    MATCHES_STORAGE.with(|storage| {
-        let matches: Vec<Match> = storage
-            .borrow()
+        // Provisional placings from the season under way bring York level with Leeds, sharing first place
+        let table = get_championship_table(80, true).unwrap();
+        let rows: Vec<(u32, &str, u32)> = table
             .iter()
-            .filter(|(_, match_obj)| match_obj.scheduled_date == date)
-            .map(|(_, match_obj)| match_obj.clone())
+            .map(|row| (row.position, row.university.as_str(), row.points))
             .collect();
-        if matches.is_empty() {
-            Err("No matches found for this date".to_string())
-        } else {
-            Ok(matches)
+        assert_eq!(
+            rows,
+            vec![(1, "Leeds", 10), (1, "York", 10), (3, "Durham", 3)]
+        );
+
+        let york = &table[1];
+        assert_eq!(york.by_sport.len(), 2);
+        assert_eq!(
+            york.placings
+                .iter()
+                .map(|placing| placing.provisional)
+                .collect::<Vec<_>>(),
+            vec![false, true]
+        );
+    }
+
+    #[test]
+    fn privileged_roles_need_an_administrator_or_controller() {
+        let user_with = |role| User {
+            id: 1,
+            owner: Principal::anonymous(),
+            name: "Sam".to_string(),
+            email: "sam@example.com".to_string(),
+            address: "Campus".to_string(),
+            role,
+        };
+        let admin = user_with(UserRole::Administrator);
+        let official = user_with(UserRole::LeagueOfficial);
+
+        // Anyone may take an ordinary role
+        assert!(ensure_role_grant_allowed(UserRole::Player, None, false).is_ok());
+        assert!(ensure_role_grant_allowed(UserRole::Coach, None, false).is_ok());
+
+        // Nobody may appoint themselves, and officials cannot appoint others
+        for role in [UserRole::Administrator, UserRole::LeagueOfficial] {
+            let err = ensure_role_grant_allowed(role, None, false).unwrap_err();
+            assert!(err.contains("Only an administrator"));
+            assert!(ensure_role_grant_allowed(role, Some(&official), false).is_err());
+            assert!(ensure_role_grant_allowed(role, Some(&admin), false).is_ok());
+            assert!(ensure_role_grant_allowed(role, None, true).is_ok());
         }
-    })
+    }
 }
-
-// Candid generator for exporting the Candid interface
-ic_cdk::export_candid!();
@@ -45,67 +45,1146 @@ pub struct Team {
     pub(crate) coaches: Vec<u64>,
     pub(crate) sport_type: SportType,
     pub(crate) members: Vec<u64>,
+    pub(crate) university: Option<String>,
+}
+
+// Struct representing a penalty shoot-out
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PenaltyShootout {
+    pub(crate) score_team_a: u32,
+    pub(crate) score_team_b: u32,
+}
+
+// Struct representing the extra time, and any penalty shoot-out, of a match that had to produce a winner
+// The goals scored in extra time are also counted in the match score
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ExtraTime {
+    pub(crate) score_team_a: u32,
+    pub(crate) score_team_b: u32,
+    pub(crate) penalties: Option<PenaltyShootout>,
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
 pub struct MatchResult {
-    pub(crate) winner_team_id: u64,
+    // None when the match is drawn
+    pub(crate) winner_team_id: Option<u64>,
     pub(crate) score_team_a: u32,
     pub(crate) score_team_b: u32,
     pub(crate) notes: String,
+    pub(crate) extra_time: Option<ExtraTime>,
+    pub(crate) forfeited_by: Option<u64>,
+}
+
+// Struct representing a Match
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Match {
+    pub(crate) id: u64,
+    pub(crate) home_team: Team,
+    pub(crate) away_team: Team,
+    pub(crate) sport_type: SportType,
+    pub(crate) scheduled_date: String,
+    pub(crate) kickoff_time: Option<String>,
+    pub(crate) result: Option<MatchResult>,
+    pub(crate) tournament_id: Option<u64>,
+    pub(crate) round: Option<u32>,
+    pub(crate) league_id: Option<u64>,
+    pub(crate) season_id: Option<u64>,
+    pub(crate) stage: Option<MatchStage>,
+    pub(crate) leg: Option<u32>,
+}
+
+// The stage of a competition a match is played in; matches without a stage are friendlies
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MatchStage {
+    RoundRobin,
+    Group,
+    Knockout,
+    ThirdPlace,
+    Final,
+    WinnersBracket,
+    LosersBracket,
+    GrandFinal,
+    Swiss,
+}
+
+// Key of the match index, ordered by competition, then round, then match
+// The competition is the ID of a league, season or tournament; matches without a round use round 0
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct MatchIndexKey {
+    pub(crate) competition_id: u64,
+    pub(crate) round: u32,
+    pub(crate) match_id: u64,
+}
+
+// Card Type Enum, covering the sanctions shown to players across sports
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum CardType {
+    #[default]
+    Yellow,
+    Red,
+    SinBin,
+    TechnicalFoul,
+}
+
+// Struct representing a card shown to a player in a Match
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Card {
+    pub(crate) id: u64,
+    pub(crate) match_id: u64,
+    pub(crate) player_id: u64,
+    pub(crate) team_id: u64,
+    pub(crate) sport_type: SportType,
+    pub(crate) season: String,
+    pub(crate) season_id: Option<u64>,
+    pub(crate) card_type: CardType,
+    pub(crate) minute: Option<u32>,
+    pub(crate) offence: String,
+    pub(crate) issued_by: u64,
+    pub(crate) issued_at: u64,
+}
+
+// Struct representing a rule turning accumulated cards into a suspension
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DisciplinaryRule {
+    pub(crate) id: u64,
+    pub(crate) sport_type: SportType,
+    pub(crate) card_type: CardType,
+    pub(crate) threshold: u32,
+    pub(crate) suspension_matches: u32,
+    pub(crate) updated_by: u64,
+    pub(crate) updated_at: u64,
+}
+
+// Struct representing a player's suspension from their team's matches in a sport
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Suspension {
+    pub(crate) id: u64,
+    pub(crate) player_id: u64,
+    pub(crate) team_id: u64,
+    pub(crate) sport_type: SportType,
+    pub(crate) reason: String,
+    pub(crate) source_match_id: u64,
+    pub(crate) card_ids: Vec<u64>,
+    pub(crate) matches_total: u32,
+    pub(crate) served_match_ids: Vec<u64>,
+    pub(crate) created_at: u64,
+}
+
+// Struct representing the players a Team fields in a Match
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Lineup {
+    pub(crate) id: u64,
+    pub(crate) match_id: u64,
+    pub(crate) team_id: u64,
+    pub(crate) starters: Vec<u64>,
+    pub(crate) substitutes: Vec<u64>,
+    pub(crate) submitted_by: u64,
+    pub(crate) submitted_at: u64,
+}
+
+// Struct representing a disciplinary action recorded in a match report
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DisciplinaryAction {
+    pub(crate) player_id: u64,
+    pub(crate) team_id: u64,
+    pub(crate) minute: Option<u32>,
+    pub(crate) offence: String,
+    pub(crate) card: CardType,
+}
+
+// Struct representing an injury recorded in a match report
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct InjuryReport {
+    pub(crate) player_id: u64,
+    pub(crate) team_id: u64,
+    pub(crate) minute: Option<u32>,
+    pub(crate) description: String,
+}
+
+// Struct representing an incident recorded in a match report
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct IncidentReport {
+    pub(crate) minute: Option<u32>,
+    pub(crate) description: String,
+}
+
+// Struct representing the referee's report on a Match, authoritative once signed
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct MatchReport {
+    pub(crate) match_id: u64,
+    pub(crate) referee_id: u64,
+    pub(crate) home_score: u32,
+    pub(crate) away_score: u32,
+    pub(crate) extra_time: Option<ExtraTime>,
+    pub(crate) disciplinary_actions: Vec<DisciplinaryAction>,
+    pub(crate) injuries: Vec<InjuryReport>,
+    pub(crate) incidents: Vec<IncidentReport>,
+    pub(crate) attendance: u32,
+    pub(crate) notes: String,
+    pub(crate) filed_at: u64,
+    pub(crate) signed_at: Option<u64>,
+    pub(crate) superseded_result: Option<MatchResult>,
+}
+
+// Struct representing a Referee
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Referee {
+    pub(crate) id: u64,
+    pub(crate) owner: Principal,
+    pub(crate) name: String,
+    pub(crate) email: String,
+    pub(crate) address: String,
+    pub(crate) university: Option<String>,
+    pub(crate) conflicts_of_interest: Vec<u64>,
+    pub(crate) certifications: Vec<Certification>,
+    pub(crate) performance_rating: f32,
+    pub(crate) total_rating: f32,
+    pub(crate) total_ratings: u32,
+    pub(crate) total_matches: u32,
+}
+
+// Certification Grade Enum, ordered from lowest to highest
+#[derive(
+    CandidType,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Default,
+    Debug,
+)]
+pub enum CertificationGrade {
+    #[default]
+    Trainee,
+    Regional,
+    National,
+    International,
+}
+
+// Struct representing a Referee's certification to officiate a sport
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Certification {
+    pub(crate) id: u64,
+    pub(crate) sport_type: SportType,
+    pub(crate) grade: CertificationGrade,
+    pub(crate) issuing_body: String,
+    pub(crate) expiry_date: String,
+    pub(crate) granted_by: u64,
+    pub(crate) granted_at: u64,
+    pub(crate) revoked_at: Option<u64>,
+    pub(crate) revocation_reason: Option<String>,
+}
+
+// Key of the certification history, ordered by referee and then by certification
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct CertificationKey {
+    pub(crate) referee_id: u64,
+    pub(crate) certification_id: u64,
+}
+
+// Struct representing a reminder about a certification nearing expiry
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CertificationReminder {
+    pub(crate) referee_id: u64,
+    pub(crate) referee_name: String,
+    pub(crate) email: String,
+    pub(crate) certification: Certification,
+    pub(crate) days_remaining: i64,
+}
+
+// Availability Kind Enum
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum AvailabilityKind {
+    #[default]
+    Available,
+    Unavailable,
+}
+
+// Weekday Enum
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum Weekday {
+    #[default]
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+// Struct representing a one-off range an official is available or unavailable in
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AvailabilityWindow {
+    pub(crate) id: u64,
+    pub(crate) kind: AvailabilityKind,
+    pub(crate) start: String,
+    pub(crate) end: String,
+    pub(crate) note: String,
+}
+
+// Struct representing a weekly time range an official is unavailable in
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RecurringUnavailability {
+    pub(crate) id: u64,
+    pub(crate) weekday: Weekday,
+    pub(crate) start_time: String,
+    pub(crate) end_time: String,
+    pub(crate) from_date: Option<String>,
+    pub(crate) until_date: Option<String>,
+    pub(crate) note: String,
+}
+
+// Struct representing an official's availability calendar
+#[derive(CandidType, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct OfficialAvailability {
+    pub(crate) referee_id: u64,
+    pub(crate) windows: Vec<AvailabilityWindow>,
+    pub(crate) recurring: Vec<RecurringUnavailability>,
+}
+
+// Official Role Enum
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum OfficialRole {
+    #[default]
+    Referee,
+    Assistant,
+}
+
+// Struct representing a Referee's assignment to a Match
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RefereeAssignment {
+    pub(crate) id: u64,
+    pub(crate) match_id: u64,
+    pub(crate) referee_id: u64,
+    pub(crate) role: OfficialRole,
+    pub(crate) assigned_at: u64,
+}
+
+// Match Outcome Enum
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum MatchOutcome {
+    #[default]
+    HomeWin,
+    AwayWin,
+    Draw,
+}
+
+// Key of the officiating history, ordered by referee and then by match
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct OfficiatingKey {
+    pub(crate) referee_id: u64,
+    pub(crate) match_id: u64,
+}
+
+// Struct representing a Match in a Referee's officiating history
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct OfficiatingRecord {
+    pub(crate) referee_id: u64,
+    pub(crate) match_id: u64,
+    pub(crate) role: OfficialRole,
+    pub(crate) sport_type: SportType,
+    pub(crate) scheduled_date: String,
+    pub(crate) home_team_id: u64,
+    pub(crate) away_team_id: u64,
+    pub(crate) home_score: u32,
+    pub(crate) away_score: u32,
+    pub(crate) outcome: MatchOutcome,
+    pub(crate) recorded_at: u64,
+}
+
+// Struct representing a page of a Referee's officiating history
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct OfficiatingHistoryPage {
+    pub(crate) records: Vec<OfficiatingRecord>,
+    pub(crate) next_cursor: Option<u64>,
+}
+
+// Legacy view of a stored Referee, used to migrate the untyped `matches_officiated` list
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LegacyRefereeHistory {
+    pub(crate) id: u64,
+    pub(crate) matches_officiated: Option<Vec<String>>,
+}
+
+// Legacy view of a disciplinary action, used to migrate reports filed with a free-text `sanction` instead of a card
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LegacyDisciplinaryAction {
+    pub(crate) player_id: u64,
+    pub(crate) team_id: u64,
+    pub(crate) minute: Option<u32>,
+    pub(crate) offence: String,
+    pub(crate) sanction: Option<String>,
+    pub(crate) card: Option<CardType>,
+}
+
+// Legacy view of a stored MatchReport, used to migrate its disciplinary actions
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LegacyMatchReport {
+    pub(crate) match_id: u64,
+    pub(crate) referee_id: u64,
+    pub(crate) home_score: u32,
+    pub(crate) away_score: u32,
+    pub(crate) extra_time: Option<ExtraTime>,
+    pub(crate) disciplinary_actions: Vec<LegacyDisciplinaryAction>,
+    pub(crate) injuries: Vec<InjuryReport>,
+    pub(crate) incidents: Vec<IncidentReport>,
+    pub(crate) attendance: u32,
+    pub(crate) notes: String,
+    pub(crate) filed_at: u64,
+    pub(crate) signed_at: Option<u64>,
+    pub(crate) superseded_result: Option<MatchResult>,
+}
+
+// Payment Status Enum
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum PaymentStatus {
+    #[default]
+    Owed,
+    Approved,
+    Paid,
+    Failed,
+}
+
+// Payment Backend Enum
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PaymentBackendKind {
+    // Officials are paid by the university's finance office and each payout is recorded here with a manual reference
+    Manual,
+    // Staging payouts that move no funds, completing each payment up to the limit and declining larger ones
+    Sandbox { payout_limit: u64 },
+}
+
+// Struct representing the payment backend chosen by an administrator, reinstalled after every upgrade
+#[derive(CandidType, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct PaymentSettings {
+    pub(crate) backend: Option<PaymentBackendKind>,
+    pub(crate) set_by: Option<u64>,
+    pub(crate) set_at: Option<u64>,
+}
+
+// Payment Kind Enum
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum PaymentKind {
+    #[default]
+    MatchFee,
+    TravelAllowance,
+}
+
+// Struct representing the fee and travel allowance paid to an official role in a sport, in the smallest currency unit
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct OfficialFeeRate {
+    pub(crate) id: u64,
+    pub(crate) sport_type: SportType,
+    pub(crate) role: OfficialRole,
+    pub(crate) match_fee: u64,
+    pub(crate) travel_allowance: u64,
+}
+
+// Struct representing an amount owed to an official for a completed assignment
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PaymentEntry {
+    pub(crate) id: u64,
+    pub(crate) referee_id: u64,
+    pub(crate) match_id: u64,
+    pub(crate) assignment_id: u64,
+    pub(crate) kind: PaymentKind,
+    pub(crate) amount: u64,
+    pub(crate) billed_university: Option<String>,
+    pub(crate) status: PaymentStatus,
+    pub(crate) created_at: u64,
+    pub(crate) approved_by: Option<u64>,
+    pub(crate) approved_at: Option<u64>,
+    pub(crate) paid_at: Option<u64>,
+    pub(crate) payment_reference: Option<String>,
+    pub(crate) failure_reason: Option<String>,
+}
+
+// Struct representing the amounts billed to a university, by payment status
+#[derive(CandidType, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct UniversityBilling {
+    pub(crate) university: Option<String>,
+    pub(crate) owed: u64,
+    pub(crate) approved: u64,
+    pub(crate) paid: u64,
+    pub(crate) failed: u64,
+    pub(crate) entries: Vec<PaymentEntry>,
+}
+
+// Struct representing a Referee assignment proposed by the allocator
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ProposedAssignment {
+    pub(crate) match_id: u64,
+    pub(crate) referee_id: u64,
+    pub(crate) referee_name: String,
+    pub(crate) role: OfficialRole,
+    pub(crate) scheduled_date: String,
+}
+
+// Struct representing an official's slot the allocator could not fill
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct UnfilledSlot {
+    pub(crate) match_id: u64,
+    pub(crate) role: OfficialRole,
+    pub(crate) reason: String,
+}
+
+// Struct representing the outcome of an allocation run
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RefereeAllocation {
+    pub(crate) committed: bool,
+    pub(crate) assignments: Vec<ProposedAssignment>,
+    pub(crate) unfilled: Vec<UnfilledSlot>,
+}
+
+// Struct representing a rating of a Referee's performance in a Match
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RefereeRating {
+    pub(crate) id: u64,
+    pub(crate) match_id: u64,
+    pub(crate) referee_id: u64,
+    pub(crate) rater_id: u64,
+    pub(crate) rater_role: UserRole,
+    pub(crate) sport_type: SportType,
+    pub(crate) season: String,
+    pub(crate) score: u8,
+    pub(crate) comment: String,
+    pub(crate) submitted_at: u64,
+}
+
+// Aggregated ratings over a set of RefereeRatings
+#[derive(CandidType, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct RatingAggregate {
+    pub(crate) rating_count: u32,
+    pub(crate) total_rating: f32,
+    pub(crate) average_rating: f32,
+}
+
+// Ratings aggregated for a single sport
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SportRatingAggregate {
+    pub(crate) sport_type: SportType,
+    pub(crate) aggregate: RatingAggregate,
+}
+
+// Ratings aggregated for a single season
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SeasonRatingAggregate {
+    pub(crate) season: String,
+    pub(crate) aggregate: RatingAggregate,
+}
+
+// Struct summarising a Referee's ratings overall, per sport and per season
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RefereeRatingSummary {
+    pub(crate) referee_id: u64,
+    pub(crate) referee_name: String,
+    pub(crate) overall: RatingAggregate,
+    pub(crate) by_sport: Vec<SportRatingAggregate>,
+    pub(crate) by_season: Vec<SeasonRatingAggregate>,
+}
+
+// Struct representing a row of the officials leaderboard
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RefereeLeaderboardEntry {
+    pub(crate) rank: u32,
+    pub(crate) referee_id: u64,
+    pub(crate) referee_name: String,
+    pub(crate) aggregate: RatingAggregate,
+    pub(crate) total_matches: u32,
+}
+
+// Tournament Structure Enum
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum TournamentStructure {
+    #[default]
+    RoundRobin,
+    Knockout,
+    GroupsThenKnockout,
+    DoubleElimination,
+    Swiss,
+}
+
+// Tournament Status Enum
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum TournamentStatus {
+    #[default]
+    Planned,
+    InProgress,
+    Completed,
+}
+
+// Tiebreaker Enum, applied in order to teams level on points
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Tiebreaker {
+    HeadToHead,
+    GoalDifference,
+    GoalsScored,
+    FairPlay,
+    DrawingOfLots,
+}
+
+// Struct representing how a standings table awards points and separates teams level on points
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct StandingsRules {
+    pub(crate) points_for_win: u32,
+    pub(crate) points_for_draw: u32,
+    pub(crate) points_for_loss: u32,
+    pub(crate) tiebreakers: Vec<Tiebreaker>,
+    pub(crate) points_for_forfeit: Option<u32>,
+}
+
+impl Default for StandingsRules {
+    fn default() -> Self {
+        StandingsRules {
+            points_for_win: 3,
+            points_for_draw: 1,
+            points_for_loss: 0,
+            tiebreakers: vec![
+                Tiebreaker::HeadToHead,
+                Tiebreaker::GoalDifference,
+                Tiebreaker::GoalsScored,
+                Tiebreaker::FairPlay,
+                Tiebreaker::DrawingOfLots,
+            ],
+            points_for_forfeit: None,
+        }
+    }
+}
+
+// Struct representing a Tournament
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Tournament {
+    pub(crate) id: u64,
+    pub(crate) name: String,
+    pub(crate) structure: TournamentStructure,
+    pub(crate) team_ids: Vec<u64>,
+    pub(crate) sport_type: SportType,
+    pub(crate) created_by: u64,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: Option<u64>,
+    pub(crate) status: TournamentStatus,
+    pub(crate) champion_team_id: Option<u64>,
+    pub(crate) standings_rules: StandingsRules,
+}
+
+// A value as stored in a storage created with the original 1024-byte bound, used to move it to a larger storage
+#[derive(Clone, Debug)]
+pub struct LegacyStored<T>(pub(crate) T);
+
+// Struct representing one round of a tournament's fixtures
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct FixtureRound {
+    pub(crate) round: u32,
+    pub(crate) scheduled_date: String,
+    pub(crate) match_ids: Vec<u64>,
+    pub(crate) bye_team_ids: Vec<u64>,
+}
+
+// Struct representing one tie of a knockout bracket
+// Nodes are numbered as a binary tree: node 1 is the final and node n is fed by nodes 2n and 2n + 1
+// Node 0 is the third-place match, played by the losing semi-finalists
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BracketNode {
+    pub(crate) id: u32,
+    pub(crate) round: u32,
+    pub(crate) home_team_id: Option<u64>,
+    pub(crate) away_team_id: Option<u64>,
+    pub(crate) home_seed: Option<u32>,
+    pub(crate) away_seed: Option<u32>,
+    pub(crate) match_id: Option<u64>,
+    pub(crate) winner_team_id: Option<u64>,
+    pub(crate) next_node_id: Option<u32>,
+    pub(crate) tie_id: Option<u64>,
+}
+
+// Struct representing the knockout bracket of a tournament
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Bracket {
+    pub(crate) tournament_id: u64,
+    pub(crate) rounds: u32,
+    pub(crate) seeded_team_ids: Vec<u64>,
+    pub(crate) round_dates: Vec<String>,
+    pub(crate) kickoff_time: Option<String>,
+    pub(crate) nodes: Vec<BracketNode>,
+    pub(crate) third_place: Option<BracketNode>,
+    pub(crate) round_offset: u32,
+    pub(crate) two_legged: Option<TwoLeggedRules>,
+}
+
+// Struct representing one team in a slot of an exported bracket
+// The score is the aggregate score for a two-legged tie
+#[derive(Serialize, Clone, Debug)]
+pub struct BracketSlotTeam {
+    pub(crate) team_id: u64,
+    pub(crate) name: String,
+    pub(crate) seed: Option<u32>,
+    pub(crate) score: Option<u32>,
+    pub(crate) winner: bool,
+}
+
+// Struct representing one slot of an exported bracket, with a missing team shown as a bye or still to be decided
+#[derive(Serialize, Clone, Debug)]
+pub struct BracketSlot {
+    pub(crate) node_id: u32,
+    pub(crate) match_id: Option<u64>,
+    pub(crate) tie_id: Option<u64>,
+    pub(crate) scheduled_date: Option<String>,
+    pub(crate) home: Option<BracketSlotTeam>,
+    pub(crate) away: Option<BracketSlotTeam>,
+    pub(crate) decided_by: Option<TieDecision>,
+    pub(crate) next_node_id: Option<u32>,
+}
+
+// Struct representing one round of an exported bracket
+#[derive(Serialize, Clone, Debug)]
+pub struct BracketRound {
+    pub(crate) round: u32,
+    pub(crate) name: String,
+    pub(crate) slots: Vec<BracketSlot>,
+}
+
+// Struct representing a knockout bracket exported as a tree of rounds and slots
+#[derive(Serialize, Clone, Debug)]
+pub struct BracketTree {
+    pub(crate) tournament_id: u64,
+    pub(crate) tournament_name: String,
+    pub(crate) rounds: Vec<BracketRound>,
+    pub(crate) third_place: Option<BracketSlot>,
+    pub(crate) champion_team_id: Option<u64>,
+}
+
+// Struct representing how the two-legged rounds of a bracket are played
+// There is one second-leg date per two-legged round; the third-place match is always played over one leg
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct TwoLeggedRules {
+    pub(crate) second_leg_dates: Vec<String>,
+    pub(crate) away_goals: bool,
+    pub(crate) single_leg_final: bool,
+}
+
+// How a two-legged tie was decided
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TieDecision {
+    Aggregate,
+    AwayGoals,
+    ExtraTime,
+    Penalties,
+}
+
+// Struct representing a tie played over two legs, with each team at home once
+// Team A is at home in the first leg and team B in the second
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct TwoLeggedTie {
+    pub(crate) id: u64,
+    pub(crate) tournament_id: u64,
+    pub(crate) team_a_id: u64,
+    pub(crate) team_b_id: u64,
+    pub(crate) first_leg_match_id: u64,
+    pub(crate) second_leg_match_id: u64,
+    pub(crate) away_goals: bool,
+    pub(crate) aggregate_team_a: u32,
+    pub(crate) aggregate_team_b: u32,
+    pub(crate) winner_team_id: Option<u64>,
+    pub(crate) decided_by: Option<TieDecision>,
+}
+
+// Struct representing one team's row in a standings table
+#[derive(CandidType, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct StandingsEntry {
+    pub(crate) position: u32,
+    pub(crate) team_id: u64,
+    pub(crate) played: u32,
+    pub(crate) won: u32,
+    pub(crate) drawn: u32,
+    pub(crate) lost: u32,
+    pub(crate) goals_for: u32,
+    pub(crate) goals_against: u32,
+    pub(crate) goal_difference: i64,
+    pub(crate) fair_play_points: u32,
+    pub(crate) points: u32,
+}
+
+// Struct representing a group of a group stage
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct TournamentGroup {
+    pub(crate) name: String,
+    pub(crate) team_ids: Vec<u64>,
+}
+
+// Struct representing a group's standings table
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct GroupTable {
+    pub(crate) name: String,
+    pub(crate) standings: Vec<StandingsEntry>,
+}
+
+// Struct representing the group stage of a tournament and how its knockout stage is played
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct GroupStage {
+    pub(crate) tournament_id: u64,
+    pub(crate) groups: Vec<TournamentGroup>,
+    pub(crate) rounds: u32,
+    pub(crate) qualifiers_per_group: u32,
+    pub(crate) knockout_round_dates: Vec<String>,
+    pub(crate) kickoff_time: Option<String>,
+    pub(crate) third_place_match: bool,
+}
+
+// Struct representing a conference of universities whose teams are kept apart in a group draw
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DrawConference {
+    pub(crate) name: String,
+    pub(crate) universities: Vec<String>,
+}
+
+// Struct representing the constraints of a group draw
+#[derive(CandidType, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct DrawConstraints {
+    pub(crate) separate_universities: bool,
+    pub(crate) conferences: Vec<DrawConference>,
+}
+
+// Struct representing a team in a group draw, with the university its constraints were checked against
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DrawEntrant {
+    pub(crate) team_id: u64,
+    pub(crate) pot: u32,
+    pub(crate) university: Option<String>,
+}
+
+// Struct representing the group draw of a tournament
+// The seed is the hex-encoded randomness the draw was made with, so anyone can repeat it from the recorded entrants
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct GroupDraw {
+    pub(crate) tournament_id: u64,
+    pub(crate) group_count: u32,
+    pub(crate) entrants: Vec<DrawEntrant>,
+    pub(crate) constraints: DrawConstraints,
+    pub(crate) seed: String,
+    pub(crate) draw_order: Vec<u64>,
+    pub(crate) groups: Vec<TournamentGroup>,
+    pub(crate) drawn_by: u64,
+    pub(crate) drawn_at: u64,
+}
+
+// Where a team in a double-elimination match comes from
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SlotSource {
+    Seed(u32),
+    WinnerOf(u32),
+    LoserOf(u32),
+}
+
+// Double Elimination Side Enum
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum EliminationSide {
+    #[default]
+    Winners,
+    Losers,
+    GrandFinal,
+    GrandFinalReset,
+}
+
+// Struct representing one match of a double-elimination bracket
+// A node is decided once it has a winner, or once it turns out to be a bye or not needed
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct EliminationNode {
+    pub(crate) id: u32,
+    pub(crate) side: EliminationSide,
+    pub(crate) round: u32,
+    pub(crate) home_source: SlotSource,
+    pub(crate) away_source: SlotSource,
+    pub(crate) home_team_id: Option<u64>,
+    pub(crate) away_team_id: Option<u64>,
+    pub(crate) match_id: Option<u64>,
+    pub(crate) winner_team_id: Option<u64>,
+    pub(crate) loser_team_id: Option<u64>,
+    pub(crate) decided: bool,
+}
+
+// Struct representing the double-elimination bracket of a tournament
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DoubleEliminationBracket {
+    pub(crate) tournament_id: u64,
+    pub(crate) seeded_team_ids: Vec<u64>,
+    pub(crate) round_dates: Vec<String>,
+    pub(crate) kickoff_time: Option<String>,
+    pub(crate) nodes: Vec<EliminationNode>,
+}
+
+// Struct representing a team sitting out a Swiss round
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SwissBye {
+    pub(crate) round: u32,
+    pub(crate) team_id: u64,
 }
 
-// Struct representing a Match
+// Struct representing the progress of a Swiss-system tournament
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
-pub struct Match {
+pub struct SwissTournament {
+    pub(crate) tournament_id: u64,
+    pub(crate) seeded_team_ids: Vec<u64>,
+    pub(crate) total_rounds: u32,
+    pub(crate) current_round: u32,
+    pub(crate) round_dates: Vec<String>,
+    pub(crate) kickoff_time: Option<String>,
+    pub(crate) byes: Vec<SwissBye>,
+}
+
+// Struct representing a team's standing in a Swiss-system tournament
+#[derive(CandidType, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct SwissStanding {
+    pub(crate) team_id: u64,
+    pub(crate) played: u32,
+    pub(crate) won: u32,
+    pub(crate) drawn: u32,
+    pub(crate) lost: u32,
+    pub(crate) byes: u32,
+    pub(crate) points: u32,
+    pub(crate) buchholz: u32,
+}
+
+// Struct representing the rules a team must meet to enter a tournament
+// An empty list of universities allows teams from any university
+#[derive(CandidType, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct EntryEligibility {
+    pub(crate) allowed_universities: Vec<String>,
+    pub(crate) minimum_members: u32,
+    pub(crate) require_coach: bool,
+}
+
+// Struct representing a tournament's entry period, capacity and eligibility rules
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct TournamentRegistration {
+    pub(crate) tournament_id: u64,
+    pub(crate) opens_on: String,
+    pub(crate) closes_on: String,
+    pub(crate) capacity: u32,
+    pub(crate) eligibility: EntryEligibility,
+    pub(crate) locked: bool,
+    pub(crate) locked_at: Option<u64>,
+}
+
+// Entry Status Enum
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum EntryStatus {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+    Waitlisted,
+    Withdrawn,
+}
+
+// Struct representing a team's application to enter a tournament
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct TournamentEntry {
     pub(crate) id: u64,
-    pub(crate) home_team: Team,
-    pub(crate) away_team: Team,
+    pub(crate) tournament_id: u64,
+    pub(crate) team_id: u64,
+    pub(crate) applied_by: u64,
+    pub(crate) applied_at: u64,
+    pub(crate) late: bool,
+    pub(crate) status: EntryStatus,
+    pub(crate) reviewed_by: Option<u64>,
+    pub(crate) reviewed_at: Option<u64>,
+    pub(crate) rejection_reason: Option<String>,
+}
+
+// Struct representing a League
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct League {
+    pub(crate) id: u64,
+    pub(crate) name: String,
     pub(crate) sport_type: SportType,
-    pub(crate) scheduled_date: String,
-    pub(crate) result: Option<MatchResult>,
+    pub(crate) team_ids: Vec<u64>,
+    pub(crate) tournament_ids: Vec<u64>,
+    pub(crate) created_by: u64,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: Option<u64>,
+    pub(crate) standings_rules: StandingsRules,
 }
 
-// Struct representing a Referee
+// Struct representing a named period of a season, such as a transfer window or an exam blackout
+// Dates are inclusive and in the format YYYY-MM-DD
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
-pub struct Referee {
+pub struct SeasonPeriod {
+    pub(crate) name: String,
+    pub(crate) start_date: String,
+    pub(crate) end_date: String,
+}
+
+// Struct representing one season of a league
+// Teams register for each season, and the matches of the season's tournaments are scoped to it.
+// An archived season is kept read-only.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Season {
     pub(crate) id: u64,
-    pub(crate) owner: Principal,
+    pub(crate) league_id: u64,
     pub(crate) name: String,
-    pub(crate) email: String,
-    pub(crate) address: String,
-    pub(crate) matches_officiated: Vec<String>,
-    pub(crate) performance_rating: f32,
-    pub(crate) total_rating: f32,
-    pub(crate) total_matches: u32,
+    pub(crate) start_date: String,
+    pub(crate) end_date: String,
+    pub(crate) registration_window: SeasonPeriod,
+    pub(crate) transfer_windows: Vec<SeasonPeriod>,
+    pub(crate) exam_blackouts: Vec<SeasonPeriod>,
+    pub(crate) team_ids: Vec<u64>,
+    pub(crate) tournament_ids: Vec<u64>,
+    pub(crate) created_by: u64,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: Option<u64>,
+    pub(crate) archived: bool,
+    pub(crate) archived_at: Option<u64>,
 }
 
-// Tournament Structure Enum
-#[derive(CandidType, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
-pub enum TournamentStructure {
-    #[default]
-    RoundRobin,
-    Knockout,
+// Struct representing one tier of a multi-division league
+// Level 1 is the top division. The top division promotes no one and the bottom division relegates no one.
+// Teams finishing just below the promotion spots contest a playoff for one more promotion place.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Division {
+    pub(crate) id: u64,
+    pub(crate) league_id: u64,
+    pub(crate) name: String,
+    pub(crate) level: u32,
+    pub(crate) promotion_spots: u32,
+    pub(crate) playoff_spots: u32,
+    pub(crate) relegation_spots: u32,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: Option<u64>,
 }
 
-// Struct representing a Tournament
+// Struct representing the teams playing in a division during a season
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
-pub struct Tournament {
+pub struct SeasonDivision {
+    pub(crate) division_id: u64,
+    pub(crate) team_ids: Vec<u64>,
+}
+
+// Enum representing how a team moved between divisions at the end of a season
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum MovementKind {
+    Promoted,
+    PromotedViaPlayoff,
+    Relegated,
+}
+
+// Struct representing a team's move between divisions at the end of a season
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DivisionMovement {
+    pub(crate) team_id: u64,
+    pub(crate) from_division_id: u64,
+    pub(crate) to_division_id: u64,
+    pub(crate) position: u32,
+    pub(crate) kind: MovementKind,
+}
+
+// Struct representing how a season's teams are split into divisions, and the moves made when it ended
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SeasonDivisions {
+    pub(crate) season_id: u64,
+    pub(crate) divisions: Vec<SeasonDivision>,
+    pub(crate) movements: Vec<DivisionMovement>,
+}
+
+// Struct representing the squad limits of a league's teams; an absent limit leaves that size unrestricted
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RosterLimits {
+    pub(crate) max_squad_size: Option<u32>,
+    pub(crate) min_starters: u32,
+    pub(crate) max_starters: Option<u32>,
+    pub(crate) max_substitutes: Option<u32>,
+}
+
+// Struct representing how many cards of a type suspend a player in a league, and for how many matches
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DisciplinaryThreshold {
+    pub(crate) card_type: CardType,
+    pub(crate) threshold: u32,
+    pub(crate) suspension_matches: u32,
+}
+
+// Struct representing one version of a league's rules, either league-wide or for one of its seasons
+// Saving rules adds a new version; earlier versions are kept for reference.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LeagueRules {
+    pub(crate) id: u64,
+    pub(crate) league_id: u64,
+    pub(crate) season_id: Option<u64>,
+    pub(crate) version: u32,
+    pub(crate) standings: StandingsRules,
+    pub(crate) roster: RosterLimits,
+    pub(crate) eligibility: EntryEligibility,
+    pub(crate) disciplinary_thresholds: Vec<DisciplinaryThreshold>,
+    pub(crate) result_confirmation_hours: Option<u32>,
+    pub(crate) set_by: u64,
+    pub(crate) set_at: u64,
+}
+
+// Struct representing a league official's appointment to a league, a sport, or a league in a sport
+// A revoked appointment is kept as part of the official's appointment history.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct OfficialAppointment {
+    pub(crate) id: u64,
+    pub(crate) user_id: u64,
+    pub(crate) league_id: Option<u64>,
+    pub(crate) sport_type: Option<SportType>,
+    pub(crate) appointed_by: u64,
+    pub(crate) appointed_at: u64,
+    pub(crate) revoked_by: Option<u64>,
+    pub(crate) revoked_at: Option<u64>,
+}
+
+// Struct representing a season counted towards an overall championship, with its own placing points if they differ
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ChampionshipCompetition {
+    pub(crate) season_id: u64,
+    pub(crate) placing_points: Option<Vec<u32>>,
+}
+
+// Struct representing an overall inter-university championship across the sports of one academic season
+// Universities score placing points for their best-placed team in each counted league season.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Championship {
     pub(crate) id: u64,
     pub(crate) name: String,
-    pub(crate) structure: TournamentStructure,
-    pub(crate) teams: Vec<String>,
+    pub(crate) placing_points: Vec<u32>,
+    pub(crate) competitions: Vec<ChampionshipCompetition>,
+    pub(crate) created_by: u64,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: Option<u64>,
+}
+
+// Struct representing the points a university scored from one placing
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ChampionshipPlacing {
+    pub(crate) season_id: u64,
     pub(crate) sport_type: SportType,
+    pub(crate) team_id: u64,
+    pub(crate) placing: u32,
+    pub(crate) points: u32,
+    pub(crate) provisional: bool,
 }
 
-// Struct representing a League
+// Struct representing a university's points in one sport
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
-pub struct League {
-    pub(crate) id: String,
-    pub(crate) name: String,
-    pub(crate) tournaments: Vec<Tournament>,
+pub struct SportPoints {
     pub(crate) sport_type: SportType,
-    pub(crate) created_by: Principal,
+    pub(crate) points: u32,
+}
+
+// Struct representing a university's row in an overall championship table
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ChampionshipStanding {
+    pub(crate) position: u32,
+    pub(crate) university: String,
+    pub(crate) points: u32,
+    pub(crate) by_sport: Vec<SportPoints>,
+    pub(crate) placings: Vec<ChampionshipPlacing>,
 }
 
 // Payloads
@@ -134,6 +1213,7 @@ pub struct UpdateUserPayload {
 pub struct CreateTeamPayload {
     pub(crate) name: String,
     pub(crate) sport_type: SportType,
+    pub(crate) university: Option<String>,
 }
 
 // Payload for adding a member to a team
@@ -157,6 +1237,7 @@ pub struct ScheduleMatchPayload {
     pub(crate) away_team_id: u64,
     pub(crate) sport_type: SportType,
     pub(crate) scheduled_date: String,
+    pub(crate) kickoff_time: Option<String>,
 }
 
 // Match Result Payload
@@ -166,13 +1247,294 @@ pub struct MatchResultPayload {
     pub(crate) result: MatchResult,
 }
 
+// Register Referee Payload
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RegisterRefereePayload {
+    pub(crate) name: String,
+    pub(crate) email: String,
+    pub(crate) address: String,
+    pub(crate) university: Option<String>,
+}
+
+// Payload for declaring a Referee's conflict of interest with a Team
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct DeclareConflictPayload {
+    pub(crate) referee_id: u64,
+    pub(crate) team_id: u64,
+}
+
+// Payload for assigning a Referee to a Match
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AssignRefereePayload {
+    pub(crate) match_id: u64,
+    pub(crate) referee_id: u64,
+    pub(crate) role: OfficialRole,
+}
+
+// Payload for adding a one-off availability range to an official's calendar
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AddAvailabilityWindowPayload {
+    pub(crate) referee_id: u64,
+    pub(crate) kind: AvailabilityKind,
+    pub(crate) start: String,
+    pub(crate) end: String,
+    pub(crate) note: String,
+}
+
+// Payload for adding weekly unavailability to an official's calendar
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AddRecurringUnavailabilityPayload {
+    pub(crate) referee_id: u64,
+    pub(crate) weekday: Weekday,
+    pub(crate) start_time: String,
+    pub(crate) end_time: String,
+    pub(crate) from_date: Option<String>,
+    pub(crate) until_date: Option<String>,
+    pub(crate) note: String,
+}
+
+// Payload for removing an entry from an official's calendar
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RemoveAvailabilityEntryPayload {
+    pub(crate) referee_id: u64,
+    pub(crate) entry_id: u64,
+}
+
+// Query for the officials free in a match slot
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct FreeOfficialsQuery {
+    pub(crate) date: String,
+    pub(crate) kickoff_time: Option<String>,
+    pub(crate) sport_type: Option<SportType>,
+}
+
+// Payload for filing or amending a referee's match report
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct FileMatchReportPayload {
+    pub(crate) match_id: u64,
+    pub(crate) home_score: u32,
+    pub(crate) away_score: u32,
+    pub(crate) extra_time: Option<ExtraTime>,
+    pub(crate) disciplinary_actions: Vec<DisciplinaryAction>,
+    pub(crate) injuries: Vec<InjuryReport>,
+    pub(crate) incidents: Vec<IncidentReport>,
+    pub(crate) attendance: u32,
+    pub(crate) notes: String,
+}
+
+// Payload for configuring a card accumulation rule
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SetDisciplinaryRulePayload {
+    pub(crate) sport_type: SportType,
+    pub(crate) card_type: CardType,
+    pub(crate) threshold: u32,
+    pub(crate) suspension_matches: u32,
+}
+
+// Payload for submitting a Team's lineup for a Match
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SubmitLineupPayload {
+    pub(crate) match_id: u64,
+    pub(crate) team_id: u64,
+    pub(crate) starters: Vec<u64>,
+    pub(crate) substitutes: Vec<u64>,
+}
+
+// Payload for setting the fee rate of an official role in a sport
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SetFeeRatePayload {
+    pub(crate) sport_type: SportType,
+    pub(crate) role: OfficialRole,
+    pub(crate) match_fee: u64,
+    pub(crate) travel_allowance: u64,
+}
+
+// Payload for granting a Referee a certification
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct GrantCertificationPayload {
+    pub(crate) referee_id: u64,
+    pub(crate) sport_type: SportType,
+    pub(crate) grade: CertificationGrade,
+    pub(crate) issuing_body: String,
+    pub(crate) expiry_date: String,
+}
+
+// Payload for revoking a Referee's certification
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RevokeCertificationPayload {
+    pub(crate) referee_id: u64,
+    pub(crate) certification_id: u64,
+    pub(crate) reason: String,
+}
+
+// A Match to allocate officials to, with its stakes from 0 (friendly) to 10 (final)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AllocationMatch {
+    pub(crate) match_id: u64,
+    pub(crate) stakes: u8,
+    pub(crate) minimum_grade: Option<CertificationGrade>,
+}
+
+// Payload for automatically allocating Referees to a set of Matches
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AllocateRefereesPayload {
+    pub(crate) matches: Vec<AllocationMatch>,
+    pub(crate) assistants_per_match: u8,
+    pub(crate) preview: bool,
+}
+
+// Payload for rating a Referee's performance in a Match
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SubmitRefereeRatingPayload {
+    pub(crate) match_id: u64,
+    pub(crate) referee_id: u64,
+    pub(crate) score: u8,
+    pub(crate) comment: String,
+}
+
+// Query for a page of a Referee's officiating history, starting after the `cursor` match ID
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct OfficiatingHistoryQuery {
+    pub(crate) referee_id: u64,
+    pub(crate) role: Option<OfficialRole>,
+    pub(crate) sport_type: Option<SportType>,
+    pub(crate) cursor: Option<u64>,
+    pub(crate) limit: u32,
+}
+
+// Filters for the officials leaderboard
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RefereeLeaderboardQuery {
+    pub(crate) sport_type: Option<SportType>,
+    pub(crate) season: Option<String>,
+    pub(crate) min_ratings: u32,
+    pub(crate) limit: u32,
+}
+
 // Tournament Payload
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct CreateTournamentPayload {
     pub(crate) name: String,
     pub(crate) structure: TournamentStructure,
-    pub(crate) team_ids: Vec<String>,
+    pub(crate) team_ids: Vec<u64>,
+    pub(crate) sport_type: SportType,
+    pub(crate) standings_rules: Option<StandingsRules>,
+}
+
+// Update Tournament Payload
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UpdateTournamentPayload {
+    pub(crate) id: u64,
+    pub(crate) name: String,
+    pub(crate) structure: TournamentStructure,
+    pub(crate) team_ids: Vec<u64>,
     pub(crate) sport_type: SportType,
+    pub(crate) standings_rules: Option<StandingsRules>,
+}
+
+// Payload for generating the fixtures of a round-robin tournament
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct GenerateRoundRobinPayload {
+    pub(crate) tournament_id: u64,
+    pub(crate) double_round_robin: bool,
+    pub(crate) start_date: String,
+    pub(crate) end_date: String,
+    pub(crate) match_days: Vec<Weekday>,
+    pub(crate) kickoff_time: Option<String>,
+}
+
+// Payload for building the bracket of a knockout tournament
+// Teams are seeded in the order given, or in the tournament's order when no seeding is given
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct GenerateKnockoutPayload {
+    pub(crate) tournament_id: u64,
+    pub(crate) seeded_team_ids: Vec<u64>,
+    pub(crate) round_dates: Vec<String>,
+    pub(crate) kickoff_time: Option<String>,
+    pub(crate) third_place_match: bool,
+    pub(crate) two_legged: Option<TwoLeggedRules>,
+}
+
+// Payload for drawing the groups of a group-stage tournament and scheduling its group matches
+// The top qualifiers_per_group teams of every group go through to a knockout stage played on the knockout round dates
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct GenerateGroupStagePayload {
+    pub(crate) tournament_id: u64,
+    pub(crate) seeded_team_ids: Vec<u64>,
+    pub(crate) group_count: u32,
+    pub(crate) qualifiers_per_group: u32,
+    pub(crate) double_round_robin: bool,
+    pub(crate) start_date: String,
+    pub(crate) end_date: String,
+    pub(crate) match_days: Vec<Weekday>,
+    pub(crate) kickoff_time: Option<String>,
+    pub(crate) knockout_round_dates: Vec<String>,
+    pub(crate) third_place_match: bool,
+}
+
+// Payload for drawing the groups of a group-stage tournament from pots
+// Teams are placed into pots of group_count teams in seeding order, or in the tournament's order when no seeding is given
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct DrawGroupsPayload {
+    pub(crate) tournament_id: u64,
+    pub(crate) seeded_team_ids: Vec<u64>,
+    pub(crate) group_count: u32,
+    pub(crate) constraints: DrawConstraints,
+}
+
+// Payload for building the bracket of a double-elimination tournament
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct GenerateDoubleEliminationPayload {
+    pub(crate) tournament_id: u64,
+    pub(crate) seeded_team_ids: Vec<u64>,
+    pub(crate) start_date: String,
+    pub(crate) end_date: String,
+    pub(crate) match_days: Vec<Weekday>,
+    pub(crate) kickoff_time: Option<String>,
+    pub(crate) bracket_reset: bool,
+}
+
+// Payload for starting a Swiss-system tournament, with one date per round
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct GenerateSwissPayload {
+    pub(crate) tournament_id: u64,
+    pub(crate) seeded_team_ids: Vec<u64>,
+    pub(crate) round_dates: Vec<String>,
+    pub(crate) kickoff_time: Option<String>,
+}
+
+// Payload for opening or changing a tournament's entry period
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct OpenRegistrationPayload {
+    pub(crate) tournament_id: u64,
+    pub(crate) opens_on: String,
+    pub(crate) closes_on: String,
+    pub(crate) capacity: u32,
+    pub(crate) eligibility: EntryEligibility,
+}
+
+// Payload for a team applying to enter a tournament
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ApplyForTournamentPayload {
+    pub(crate) tournament_id: u64,
+    pub(crate) team_id: u64,
+}
+
+// Payload for approving or rejecting a tournament entry
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReviewEntryPayload {
+    pub(crate) entry_id: u64,
+    pub(crate) approve: bool,
+    pub(crate) reason: Option<String>,
+}
+
+// Payload for finding the matches of a league, season or tournament
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CompetitionMatchesPayload {
+    pub(crate) competition_id: u64,
+    pub(crate) round: Option<u32>,
+    pub(crate) stage: Option<MatchStage>,
+    pub(crate) leg: Option<u32>,
 }
 
 // League Payload
@@ -180,4 +1542,141 @@ pub struct CreateTournamentPayload {
 pub struct CreateLeaguePayload {
     pub(crate) name: String,
     pub(crate) sport_type: SportType,
+    pub(crate) standings_rules: Option<StandingsRules>,
+}
+
+// Update League Payload
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UpdateLeaguePayload {
+    pub(crate) id: u64,
+    pub(crate) name: String,
+    pub(crate) standings_rules: Option<StandingsRules>,
+}
+
+// Payload for adding a team to or removing it from a league
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LeagueTeamPayload {
+    pub(crate) league_id: u64,
+    pub(crate) team_id: u64,
+}
+
+// Payload for attaching a tournament to or detaching it from a league
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LeagueTournamentPayload {
+    pub(crate) league_id: u64,
+    pub(crate) tournament_id: u64,
+    pub(crate) season_id: Option<u64>,
+}
+
+// Payload for creating a season of a league
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CreateSeasonPayload {
+    pub(crate) league_id: u64,
+    pub(crate) name: String,
+    pub(crate) start_date: String,
+    pub(crate) end_date: String,
+    pub(crate) registration_window: SeasonPeriod,
+    pub(crate) transfer_windows: Vec<SeasonPeriod>,
+    pub(crate) exam_blackouts: Vec<SeasonPeriod>,
+}
+
+// Payload for changing the dates of a season
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UpdateSeasonPayload {
+    pub(crate) id: u64,
+    pub(crate) name: String,
+    pub(crate) start_date: String,
+    pub(crate) end_date: String,
+    pub(crate) registration_window: SeasonPeriod,
+    pub(crate) transfer_windows: Vec<SeasonPeriod>,
+    pub(crate) exam_blackouts: Vec<SeasonPeriod>,
+}
+
+// Payload for saving a new version of a league's rules, league-wide or for one of its seasons
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SetLeagueRulesPayload {
+    pub(crate) league_id: u64,
+    pub(crate) season_id: Option<u64>,
+    pub(crate) standings: StandingsRules,
+    pub(crate) roster: RosterLimits,
+    pub(crate) eligibility: EntryEligibility,
+    pub(crate) disciplinary_thresholds: Vec<DisciplinaryThreshold>,
+    pub(crate) result_confirmation_hours: Option<u32>,
+}
+
+// Payload for appointing a league official to a league, a sport, or both
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AppointOfficialPayload {
+    pub(crate) user_id: u64,
+    pub(crate) league_id: Option<u64>,
+    pub(crate) sport_type: Option<SportType>,
+}
+
+// Payload for creating an overall championship
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CreateChampionshipPayload {
+    pub(crate) name: String,
+    pub(crate) placing_points: Vec<u32>,
+}
+
+// Payload for renaming an overall championship or changing its placing points
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UpdateChampionshipPayload {
+    pub(crate) id: u64,
+    pub(crate) name: String,
+    pub(crate) placing_points: Vec<u32>,
+}
+
+// Payload for counting a league season towards an overall championship
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ChampionshipCompetitionPayload {
+    pub(crate) championship_id: u64,
+    pub(crate) season_id: u64,
+    pub(crate) placing_points: Option<Vec<u32>>,
+}
+
+// Payload for adding a division to a league
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CreateDivisionPayload {
+    pub(crate) league_id: u64,
+    pub(crate) name: String,
+    pub(crate) level: u32,
+    pub(crate) promotion_spots: u32,
+    pub(crate) playoff_spots: u32,
+    pub(crate) relegation_spots: u32,
+}
+
+// Payload for changing a division's name, level or movement spots
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UpdateDivisionPayload {
+    pub(crate) id: u64,
+    pub(crate) name: String,
+    pub(crate) level: u32,
+    pub(crate) promotion_spots: u32,
+    pub(crate) playoff_spots: u32,
+    pub(crate) relegation_spots: u32,
+}
+
+// Payload for placing a season's team in a division
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SeasonDivisionPayload {
+    pub(crate) season_id: u64,
+    pub(crate) division_id: u64,
+    pub(crate) team_id: u64,
+}
+
+// Payload for closing a season and seeding the next season's divisions
+// Each division with playoff spots needs the winner of its promotion playoff.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EndSeasonPayload {
+    pub(crate) season_id: u64,
+    pub(crate) next_season_id: u64,
+    pub(crate) playoff_winner_ids: Vec<u64>,
+}
+
+// Payload for registering a team for a season or withdrawing it
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SeasonTeamPayload {
+    pub(crate) season_id: u64,
+    pub(crate) team_id: u64,
 }